@@ -0,0 +1,268 @@
+//! The activity report of the owners of the bot: the players active per day and over the week, the games played
+//! per guild, and how both have changed since the week before. It is shown by `/debug activity` and, if
+//! `activity.channel_id` is set, posted there after every midnight UTC. Only the complete UTC days are counted,
+//! out of the matches the retention has kept.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::id::ChannelId;
+use serenity::model::Timestamp;
+
+use tracing::{error, info};
+
+use crate::config;
+use crate::i18n::{tr, DEFAULT_LOCALE};
+use crate::storage::{self, StorageResult};
+use crate::theme::Palette;
+
+/// The days of the week the report covers, compared to the same number of days before.
+const DAYS: i64 = 7;
+
+/// How many of the guilds with the most games are listed.
+const LISTED_GUILDS: usize = 5;
+
+/// How long the report is reused before it is read again; it only changes once a day, but the cache
+/// is kept short so a report read just before midnight is not shown for long.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How often the scheduled task checks whether a day has passed.
+const INTERVAL: Duration = Duration::from_secs(60 * 10);
+
+lazy_static! {
+    static ref CACHE: Mutex<Option<(Instant, Report)>> = Mutex::new(None);
+}
+
+/// `ready` is dispatched again on every reconnect, but only one task must post the report.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// The first day of the week, counted from the UNIX epoch; the week ends with yesterday.
+    pub first_day: i64,
+    /// The distinct players and the games of every day of the week, oldest first.
+    pub days: Vec<(u64, u64)>,
+    /// The distinct players of the week, then of the week before.
+    pub players: (u64, u64),
+    /// The games of the week, then of the week before.
+    pub games: (u64, u64),
+    /// The guilds with the most games in the week (`None` for direct messages), with their games then
+    /// and the week before.
+    pub guilds: Vec<(Option<u64>, u64, u64)>,
+}
+
+pub fn start(ctx: &Context) {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run(ctx.clone()));
+    }
+}
+
+async fn run(ctx: Context) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    // The day the bot starts on is only reported once it is over
+    let mut reported_day = today();
+
+    loop {
+        interval.tick().await;
+
+        if today() == reported_day {
+            continue;
+        }
+
+        reported_day = today();
+
+        let channel_id = match config::get().activity.channel_id {
+            Some(channel_id) => ChannelId(channel_id),
+            None => continue,
+        };
+
+        let report = match report() {
+            Ok(report) => report,
+            Err(err) => {
+                error!("The activity report could not have been read: {:?}", err);
+
+                continue;
+            },
+        };
+
+        let embed = embed(&report, &Palette::default(), DEFAULT_LOCALE);
+
+        match channel_id.send_message(&ctx.http, |msg| msg.set_embed(embed)).await {
+            Ok(_) => info!("The activity report has been posted"),
+            Err(err) => error!("The activity report could not have been posted: {:?}", err),
+        }
+    }
+}
+
+/// The report of the week ending with yesterday, from the cache unless it is outdated.
+pub fn report() -> StorageResult<Report> {
+    let first_day = today() - DAYS;
+
+    if let Some((read_at, report)) = CACHE.lock().unwrap().as_ref() {
+        if report.first_day == first_day && read_at.elapsed() < CACHE_TTL {
+            return Ok(report.clone());
+        }
+    }
+
+    let previous_first_day = first_day - DAYS;
+
+    let report = aggregate(
+        first_day,
+        storage::daily_players(first_day * 86400)?,
+        storage::daily_matches(previous_first_day * 86400)?,
+        (
+            storage::active_players(first_day * 86400, (first_day + DAYS) * 86400)?,
+            storage::active_players(previous_first_day * 86400, first_day * 86400)?,
+        ),
+    );
+
+    *CACHE.lock().unwrap() = Some((Instant::now(), report.clone()));
+
+    Ok(report)
+}
+
+fn today() -> i64 {
+    Timestamp::now().unix_timestamp() / 86400
+}
+
+/// Sums the daily counts into the report of the week starting with the day, leaving out the days after it
+/// (today, which is not over yet).
+fn aggregate(
+    first_day: i64,
+    daily_players: Vec<(i64, u64)>,
+    daily_matches: Vec<(Option<u64>, i64, u64)>,
+    players: (u64, u64),
+) -> Report {
+    let mut days = vec![(0, 0); DAYS as usize];
+    let mut guilds = HashMap::<Option<u64>, (u64, u64)>::new();
+
+    let index = |day: i64| usize::try_from(day - first_day).ok().filter(|index| *index < DAYS as usize);
+
+    for (day, count) in daily_players {
+        if let Some(index) = index(day) {
+            days[index].0 = count;
+        }
+    }
+
+    for (guild_id, day, count) in daily_matches {
+        if let Some(index) = index(day) {
+            days[index].1 += count;
+            guilds.entry(guild_id).or_default().0 += count;
+        } else if day < first_day {
+            guilds.entry(guild_id).or_default().1 += count;
+        }
+    }
+
+    let games = (days.iter().map(|(_, games)| games).sum(), guilds.values().map(|(_, previous)| previous).sum());
+
+    let mut guilds = guilds.into_iter()
+        .filter(|(_, (games, _))| *games > 0)
+        .map(|(guild_id, (games, previous))| (guild_id, games, previous))
+        .collect::<Vec<_>>();
+
+    guilds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    guilds.truncate(LISTED_GUILDS);
+
+    Report { first_day, days, players, games, guilds }
+}
+
+pub fn embed(report: &Report, palette: &Palette, locale: &str) -> CreateEmbed {
+    let date = |day: i64| format!("<t:{}:d>", day * 86400);
+
+    let days = report.days.iter()
+        .enumerate()
+        .map(|(index, (players, games))| {
+            tr!(locale, "activity.day", day = date(report.first_day + index as i64), players = players, games = games)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let trend = |(current, previous): (u64, u64)| {
+        tr!(locale, "activity.trend", count = current, change = change(locale, current, previous))
+    };
+
+    let guilds = if report.guilds.is_empty() {
+        tr!(locale, "activity.no_games")
+    } else {
+        report.guilds.iter()
+            .map(|(guild_id, games, previous)| {
+                let guild = match guild_id {
+                    Some(guild_id) => format!("`{}`", guild_id),
+                    None => tr!(locale, "activity.direct_messages"),
+                };
+
+                tr!(locale, "activity.guild", guild = guild, games = games, change = change(locale, *games, *previous))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.activity")))
+        .color(palette.success)
+        .description(tr!(
+            locale,
+            "activity.period",
+            from = date(report.first_day),
+            to = date(report.first_day + DAYS - 1),
+        ))
+        .field(tr!(locale, "activity.daily_players"), days, false)
+        .field(tr!(locale, "activity.weekly_players"), trend(report.players), true)
+        .field(tr!(locale, "activity.weekly_games"), trend(report.games), true)
+        .field(tr!(locale, "activity.guilds"), guilds, false)
+        .footer(|f| f.text(tr!(locale, "activity.footer")));
+
+    embed
+}
+
+/// How much the count has grown since the week before, as a signed percentage.
+fn change(locale: &str, current: u64, previous: u64) -> String {
+    match (current, previous) {
+        (0, 0) => "±0%".to_string(),
+        (_, 0) => tr!(locale, "activity.new"),
+        _ => format!("{:+.0}%", (current as f64 / previous as f64 - 1.0) * 100.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_week_is_compared_to_the_one_before() {
+        let daily_players = vec![(100, 4), (106, 2), (107, 9)];
+
+        let daily_matches = vec![
+            (Some(1), 93, 5),
+            (Some(1), 100, 3),
+            (Some(1), 106, 1),
+            (None, 101, 2),
+            (Some(2), 95, 4),
+            (Some(3), 107, 7),
+        ];
+
+        let report = aggregate(100, daily_players, daily_matches, (12, 8));
+
+        assert_eq!(report.days, vec![(4, 3), (0, 2), (0, 0), (0, 0), (0, 0), (0, 0), (2, 1)]);
+        assert_eq!(report.players, (12, 8));
+        assert_eq!(report.games, (6, 9));
+        assert_eq!(report.guilds, vec![(Some(1), 4, 5), (None, 2, 0)]);
+    }
+
+    #[test]
+    fn changes_are_signed_percentages() {
+        assert_eq!(change(DEFAULT_LOCALE, 15, 10), "+50%");
+        assert_eq!(change(DEFAULT_LOCALE, 5, 10), "-50%");
+        assert_eq!(change(DEFAULT_LOCALE, 10, 10), "+0%");
+        assert_eq!(change(DEFAULT_LOCALE, 0, 0), "±0%");
+    }
+}
@@ -0,0 +1,179 @@
+//! The write endpoints of the HTTP server, letting the operators of the bot moderate without being in the guild:
+//!
+//! - `POST /admin/sessions/<session>/end` ends a game in progress;
+//! - `POST /admin/guilds/<guild>/users/<user>/reset-stats` resets the statistics of a player, as `/reset-stats` does;
+//! - `PUT /admin/guilds/<guild>/flags/<flag>` sets a feature flag of the guild to the body, `on`, `off` or `default`.
+//!
+//! They all answer 204 once done, and are only served while `http.admin_token` is set, to the requests bearing it.
+
+use std::error::Error;
+use std::sync::RwLock;
+
+use hyper::{Body, Method, Request, Response, StatusCode};
+
+use lazy_static::lazy_static;
+
+use serenity::client::Context;
+use serenity::model::Timestamp;
+
+use tracing::{error, info};
+
+use crate::commands::{leaderboard, rps, top};
+use crate::config;
+use crate::flags;
+use crate::retention;
+use crate::storage;
+
+/// The prefix of the paths of the endpoints.
+pub const PREFIX: &str = "/admin/";
+
+lazy_static! {
+    /// The context of the client, needed to edit the message of a game being ended.
+    static ref CONTEXT: RwLock<Option<Context>> = RwLock::new(None);
+}
+
+pub fn ready(ctx: &Context) {
+    *CONTEXT.write().unwrap() = Some(ctx.clone());
+}
+
+/// The context of the client, once it has connected; shared with the other channels the operators act through.
+pub fn context() -> Option<Context> {
+    CONTEXT.read().unwrap().clone()
+}
+
+/// Reads the config again and applies what can be changed at runtime, returning the keys that have changed;
+/// shared by `/admin reload` and the control socket.
+pub fn reload_config() -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let changes = config::reload()?;
+
+    // The maintenance mode toggled with `/maintenance` is kept unless the config itself changes it
+    if changes.iter().any(|key| key == "maintenance") {
+        flags::set_maintenance(config::get().maintenance);
+    }
+
+    leaderboard::clear_cache();
+    top::clear_cache();
+
+    info!("The config has been reloaded with {} change(s)", changes.len());
+
+    Ok(changes)
+}
+
+/// Whether the endpoints are served.
+pub fn enabled() -> bool {
+    config::get().http.admin_token.is_some()
+}
+
+pub async fn handle(request: Request<Body>) -> hyper::http::Result<Response<Body>> {
+    if !authorized(&request) {
+        return status(StatusCode::UNAUTHORIZED);
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().trim_start_matches(PREFIX).to_string();
+    let segments = path.split('/').collect::<Vec<_>>();
+
+    match (method, segments.as_slice()) {
+        (Method::POST, ["sessions", session_id, "end"]) => match session_id.parse() {
+            Ok(session_id) => end_session(session_id).await,
+            Err(_) => status(StatusCode::BAD_REQUEST),
+        },
+        (Method::POST, ["guilds", guild_id, "users", user_id, "reset-stats"]) => {
+            match (guild_id.parse(), user_id.parse()) {
+                (Ok(guild_id), Ok(user_id)) => reset_stats(guild_id, user_id),
+                _ => status(StatusCode::BAD_REQUEST),
+            }
+        },
+        (Method::PUT, ["guilds", guild_id, "flags", flag]) => match guild_id.parse() {
+            Ok(guild_id) => {
+                let flag = flag.to_string();
+
+                match hyper::body::to_bytes(request.into_body()).await {
+                    Ok(body) => set_flag(guild_id, &flag, String::from_utf8_lossy(&body).trim()),
+                    Err(_) => status(StatusCode::BAD_REQUEST),
+                }
+            },
+            Err(_) => status(StatusCode::BAD_REQUEST),
+        },
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Checks the bearer token against the admin token.
+fn authorized(request: &Request<Body>) -> bool {
+    let token = match config::get().http.admin_token {
+        Some(token) => token,
+        None => return false,
+    };
+
+    let bearer = request.headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    bearer.is_some_and(|bearer| matches_secret(bearer, &token))
+}
+
+/// Compares the credential sent with a request to the secret in constant time, so the secret cannot be guessed
+/// from how long the comparison takes.
+pub fn matches_secret(given: &str, secret: &str) -> bool {
+    given.len() == secret.len()
+        && given.bytes().zip(secret.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+async fn end_session(session_id: u64) -> hyper::http::Result<Response<Body>> {
+    let ctx = match context() {
+        Some(ctx) => ctx,
+        None => return status(StatusCode::SERVICE_UNAVAILABLE),
+    };
+
+    if !rps::force_end(&ctx, session_id).await {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    info!(session = session_id, "The game has been ended through the admin API");
+
+    status(StatusCode::NO_CONTENT)
+}
+
+fn reset_stats(guild_id: u64, user_id: u64) -> hyper::http::Result<Response<Body>> {
+    // There is no user behind the API, which the reset records as 0
+    let result = storage::reset_stats(guild_id, user_id, 0, Timestamp::now().unix_timestamp(), retention::grace_seconds());
+
+    if let Err(err) = result {
+        error!("The statistics could not have been reset: {:?}", err);
+
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    info!("The statistics of {} in {} have been reset through the admin API", user_id, guild_id);
+
+    status(StatusCode::NO_CONTENT)
+}
+
+fn set_flag(guild_id: u64, flag: &str, state: &str) -> hyper::http::Result<Response<Body>> {
+    if !flags::declared().contains_key(flag) {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    let enabled = match state {
+        "on" => Some(true),
+        "off" => Some(false),
+        "default" => None,
+        _ => return status(StatusCode::BAD_REQUEST),
+    };
+
+    if let Err(err) = storage::save_guild_flag(guild_id, flag, enabled) {
+        error!("The feature flag could not have been saved: {:?}", err);
+
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    info!("The feature flag {} of {} has been set to {} through the admin API", flag, guild_id, state);
+
+    status(StatusCode::NO_CONTENT)
+}
+
+fn status(status: StatusCode) -> hyper::http::Result<Response<Body>> {
+    Response::builder().status(status).body(Body::empty())
+}
@@ -0,0 +1,212 @@
+//! Signs the requests to AWS (and S3-compatible) APIs with Signature Version 4, which is all the bot needs
+//! from them; the credentials come from the usual `AWS_*` environment variables.
+
+use std::env;
+
+use hmac::{Hmac, Mac};
+
+use serenity::model::Timestamp;
+
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    pub fn from_env() -> Result<Self, String> {
+        let variable = |name: &str| env::var(name).map_err(|_| format!("{} is not set", name));
+
+        Ok(Self {
+            access_key: variable("AWS_ACCESS_KEY_ID")?,
+            secret_key: variable("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// A request to sign; the path must already be percent-encoded, and the query string is always empty.
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub host: &'a str,
+    pub path: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+    /// The headers to sign besides the ones added here, with lowercase names.
+    pub headers: Vec<(&'static str, String)>,
+    pub payload: &'a [u8],
+}
+
+/// Returns every header to send the request with, including the `Authorization` one, except for `Host`.
+pub fn sign(credentials: &Credentials, request: Request<'_>) -> Vec<(&'static str, String)> {
+    // The timestamp in the basic ISO 8601 format, e.g. `20240102T030405Z`
+    let now = Timestamp::now().to_string();
+
+    sign_at(credentials, request, &format!("{}Z", now[..19].replace(['-', ':'], "")))
+}
+
+fn sign_at(credentials: &Credentials, request: Request<'_>, amz_date: &str) -> Vec<(&'static str, String)> {
+    let date = &amz_date[..8];
+
+    let payload_hash = hex(&Sha256::digest(request.payload));
+
+    let mut headers = request.headers;
+
+    headers.push(("host", request.host.to_string()));
+    headers.push(("x-amz-date", amz_date.to_string()));
+
+    // Only S3 requires the hash of the payload as a header of its own
+    if request.service == "s3" {
+        headers.push(("x-amz-content-sha256", payload_hash.clone()));
+    }
+
+    if let Some(session_token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", session_token.clone()));
+    }
+
+    headers.sort_by_key(|(name, _)| *name);
+
+    let canonical_headers = headers.iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect::<String>();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        request.method,
+        request.path,
+        canonical_headers,
+        signed_headers,
+        payload_hash,
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, request.region, request.service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes())),
+    );
+
+    let key = [date, request.region, request.service, "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{}", credentials.secret_key).into_bytes(), |key, part| hmac(&key, part));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key,
+        scope,
+        signed_headers,
+        hex(&hmac(&key, &string_to_sign)),
+    );
+
+    headers.retain(|(name, _)| *name != "host");
+    headers.push(("authorization", authorization));
+
+    headers
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+
+    mac.update(data.as_bytes());
+
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The requests and the signatures of the test suite AWS has published for Signature Version 4
+    const DATE: &str = "20150830T123600Z";
+
+    fn credentials(session_token: Option<&str>) -> Credentials {
+        Credentials {
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: session_token.map(str::to_string),
+        }
+    }
+
+    fn request<'a>(method: &'a str, headers: Vec<(&'static str, String)>, payload: &'a [u8]) -> Request<'a> {
+        Request {
+            method,
+            host: "example.amazonaws.com",
+            path: "/",
+            region: "us-east-1",
+            service: "service",
+            headers,
+            payload,
+        }
+    }
+
+    fn header(headers: &[(&'static str, String)], name: &str) -> Option<String> {
+        headers.iter().find(|(header, _)| *header == name).map(|(_, value)| value.clone())
+    }
+
+    #[test]
+    fn get_vanilla() {
+        let headers = sign_at(&credentials(None), request("GET", Vec::new(), b""), DATE);
+
+        assert_eq!(
+            header(&headers, "authorization").unwrap(),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, \
+            Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31",
+        );
+        assert_eq!(header(&headers, "x-amz-date").unwrap(), DATE);
+        assert_eq!(header(&headers, "host"), None);
+    }
+
+    #[test]
+    fn post_x_www_form_urlencoded() {
+        let content_type = vec![("content-type", "application/x-www-form-urlencoded".to_string())];
+        let headers = sign_at(&credentials(None), request("POST", content_type, b"Param1=value1"), DATE);
+
+        assert_eq!(
+            header(&headers, "authorization").unwrap(),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=content-type;host;x-amz-date, \
+            Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a",
+        );
+    }
+
+    #[test]
+    fn post_sts_header_before() {
+        let token = "AQoDYXdzEPT//////////wEXAMPLEtc764bNrC9SAPBSM22wDOk4x4HIZ8j4FZTwdQWLWsKWHGBuFqwAeMicRXmxfpSPfIeoIYRqTflf\
+            KD8YUuwthAx7mSEI/qkPpKPi/kMcGdQrmGdeehM4IC1NtBmUpp2wUE8phUZampKsburEDy0KPkyQDYwT7WZ0wq5VSXDvp75YU9HFvlRd8Tx6q6fE8Y\
+            QcHNVXAkiY9q6d+xo0rKwT38xVqr7ZD0u0iPPkUL64lIZbqBAz+scqKmlzm8FDrypNC9Yjc8fPOLn9FX9KSYvKTr4rvx3iSIlTJabIQwj2ICCR/oLxBA==";
+
+        let headers = sign_at(&credentials(Some(token)), request("POST", Vec::new(), b""), DATE);
+
+        assert_eq!(header(&headers, "x-amz-security-token").unwrap(), token);
+        assert_eq!(
+            header(&headers, "authorization").unwrap(),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date;x-amz-security-token, \
+            Signature=85d96828115b5dc0cfc3bd16ad9e210dd772bbebba041836c64533a82be05ead",
+        );
+    }
+
+    #[test]
+    fn s3_requests_sign_the_hash_of_the_payload() {
+        let request = Request { service: "s3", ..request("PUT", Vec::new(), b"") };
+        let headers = sign_at(&credentials(None), request, DATE);
+
+        // The hash of an empty payload
+        assert_eq!(
+            header(&headers, "x-amz-content-sha256").unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        );
+        assert!(header(&headers, "authorization").unwrap().contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date,"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Makes consistent snapshots of the database, on a schedule if `backups.interval_hours` is set or on demand
+//! with `/backup` and the `backup` subcommand, and uploads them to an S3-compatible bucket if one is configured.
+//! They are restored with the `restore` subcommand while the bot is stopped.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serenity::model::Timestamp;
+
+use tracing::{error, info, warn};
+
+use crate::aws;
+use crate::config::{self, S3Config};
+use crate::storage::{self, StorageResult};
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+pub struct Backup {
+    pub path: PathBuf,
+    /// The schema version of the database, which is also part of the file name.
+    pub version: usize,
+    /// Whether the backup has been uploaded, which is never the case without a bucket.
+    pub uploaded: bool,
+}
+
+impl Backup {
+    pub fn name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+pub fn start() {
+    let hours = match config::get().backups.interval_hours {
+        Some(hours) if hours > 0 => hours,
+        _ => return,
+    };
+
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run(Duration::from_secs(hours * 60 * 60)));
+    }
+}
+
+async fn run(interval: Duration) {
+    // The first backup waits for a whole interval, so restarting the bot does not make one every time
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+
+    loop {
+        interval.tick().await;
+
+        match create().await {
+            Ok(backup) => info!("The database has been backed up to {}", backup.path.display()),
+            Err(err) => error!("The database could not have been backed up: {:?}", err),
+        }
+    }
+}
+
+/// Backs up the database, uploads the backup if a bucket is configured, then drops the oldest local backups.
+/// A failed upload is only logged, as the local backup is still there.
+pub async fn create() -> StorageResult<Backup> {
+    let config = config::get().backups;
+
+    std::fs::create_dir_all(&config.directory)?;
+
+    let version = storage::schema_version()?;
+    let name = format!("rps-{}-v{}.sqlite3", Timestamp::now().unix_timestamp(), version);
+    let path = Path::new(&config.directory).join(name);
+
+    // Vacuuming into the file can take a while on a large database
+    let target = path.to_string_lossy().to_string();
+
+    tokio::task::spawn_blocking(move || storage::backup_into(&target)).await??;
+
+    let mut backup = Backup { path, version, uploaded: false };
+
+    if let Some(s3) = &config.s3 {
+        match upload(s3, &backup).await {
+            Ok(_) => backup.uploaded = true,
+            Err(err) => error!("The backup could not have been uploaded: {:?}", err),
+        }
+    }
+
+    if let Some(keep) = config.keep {
+        if let Err(err) = remove_old(&config.directory, keep) {
+            warn!("The old backups could not have been removed: {:?}", err);
+        }
+    }
+
+    Ok(backup)
+}
+
+async fn upload(s3: &S3Config, backup: &Backup) -> StorageResult<()> {
+    let credentials = aws::Credentials::from_env()?;
+
+    let endpoint = reqwest::Url::parse(&s3.endpoint)?;
+    let host = match (endpoint.host_str(), endpoint.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(format!("The S3 endpoint {} has no host", s3.endpoint).into()),
+    };
+
+    // The names of the backups and the usual prefixes only consist of characters that need no encoding
+    let path = format!("/{}/{}{}", s3.bucket, s3.prefix, backup.name());
+    let body = tokio::fs::read(&backup.path).await?;
+
+    let headers = aws::sign(&credentials, aws::Request {
+        method: "PUT",
+        host: &host,
+        path: &path,
+        region: &s3.region,
+        service: "s3",
+        headers: vec![("content-type", "application/vnd.sqlite3".to_string())],
+        payload: &body,
+    });
+
+    let mut request = reqwest::Client::new()
+        .put(format!("{}://{}{}", endpoint.scheme(), host, path))
+        .body(body);
+
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    request.send().await?.error_for_status()?;
+
+    Ok(())
+}
+
+/// Removes the oldest backups in the directory beyond the given number; the timestamps in the names sort them.
+fn remove_old(directory: &str, keep: usize) -> StorageResult<()> {
+    let mut backups = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            name.starts_with("rps-") && name.ends_with(".sqlite3")
+        })
+        .collect::<Vec<_>>();
+
+    backups.sort();
+
+    for path in backups.iter().rev().skip(keep) {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the database with the backup once it has been checked, then migrates it to the current schema.
+/// The bot must not be running, as its connections would keep using the replaced file.
+pub fn restore(path: &str) -> StorageResult<usize> {
+    let version = storage::check_backup(path)?;
+    let database_path = config::get().database_path;
+
+    // A journal left behind by the replaced database would be replayed onto the backup
+    for suffix in ["-journal", "-wal", "-shm"] {
+        let journal = format!("{}{}", database_path, suffix);
+
+        if Path::new(&journal).exists() {
+            std::fs::remove_file(journal)?;
+        }
+    }
+
+    std::fs::copy(path, &database_path)?;
+    storage::migrate()?;
+
+    Ok(version)
+}
@@ -0,0 +1,111 @@
+//! Posts the number of guilds and shards to the bot lists an API token is configured for, so their listings stay
+//! up to date. Each list is posted to independently, and one that keeps failing is retried less and less often.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serde_json::json;
+
+use serenity::model::id::UserId;
+
+use tracing::{info, warn};
+
+use crate::config;
+use crate::guilds;
+
+/// How often the statistics are posted; the lists only show the number of guilds, so there is no need to be precise.
+const INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// How long to wait before posting again after a first failure, doubling with every further failure in a row.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// The longest wait between two posts to a list that keeps failing, e.g. because its token has been revoked.
+const MAX_BACKOFF: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// `ready` is dispatched again on every reconnect, but only one poster must run per list.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug)]
+enum BotList {
+    TopGg,
+    DiscordBotsGg,
+}
+
+impl BotList {
+    fn name(self) -> &'static str {
+        match self {
+            Self::TopGg => "top.gg",
+            Self::DiscordBotsGg => "discord.bots.gg",
+        }
+    }
+
+    fn url(self, bot_id: UserId) -> String {
+        match self {
+            Self::TopGg => format!("https://top.gg/api/bots/{}/stats", bot_id),
+            Self::DiscordBotsGg => format!("https://discord.bots.gg/api/v1/bots/{}/stats", bot_id),
+        }
+    }
+
+    fn body(self, guild_count: usize, shard_count: u64) -> String {
+        let body = match self {
+            Self::TopGg => json!({ "server_count": guild_count, "shard_count": shard_count }),
+            Self::DiscordBotsGg => json!({ "guildCount": guild_count, "shardCount": shard_count }),
+        };
+
+        body.to_string()
+    }
+}
+
+pub fn start(bot_id: UserId) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let config = config::get().bot_lists;
+
+    for (list, token) in [(BotList::TopGg, config.top_gg), (BotList::DiscordBotsGg, config.discord_bots_gg)] {
+        if let Some(token) = token {
+            tokio::spawn(run(list, token, bot_id));
+        }
+    }
+}
+
+async fn run(list: BotList, token: String, bot_id: UserId) {
+    let client = reqwest::Client::new();
+    let mut failures = 0;
+
+    loop {
+        let (guild_count, shard_count) = (guilds::count(), guilds::shard_count());
+
+        let response = client.post(list.url(bot_id))
+            .header("Authorization", &token)
+            .header("Content-Type", "application/json")
+            .body(list.body(guild_count, shard_count))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        let delay = match response {
+            Ok(_) => {
+                if failures > 0 {
+                    info!("The statistics have been posted to {} again", list.name());
+                }
+
+                failures = 0;
+
+                INTERVAL
+            },
+            Err(err) => {
+                failures += 1;
+
+                let delay = RETRY_DELAY.saturating_mul(2u32.saturating_pow(failures - 1)).min(MAX_BACKOFF);
+
+                warn!(failures, "The statistics could not have been posted to {}: {:?}", list.name(), err);
+
+                delay
+            },
+        };
+
+        tokio::time::sleep(delay).await;
+    }
+}
@@ -0,0 +1,198 @@
+//! The champions of the guilds, i.e. the players at the top of their all-time leaderboards. Whenever a recorded match
+//! moves someone else to the first place, the player who has lost it is sent a direct message if they have opted in
+//! with `/notifications`, and the new champion is announced in the channel the guild has chosen with `/settings champions`.
+//!
+//! The champion of every guild is remembered once read, so a finished game costs a single read of the leaderboard,
+//! made off the runtime like the other reads that aggregate all the matches of a guild.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info, warn};
+
+use crate::guilds;
+use crate::i18n::{tr, DEFAULT_LOCALE};
+use crate::storage;
+use crate::theme::{self, Palette};
+
+lazy_static! {
+    /// The champions of the guilds as last read, `None` for the guilds nobody has won a match in yet.
+    static ref CHAMPIONS: Mutex<HashMap<u64, Option<u64>>> = Mutex::new(HashMap::new());
+}
+
+/// The first place of the leaderboard of a guild having changed hands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dethronement {
+    pub guild_id: u64,
+    pub champion_id: u64,
+    pub dethroned_id: u64,
+}
+
+/// Reads the champion of the guild before a match is recorded there, unless it is remembered already,
+/// so that [`refresh`] has something to compare the new one with.
+pub async fn prime(guild_id: u64) {
+    if CHAMPIONS.lock().unwrap().contains_key(&guild_id) {
+        return;
+    }
+
+    if let Some(champion) = read(guild_id).await {
+        CHAMPIONS.lock().unwrap().entry(guild_id).or_insert(champion);
+    }
+}
+
+/// Reads the champion of the guild once a match has been recorded there and announces them if they are new.
+pub async fn refresh(ctx: Context, guild_id: u64) {
+    let champion = match read(guild_id).await {
+        Some(champion) => champion,
+        None => return,
+    };
+
+    let previous = CHAMPIONS.lock().unwrap().insert(guild_id, champion);
+
+    if let Some(dethronement) = dethronement(guild_id, previous.flatten(), champion) {
+        announce(&ctx, dethronement).await;
+    }
+}
+
+/// The player at the top of the all-time leaderboard of the guild, or `None` inside if nobody has won a match there yet;
+/// `None` outside if it could not have been read.
+async fn read(guild_id: u64) -> Option<Option<u64>> {
+    match tokio::task::spawn_blocking(move || storage::leaderboard(Some(guild_id), None, 1)).await {
+        Ok(Ok(standings)) => Some(standings.first().filter(|standing| standing.wins > 0).map(|standing| standing.user_id)),
+        Ok(Err(err)) => {
+            error!(guild = guild_id, "The champion of the guild could not have been read: {:?}", err);
+
+            None
+        },
+        Err(err) => {
+            error!(guild = guild_id, "The read of the champion of the guild has failed: {:?}", err);
+
+            None
+        },
+    }
+}
+
+/// Compares the previous champion of the guild with the current one; a guild without a champion before has nobody to dethrone.
+fn dethronement(guild_id: u64, previous: Option<u64>, champion: Option<u64>) -> Option<Dethronement> {
+    let dethroned_id = previous?;
+    let champion_id = champion.filter(|champion_id| *champion_id != dethroned_id)?;
+
+    Some(Dethronement { guild_id, champion_id, dethroned_id })
+}
+
+async fn announce(ctx: &Context, dethronement: Dethronement) {
+    info!(guild = dethronement.guild_id, champion = dethronement.champion_id, "The guild has a new champion");
+
+    notify_dethroned(ctx, &dethronement).await;
+
+    let channel_id = match theme::settings(Some(GuildId(dethronement.guild_id))).champion_channel_id {
+        Some(channel_id) => ChannelId(channel_id),
+        None => return,
+    };
+
+    let palette = Palette::for_guild(Some(GuildId(dethronement.guild_id)));
+    let locale = guilds::locale(dethronement.guild_id).unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+    let locale = locale.as_str();
+
+    if let Err(err) = channel_id.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.champion")))
+                .color(palette.success)
+                .description(tr!(
+                    locale,
+                    "champions.announcement",
+                    champion = UserId(dethronement.champion_id).mention(),
+                    dethroned = UserId(dethronement.dethroned_id).mention(),
+                ))
+        })
+    }).await {
+        // Most likely the channel has been deleted or the bot may no longer post there
+        warn!(guild = dethronement.guild_id, "The new champion could not have been announced: {:?}", err);
+    }
+}
+
+/// Sends the dethroned player a direct message if they have opted in with `/notifications`.
+async fn notify_dethroned(ctx: &Context, dethronement: &Dethronement) {
+    let preferences = match storage::user_preferences(dethronement.dethroned_id) {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            return;
+        },
+    };
+
+    if !preferences.dm_dethronements {
+        return;
+    }
+
+    let palette = Palette::for_guild(Some(GuildId(dethronement.guild_id)));
+
+    let locale = preferences.locale
+        .or_else(|| guilds::locale(dethronement.guild_id))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+    let locale = locale.as_str();
+
+    let guild = guild_name(ctx, GuildId(dethronement.guild_id)).await;
+
+    let channel = match UserId(dethronement.dethroned_id).create_dm_channel(&ctx.http).await {
+        Ok(channel) => channel,
+        Err(err) => {
+            info!(user = dethronement.dethroned_id, "The direct message channel could not have been opened: {:?}", err);
+
+            return;
+        },
+    };
+
+    if let Err(err) = channel.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.champion")))
+                .color(palette.confirmation)
+                .description(tr!(
+                    locale,
+                    "champions.dethroned",
+                    champion = UserId(dethronement.champion_id).mention(),
+                    guild = guild,
+                ))
+        })
+    }).await {
+        // Most likely the player does not accept direct messages from the server
+        info!(user = dethronement.dethroned_id, "The dethronement could not have been sent as a direct message: {:?}", err);
+    }
+}
+
+/// The name of the guild, or its ID if it cannot be found.
+#[cfg(feature = "cache")]
+async fn guild_name(ctx: &Context, guild_id: GuildId) -> String {
+    guild_id.name(&ctx.cache).unwrap_or_else(|| guild_id.to_string())
+}
+
+/// The name of the guild, or its ID if it cannot be fetched.
+#[cfg(not(feature = "cache"))]
+async fn guild_name(ctx: &Context, guild_id: GuildId) -> String {
+    guild_id.to_partial_guild(&ctx.http).await.map_or_else(|_| guild_id.to_string(), |guild| guild.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_new_champion_dethrones_the_previous_one() {
+        assert_eq!(dethronement(1, Some(2), Some(2)), None);
+        assert_eq!(dethronement(1, None, Some(3)), None);
+        assert_eq!(dethronement(1, Some(2), None), None);
+        assert_eq!(
+            dethronement(1, Some(2), Some(3)),
+            Some(Dethronement { guild_id: 1, champion_id: 3, dethroned_id: 2 }),
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! The subcommands of the binary, so the operational tasks can be run without starting the bot.
+
+use std::error::Error;
+use std::io::{self, Write};
+
+use clap::{Parser, Subcommand};
+
+use serenity::http::Http;
+
+use tracing::{error, info};
+
+use crate::backups;
+use crate::commands::{self, rps};
+use crate::config;
+use crate::loadtest;
+use crate::storage;
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Runs the bot if omitted.
+    #[command(subcommand)]
+    pub task: Option<Task>,
+}
+
+#[derive(Subcommand)]
+pub enum Task {
+    /// Connects to Discord and serves the games.
+    Run,
+    /// Registers the application commands, as the bot does whenever it connects.
+    RegisterCommands,
+    /// Removes the application commands, e.g. before retiring the application or leaving the guild scope.
+    ClearCommands,
+    /// Applies the pending migrations to the database.
+    Migrate,
+    /// Writes an encrypted copy of the plaintext database, keyed with `database_key`, to replace it with.
+    #[cfg(feature = "sqlcipher")]
+    EncryptDatabase {
+        /// Where the encrypted copy is written; must not exist yet.
+        output: String,
+    },
+    /// Backs up the database, uploading the backup if a bucket is configured.
+    Backup,
+    /// Replaces the database with a backup; the bot must be stopped.
+    Restore {
+        /// The backup file to restore.
+        path: String,
+    },
+    /// Prints the wins and losses of every player as CSV.
+    ExportStats {
+        /// Only counts the matches played in the guild, instead of the global ones.
+        #[arg(long)]
+        guild: Option<u64>,
+        /// Only counts the matches finished since the Unix timestamp, leaving out the totals of the pruned ones.
+        #[arg(long)]
+        since: Option<i64>,
+    },
+    /// Plays the recorded rounds of the latest matches again through the game and checks they end as recorded,
+    /// e.g. after changing the rules or the state machine of the game.
+    VerifyReplays {
+        /// How many of the latest matches are played again.
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+    },
+    /// Plays synthetic games at once through the sessions and the game into a scratch database, and reports
+    /// the latencies, the contention of the registry of the sessions and the memory used.
+    LoadTest {
+        /// Where the scratch database is created; must not exist yet, so the real one is never written to.
+        database: String,
+        /// How many games are played in total.
+        #[arg(long, default_value_t = 10000)]
+        games: usize,
+        /// How many games are in progress at most at once.
+        #[arg(long, default_value_t = 1000)]
+        concurrency: usize,
+        /// How long the players think before every press, in milliseconds.
+        #[arg(long, default_value_t = 50)]
+        pause: u64,
+        /// The seed the moves are drawn from, so that the runs play the same games.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+}
+
+pub async fn register_commands(token: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let http = http(token).await?;
+    let count = commands::sync(&http).await?;
+
+    info!("{} application commands have been registered!", count);
+
+    Ok(())
+}
+
+pub async fn clear_commands(token: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let http = http(token).await?;
+
+    commands::clear(&http).await?;
+
+    info!("The application commands have been removed!");
+
+    Ok(())
+}
+
+pub fn migrate() -> Result<(), Box<dyn Error + Send + Sync>> {
+    storage::migrate()?;
+
+    info!("The database is up to date!");
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlcipher")]
+pub fn encrypt_database(output: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if std::path::Path::new(output).exists() {
+        return Err(format!("{} already exists", output).into());
+    }
+
+    storage::encrypt_into(output)?;
+
+    info!("The encrypted copy of the database has been written to {}; point database_path at it once checked", output);
+
+    Ok(())
+}
+
+pub async fn backup() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let backup = backups::create().await?;
+
+    info!("The database has been backed up to {} (schema version {})", backup.path.display(), backup.version);
+
+    Ok(())
+}
+
+pub fn restore(path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let version = backups::restore(path)?;
+
+    info!("The backup with schema version {} has been restored and migrated!", version);
+
+    Ok(())
+}
+
+pub fn export_stats(guild_id: Option<u64>, since: Option<i64>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // SQLite takes the limit as a signed integer
+    let standings = storage::leaderboard(guild_id, since, i64::MAX as usize)?;
+
+    let mut stdout = io::stdout().lock();
+
+    writeln!(stdout, "user_id,wins,losses")?;
+
+    for standing in standings {
+        writeln!(stdout, "{},{},{}", standing.user_id, standing.wins, standing.losses)?;
+    }
+
+    Ok(())
+}
+
+pub fn verify_replays(limit: usize) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let matches = storage::recent_matches(None, None, limit)?;
+    let mut mismatches = 0;
+
+    for game in &matches {
+        if let Err(mismatch) = rps::verify_replay(game, &storage::match_rounds(game.id)?) {
+            error!(game = game.id, "The match has been played again differently: {}", mismatch);
+
+            mismatches += 1;
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(format!("{} of {} matches have been played again differently", mismatches, matches.len()).into());
+    }
+
+    info!("{} matches have been played again as recorded!", matches.len());
+
+    Ok(())
+}
+
+pub async fn load_test(database: &str, options: loadtest::Options) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if std::path::Path::new(database).exists() {
+        return Err(format!("{} already exists", database).into());
+    }
+
+    // Nothing has opened the database yet, so the synthetic games never reach the configured one
+    config::set_database_path(database);
+
+    loadtest::run(options).await
+}
+
+/// A REST client for the application of the token, as the commands are registered without connecting to the gateway.
+async fn http(token: &str) -> serenity::Result<Http> {
+    let http = Http::new(token);
+    let application = http.get_current_application_info().await?;
+
+    http.set_application_id(application.id.0);
+
+    Ok(http)
+}
@@ -0,0 +1,75 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::permissions::Permissions;
+
+use tracing::{error, info};
+
+use crate::admin;
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::config;
+use crate::i18n::{self, tr};
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.admin")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .create_option(|sub| {
+            sub
+                .localized("commands.admin.reload")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    if !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "admin.owner_only")).await;
+
+        return;
+    }
+
+    // `reload` is the only subcommand for now
+    let (color, description) = match admin::reload_config() {
+        Ok(changes) if changes.is_empty() => (palette.success, tr!(&locale, "admin.unchanged")),
+        Ok(changes) => {
+            info!("The config has been reloaded by {}", cmd.user.tag());
+
+            let changes = changes.iter()
+                .map(|key| if config::needs_restart(key) {
+                    format!("• `{}` ({})", key, tr!(&locale, "admin.needs_restart"))
+                } else {
+                    format!("• `{}`", key)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            (palette.success, tr!(&locale, "admin.reloaded", changes = changes))
+        },
+        Err(err) => {
+            error!("The config could not have been reloaded: {:?}", err);
+
+            (palette.failure, tr!(&locale, "admin.reload_failed", error = err))
+        },
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.admin")))
+                            .color(color)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
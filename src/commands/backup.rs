@@ -0,0 +1,65 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::permissions::Permissions;
+
+use tracing::{error, info};
+
+use crate::backups;
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::config;
+use crate::i18n::{self, tr};
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.backup")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    if !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "backup.owner_only")).await;
+
+        return;
+    }
+
+    // Backing up and uploading a large database takes longer than an interaction may wait for its response
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            .interaction_response_data(|msg| msg.ephemeral(true))
+    }).await;
+
+    let (color, description) = match backups::create().await {
+        Ok(backup) => {
+            info!("The database has been backed up to {} by {}", backup.path.display(), cmd.user.tag());
+
+            let key = match (backup.uploaded, config::get().backups.s3.is_some()) {
+                (true, _) => "backup.uploaded",
+                (false, true) => "backup.upload_failed",
+                (false, false) => "backup.created",
+            };
+
+            (palette.success, tr!(&locale, key, name = backup.name(), version = backup.version))
+        },
+        Err(err) => {
+            error!("The database could not have been backed up: {:?}", err);
+
+            (palette.failure, tr!(&locale, "backup.failed"))
+        },
+    };
+
+    let _ = cmd.edit_original_interaction_response(&ctx.http, |response| {
+        response.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(&locale, "embeds.backup")))
+                .color(color)
+                .description(description)
+        })
+    }).await;
+}
@@ -0,0 +1,84 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::storage;
+use crate::theme::Palette;
+
+pub fn register_block(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.block")
+        .create_option(|option| {
+            option
+                .localized("commands.block.user")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+}
+
+pub fn register_unblock(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.unblock")
+        .create_option(|option| {
+            option
+                .localized("commands.unblock.user")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+}
+
+/// Handles both `/block` and `/unblock`, which only differ by the state they save.
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let user = match cmd.data.options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::User(user, _)) => user,
+        _ => return,
+    };
+
+    if user.id == cmd.user.id {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "blocks.self")).await;
+
+        return;
+    }
+
+    let blocked = cmd.data.name == "block";
+
+    if let Err(err) = storage::save_block(cmd.user.id.0, user.id.0, blocked) {
+        error!("The block list could not have been saved: {:?}", err);
+
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.save_failed")).await;
+
+        return;
+    }
+
+    let description = if blocked {
+        tr!(&locale, "blocks.blocked", user = user.mention())
+    } else {
+        tr!(&locale, "blocks.unblocked", user = user.mention())
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.blocks")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
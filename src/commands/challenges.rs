@@ -0,0 +1,94 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::prelude::Mentionable;
+
+use crate::commands::rps;
+use crate::commands::Localized;
+use crate::i18n::{self, tr};
+use crate::sessions;
+use crate::theme::{self, Palette};
+
+/// A message can hold up to five action rows, one per invitation.
+const MAX_INVITATIONS: usize = 5;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.localized("commands.challenges")
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let mut invitations = sessions::invitations_for(cmd.user.id);
+
+    invitations.sort_by_key(|session| session.id);
+    invitations.truncate(MAX_INVITATIONS);
+
+    let description = if invitations.is_empty() {
+        tr!(locale, "challenges.empty")
+    } else {
+        invitations.iter()
+            .map(|session| {
+                let expiry = session.started_at.unix_timestamp() + rps::timeout(&theme::settings(session.guild_id)).as_secs() as i64;
+
+                tr!(
+                    locale,
+                    "challenges.entry",
+                    user = session.starter.mention(),
+                    channel = session.channel_id.mention(),
+                    expiry = format!("<t:{}:R>", expiry),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(locale, "embeds.challenges")))
+                            .color(palette.confirmation)
+                            .description(description)
+                    })
+                    .components(|comp| {
+                        for session in &invitations {
+                            comp.create_action_row(|row| {
+                                if let Some(message_id) = session.message_id {
+                                    row.create_button(|button| {
+                                        button
+                                            .style(ButtonStyle::Link)
+                                            .label(&session.starter.name)
+                                            .url(message_id.link(session.channel_id, session.guild_id))
+                                    });
+                                }
+
+                                row
+                                    .create_button(|button| {
+                                        button
+                                            .style(ButtonStyle::Success)
+                                            .label(tr!(locale, "challenges.accept"))
+                                            .custom_id(rps::custom_id(session, "accept"))
+                                    })
+                                    .create_button(|button| {
+                                        button
+                                            .style(ButtonStyle::Danger)
+                                            .label(tr!(locale, "challenges.decline"))
+                                            .custom_id(rps::custom_id(session, "decline"))
+                                    })
+                            });
+                        }
+
+                        comp
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,203 @@
+use prometheus::Histogram;
+
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::activity;
+use crate::commands::rps::{AFK_WARNING, TIMEOUT};
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::custom_ids::{self, CustomId};
+use crate::i18n::{self, tr};
+use crate::metrics::{self, RPS};
+use crate::sessions::{self, Session, State};
+use crate::theme::Palette;
+
+/// The prefix of the custom IDs of the buttons turning the pages.
+pub const DEBUG: &str = "debug";
+
+const SESSIONS_PER_PAGE: usize = 5;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.debug")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .create_option(|sub| {
+            sub
+                .localized("commands.debug.sessions")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.debug.timings")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.debug.activity")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    if !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "debug.owner_only")).await;
+
+        return;
+    }
+
+    let (embed, components) = match cmd.data.options[0].name.as_str() {
+        "timings" => (timings(&palette, &locale), CreateComponents::default()),
+        "activity" => match activity::report() {
+            Ok(report) => (activity::embed(&report, &palette, &locale), CreateComponents::default()),
+            Err(err) => {
+                error!("The activity report could not have been read: {:?}", err);
+
+                reply_failure(ctx, cmd, &palette, &tr!(&locale, "activity.load_failed")).await;
+
+                return;
+            },
+        },
+        _ => page(&palette, &locale, 0),
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| msg.ephemeral(true).set_embed(embed).set_components(components))
+    }).await;
+}
+
+/// Turns the pages, reading the sessions again so the listing stays current; only the owner who has run
+/// the command can see the ephemeral message, so the presses need no further check.
+pub async fn handle_component(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let palette = Palette::for_guild(interaction.guild_id);
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+
+    let (embed, components) = page(&palette, &locale, custom_id.turn as usize);
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|msg| msg.set_embed(embed).set_components(components))
+    }).await;
+}
+
+/// Builds the page of the listing, the last one if the sessions have become fewer since it was asked for.
+fn page(palette: &Palette, locale: &str, page: usize) -> (CreateEmbed, CreateComponents) {
+    let sessions = sessions::all();
+    let pages = sessions.len().div_ceil(SESSIONS_PER_PAGE).max(1);
+    let page = page.min(pages - 1);
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.debug")))
+        .color(palette.success);
+
+    if sessions.is_empty() {
+        embed.description(tr!(locale, "debug.no_sessions"));
+    } else {
+        for session in sessions.iter().skip(page * SESSIONS_PER_PAGE).take(SESSIONS_PER_PAGE) {
+            embed.field(format!("#{}", session.id), describe(session, locale), false);
+        }
+
+        embed.footer(|f| f.text(tr!(locale, "debug.page", page = page + 1, pages = pages, count = sessions.len())));
+    }
+
+    let mut components = CreateComponents::default();
+
+    if pages > 1 {
+        components.create_action_row(|row| {
+            row
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label("◀")
+                        .disabled(page == 0)
+                        .custom_id(custom_ids::encode(DEBUG, 0, page.saturating_sub(1) as u32, "page"))
+                })
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label("▶")
+                        .disabled(page + 1 == pages)
+                        .custom_id(custom_ids::encode(DEBUG, 0, (page + 1) as u32, "page"))
+                })
+        });
+    }
+
+    (embed, components)
+}
+
+/// The median and 95th percentile of how long the games and their turns have taken since the bot has started,
+/// next to the default timeouts they are meant to tune.
+fn timings(palette: &Palette, locale: &str) -> CreateEmbed {
+    let summary = |histogram: &Histogram| match (metrics::quantile(histogram, 0.5), metrics::quantile(histogram, 0.95)) {
+        (Some(p50), Some(p95)) => tr!(
+            locale,
+            "debug.quantiles",
+            p50 = format!("{:.1}", p50),
+            p95 = format!("{:.1}", p95),
+            count = histogram.get_sample_count(),
+        ),
+        _ => tr!(locale, "debug.no_samples"),
+    };
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.timings")))
+        .color(palette.success)
+        .field(tr!(locale, "debug.game_duration"), summary(&metrics::GAME_DURATION.with_label_values(&[RPS])), false)
+        .field(tr!(locale, "debug.invitation_latency"), summary(&metrics::TURN_LATENCY.with_label_values(&[RPS, "invitation"])), false)
+        .field(tr!(locale, "debug.move_latency"), summary(&metrics::TURN_LATENCY.with_label_values(&[RPS, "move"])), false)
+        .footer(|f| {
+            f.text(tr!(
+                locale,
+                "debug.timings_footer",
+                timeout = TIMEOUT.as_secs(),
+                warning = AFK_WARNING.as_secs(),
+            ))
+        });
+
+    embed
+}
+
+fn describe(session: &Session, locale: &str) -> String {
+    let game = if session.ladder { "debug.game_ladder" } else { "debug.game_rps" };
+
+    let state = match session.state {
+        State::Invited => "debug.state_invited",
+        State::StarterTurn => "debug.state_starter_turn",
+        State::OpponentTurn { .. } => "debug.state_opponent_turn",
+    };
+
+    let message = match session.message_id {
+        Some(message_id) => message_id.link(session.channel_id, session.guild_id),
+        None => tr!(locale, "debug.no_message"),
+    };
+
+    tr!(
+        locale,
+        "debug.session",
+        starter = session.starter.mention(),
+        opponent = session.opponent.mention(),
+        game = tr!(locale, game),
+        round = session.round,
+        state = tr!(locale, state),
+        started = format!("<t:{}:R>", session.started_at.unix_timestamp()),
+        message = message,
+    )
+}
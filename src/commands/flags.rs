@@ -0,0 +1,155 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::error;
+
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::flags;
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::storage;
+use crate::theme::Palette;
+
+const STATES: [&str; 3] = ["on", "off", "default"];
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.flags")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .create_option(|sub| {
+            sub
+                .localized("commands.flags.list")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.flags.set")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.flags.set.flag")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.flags.set.state")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+
+                    for state in STATES {
+                        option.add_string_choice(tr!(DEFAULT_LOCALE, &format!("flags.{}", state)), state);
+                    }
+
+                    option
+                })
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let guild_id = match cmd.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.guild_only")).await;
+
+            return;
+        },
+    };
+
+    if !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "flags.owner_only")).await;
+
+        return;
+    }
+
+    let subcommand = &cmd.data.options[0];
+
+    let option = |name: &str| subcommand.options.iter()
+        .find(|option| option.name == name)
+        .and_then(|option| match &option.resolved {
+            Some(CommandDataOptionValue::String(value)) => Some(value.as_str()),
+            _ => None,
+        });
+
+    let description = match (option("flag"), option("state")) {
+        (Some(flag), Some(state)) => {
+            if !flags::declared().contains_key(flag) {
+                reply_failure(ctx, cmd, &palette, &tr!(&locale, "flags.unknown", flag = flag)).await;
+
+                return;
+            }
+
+            let enabled = match state {
+                "on" => Some(true),
+                "off" => Some(false),
+                _ => None,
+            };
+
+            if let Err(err) = storage::save_guild_flag(guild_id.0, flag, enabled) {
+                error!("The feature flag could not have been saved: {:?}", err);
+
+                reply_failure(ctx, cmd, &palette, &tr!(&locale, "flags.save_failed")).await;
+
+                return;
+            }
+
+            tr!(&locale, "flags.updated", flag = flag)
+        },
+        _ => tr!(&locale, "flags.current"),
+    };
+
+    let embed = match flags_embed(guild_id, &palette, &locale, description) {
+        Ok(embed) => embed,
+        Err(err) => {
+            error!("The feature flags of the guild could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "flags.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| msg.ephemeral(true).set_embed(embed))
+    }).await;
+}
+
+/// Lists every declared flag along with its state in the guild and where that state comes from.
+fn flags_embed(guild_id: GuildId, palette: &Palette, locale: &str, description: String) -> storage::StorageResult<CreateEmbed> {
+    let states = flags::guild_states(guild_id)?;
+
+    let lines: Vec<_> = states.iter()
+        .map(|(name, state)| {
+            let enabled = tr!(locale, if state.enabled { "flags.on" } else { "flags.off" });
+            let source = tr!(locale, if state.overridden { "flags.overridden" } else { "flags.inherited" });
+
+            format!("`{}`: {} ({})", name, enabled, source)
+        })
+        .collect();
+
+    let list = if lines.is_empty() {
+        tr!(locale, "flags.none")
+    } else {
+        lines.join("\n")
+    };
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.flags")))
+        .color(palette.success)
+        .description(format!("{}\n\n{}", description, list));
+
+    Ok(embed)
+}
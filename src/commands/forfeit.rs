@@ -0,0 +1,42 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::prelude::Mentionable;
+
+use crate::commands::{reply_failure, rps, Localized};
+use crate::i18n::{self, tr};
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.localized("commands.forfeit")
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let winner = match rps::forfeit(ctx, &cmd.user).await {
+        Some(winner) => winner,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "forfeit.not_playing")).await;
+
+            return;
+        },
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.forfeit")))
+                            .color(palette.success)
+                            .description(tr!(&locale, "forfeit.done", user = winner.mention()))
+                    })
+            })
+    }).await;
+}
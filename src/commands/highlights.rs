@@ -0,0 +1,221 @@
+use serenity::builder::CreateActionRow;
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::Timestamp;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info};
+
+use crate::custom_ids::{self, CustomId};
+use crate::i18n::{self, tr};
+use crate::sessions::Session;
+use crate::storage::{self, Match};
+use crate::theme::{self, Palette};
+use crate::titles;
+
+/// The prefix of the custom IDs of the buttons sharing a result.
+pub const HIGHLIGHTS: &str = "highlights";
+
+/// The channel the guild shares the results of its games to, if it has chosen one.
+pub fn channel(guild_id: Option<GuildId>) -> Option<ChannelId> {
+    theme::settings(guild_id).highlights_channel_id.map(ChannelId)
+}
+
+/// Adds the button offering the players of a won game to share its result, for the guilds with a highlights [`channel`].
+pub fn add_button(row: &mut CreateActionRow, session: &Session) {
+    row.create_button(|button| {
+        button
+            .style(ButtonStyle::Secondary)
+            .label(tr!(&session.locale, "highlights.share"))
+            .custom_id(custom_ids::encode(HIGHLIGHTS, session.id, session.turn, "share"))
+    });
+}
+
+/// Handles the share button of a result, which asks for a confirmation in a private message, and that confirmation.
+pub async fn handle_component(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let palette = Palette::for_guild(interaction.guild_id);
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+    let locale = locale.as_str();
+
+    let game = match storage::find_match(custom_id.session_id) {
+        Ok(game) => game,
+        Err(err) => {
+            error!("The match could not have been read: {:?}", err);
+
+            None
+        },
+    };
+
+    let checked = match (game, channel(interaction.guild_id)) {
+        (None, _) => Err("highlights.unknown"),
+        (_, None) => Err("highlights.disabled"),
+        (Some(game), _) if game.starter_id != interaction.user.id.0 && game.opponent_id != interaction.user.id.0 => {
+            Err("highlights.not_participant")
+        },
+        (Some(game), _) if game.shared => Err("highlights.already_shared"),
+        (Some(game), Some(channel_id)) => Ok((game, channel_id)),
+    };
+
+    let (game, channel_id) = match checked {
+        Ok(checked) => checked,
+        Err(reason) => {
+            respond(ctx, interaction, custom_id.action == "share", Err(tr!(locale, reason))).await;
+
+            return;
+        },
+    };
+
+    match custom_id.action {
+        "share" => {
+            let _ = interaction.create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|msg| {
+                        msg
+                            .ephemeral(true)
+                            .embed(|embed| {
+                                embed
+                                    .author(|a| a.name(tr!(locale, "embeds.confirmation")))
+                                    .color(palette.confirmation)
+                                    .description(tr!(locale, "highlights.confirm", channel = channel_id.mention()))
+                            })
+                            .components(|comp| {
+                                comp.create_action_row(|row| {
+                                    row
+                                        .create_button(|button| {
+                                            button
+                                                .style(ButtonStyle::Success)
+                                                .label(tr!(locale, "highlights.post"))
+                                                .custom_id(custom_ids::encode(HIGHLIGHTS, game.id, custom_id.turn, "confirm"))
+                                        })
+                                        .create_button(|button| {
+                                            button
+                                                .style(ButtonStyle::Secondary)
+                                                .label(tr!(locale, "highlights.cancel"))
+                                                .custom_id(custom_ids::encode(HIGHLIGHTS, game.id, custom_id.turn, "cancel"))
+                                        })
+                                })
+                            })
+                    })
+            }).await;
+        },
+        "confirm" => {
+            let outcome = share(ctx, interaction, &game, channel_id, &palette, locale).await;
+
+            respond(ctx, interaction, false, outcome).await;
+        },
+        _ => respond(ctx, interaction, false, Ok(tr!(locale, "highlights.cancelled"))).await,
+    }
+}
+
+/// Posts the result card to the highlights channel, returning the description of the outcome.
+async fn share(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    game: &Match,
+    channel_id: ChannelId,
+    palette: &Palette,
+    locale: &str,
+) -> Result<String, String> {
+    // Marked first, so the result is not shared twice by both players confirming at once
+    match storage::set_match_shared(game.id, true) {
+        Ok(true) => {},
+        Ok(false) => return Err(tr!(locale, "highlights.already_shared")),
+        Err(err) => {
+            error!("The match could not have been marked as shared: {:?}", err);
+
+            return Err(tr!(locale, "highlights.failed", channel = channel_id.mention()));
+        },
+    }
+
+    let loser_id = if game.winner_id == game.starter_id { game.opponent_id } else { game.starter_id };
+
+    let (winner, loser) = match (UserId(game.winner_id).to_user(ctx).await, UserId(loser_id).to_user(ctx).await) {
+        (Ok(winner), Ok(loser)) => (winner, loser),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("The players of the match could not have been fetched: {:?}", err);
+
+            if let Err(err) = storage::set_match_shared(game.id, false) {
+                error!("The match could not have been marked as unshared: {:?}", err);
+            }
+
+            return Err(tr!(locale, "highlights.failed", channel = channel_id.mention()));
+        },
+    };
+
+    let sent = channel_id.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.highlight")).icon_url(winner.face()))
+                .color(palette.success)
+                .description(tr!(
+                    locale,
+                    "highlights.card",
+                    winner = titles::decorated(&winner, locale),
+                    loser = titles::decorated(&loser, locale),
+                    rounds = game.rounds,
+                ))
+                .field(tr!(locale, "highlights.shared_by"), interaction.user.mention(), false);
+
+            if let Ok(finished_at) = Timestamp::from_unix_timestamp(game.finished_at) {
+                embed.timestamp(finished_at);
+            }
+
+            embed
+        })
+    }).await;
+
+    match sent {
+        Ok(message) => {
+            info!(session = game.id, "The result has been shared");
+
+            Ok(tr!(locale, "highlights.shared", link = message.link()))
+        },
+        Err(err) => {
+            // Most likely missing permissions in the channel, which can be fixed before trying again
+            error!("The result could not have been shared: {:?}", err);
+
+            if let Err(err) = storage::set_match_shared(game.id, false) {
+                error!("The match could not have been marked as unshared: {:?}", err);
+            }
+
+            Err(tr!(locale, "highlights.failed", channel = channel_id.mention()))
+        },
+    }
+}
+
+/// Answers with a private message to a press on the result, or by updating the confirmation otherwise.
+async fn respond(ctx: &Context, interaction: &MessageComponentInteraction, new_message: bool, outcome: Result<String, String>) {
+    let palette = Palette::for_guild(interaction.guild_id);
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+
+    let kind = if new_message {
+        InteractionResponseType::ChannelMessageWithSource
+    } else {
+        InteractionResponseType::UpdateMessage
+    };
+
+    let (name, color, description) = match outcome {
+        Ok(description) => ("embeds.highlight", palette.success, description),
+        Err(description) => ("embeds.failure", palette.failure, description),
+    };
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(kind)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .components(|comp| comp)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, name)))
+                            .color(color)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
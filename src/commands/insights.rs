@@ -0,0 +1,182 @@
+use serenity::builder::{CreateActionRow, CreateApplicationCommand};
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::UserId;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::custom_ids::{self, CustomId};
+use crate::i18n::{self, tr};
+use crate::sessions::Session;
+use crate::storage;
+use crate::theme::Palette;
+
+/// The prefix of the custom IDs of the buttons showing the tendencies of the opponent.
+pub const INSIGHTS: &str = "insights";
+
+/// How many openings of the opponent must have been recorded before anything is said about them.
+const MIN_OPENINGS: u64 = 5;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.insights")
+        .create_option(|option| {
+            option
+                .localized("commands.insights.shared")
+                .kind(CommandOptionType::Boolean)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let mut preferences = match storage::user_preferences(cmd.user.id.0) {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let choice = cmd.data.options.first().and_then(|option| match option.resolved {
+        Some(CommandDataOptionValue::Boolean(shared)) => Some(shared),
+        _ => None,
+    });
+
+    if let Some(shared) = choice {
+        preferences.hide_tendencies = !shared;
+
+        if let Err(err) = storage::save_user_preferences(cmd.user.id.0, &preferences) {
+            error!("The user preferences could not have been saved: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.save_failed")).await;
+
+            return;
+        }
+    }
+
+    let description = match (choice.is_some(), preferences.hide_tendencies) {
+        (true, false) => tr!(&locale, "insights.shared"),
+        (true, true) => tr!(&locale, "insights.hidden"),
+        (false, false) => tr!(&locale, "insights.current_shared"),
+        (false, true) => tr!(&locale, "insights.current_hidden"),
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.insights")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// Adds the button offering both players of a won game a private look at how their opponent tends to play.
+pub fn add_button(row: &mut CreateActionRow, session: &Session) {
+    row.create_button(|button| {
+        button
+            .style(ButtonStyle::Secondary)
+            .label(tr!(&session.locale, "insights.button"))
+            .custom_id(custom_ids::encode(INSIGHTS, session.id, session.turn, "show"))
+    });
+}
+
+/// Answers the button with a private message describing the openings of the opponent of the player who has pressed it.
+pub async fn handle_component(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+    let locale = locale.as_str();
+
+    let outcome = match storage::find_match(custom_id.session_id) {
+        Ok(Some(game)) if game.starter_id == interaction.user.id.0 => insight(UserId(game.opponent_id), game.guild_id, locale),
+        Ok(Some(game)) if game.opponent_id == interaction.user.id.0 => insight(UserId(game.starter_id), game.guild_id, locale),
+        Ok(Some(_)) => Err(tr!(locale, "insights.not_participant")),
+        Ok(None) => Err(tr!(locale, "highlights.unknown")),
+        Err(err) => {
+            error!("The match could not have been read: {:?}", err);
+
+            Err(tr!(locale, "insights.load_failed"))
+        },
+    };
+
+    let palette = Palette::for_guild(interaction.guild_id);
+
+    let (title, color, description) = match outcome {
+        Ok(description) => (tr!(locale, "embeds.insights"), palette.success, description),
+        Err(description) => (tr!(locale, "embeds.failure"), palette.failure, description),
+    };
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(title))
+                            .color(color)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// Describes the favorite opening of the opponent, unless they keep their tendencies private
+/// or too few of their games are known for it to mean anything.
+fn insight(opponent_id: UserId, guild_id: Option<u64>, locale: &str) -> Result<String, String> {
+    let hidden = storage::user_preferences(opponent_id.0).map(|preferences| preferences.hide_tendencies);
+
+    let openings = match hidden {
+        Ok(true) => return Err(tr!(locale, "insights.private", user = opponent_id.mention())),
+        Ok(false) => storage::opening_moves(opponent_id.0, guild_id),
+        Err(err) => Err(err),
+    };
+
+    let openings = match openings {
+        Ok(openings) => openings,
+        Err(err) => {
+            error!("The openings could not have been read: {:?}", err);
+
+            return Err(tr!(locale, "insights.load_failed"));
+        },
+    };
+
+    let total = openings.values().sum::<u64>();
+
+    if total < MIN_OPENINGS {
+        return Ok(tr!(locale, "insights.too_few", user = opponent_id.mention()));
+    }
+
+    // Ties go to the move that comes first alphabetically, so the same history always reads the same
+    let (favorite, count) = openings.iter()
+        .max_by(|(a_move, a_count), (b_move, b_count)| a_count.cmp(b_count).then(b_move.cmp(a_move)))
+        .map(|(name, count)| (name.as_str(), *count))
+        .unwrap_or_default();
+
+    Ok(tr!(
+        locale,
+        "insights.opening",
+        user = opponent_id.mention(),
+        name = tr!(locale, &format!("moves.{}", favorite)),
+        share = (count * 100 + total / 2) / total,
+        games = total,
+    ))
+}
@@ -0,0 +1,49 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+
+use crate::commands::{Localized, GAME_PERMISSIONS, PERMISSIONS};
+use crate::i18n::{self, tr};
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.localized("commands.invite")
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let url = format!(
+        "https://discord.com/api/oauth2/authorize?client_id={}&permissions={}&scope=bot%20applications.commands",
+        cmd.application_id,
+        (PERMISSIONS | GAME_PERMISSIONS).bits(),
+    );
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.invite")))
+                            .color(palette.success)
+                            .description(tr!(&locale, "invite.description"))
+                    })
+                    .components(|comp| {
+                        comp.create_action_row(|row| {
+                            row.create_button(|button| {
+                                button
+                                    .style(ButtonStyle::Link)
+                                    .label(tr!(&locale, "invite.button"))
+                                    .url(url)
+                            })
+                        })
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,232 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::user::User;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info};
+
+use crate::commands::{defer, followup, followup_failure, reply_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::sessions::Session;
+use crate::storage;
+use crate::theme::{self, Palette};
+
+/// How many positions above themselves the players can challenge, unless the guild has chosen another range.
+pub const DEFAULT_RANGE: u32 = 3;
+
+/// How many players `/ladder view` lists, from the top.
+const LISTED_PLAYERS: usize = 25;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.ladder")
+        .dm_permission(false)
+        .create_option(|sub| {
+            sub
+                .localized("commands.ladder.view")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.ladder.join")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.ladder.leave")
+                .kind(CommandOptionType::SubCommand)
+        })
+}
+
+/// How many positions above themselves the players of the guild can challenge.
+pub fn range(guild_id: GuildId) -> u32 {
+    theme::settings(Some(guild_id)).ladder_range.unwrap_or(DEFAULT_RANGE)
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let guild_id = match cmd.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(locale, "settings.guild_only")).await;
+
+            return;
+        },
+    };
+
+    let result = match cmd.data.options[0].name.as_str() {
+        "view" => {
+            view(ctx, cmd, guild_id, locale).await;
+
+            return;
+        },
+        "join" => storage::join_ladder(guild_id.0, cmd.user.id.0).map(|position| match position {
+            Some(position) => {
+                info!(position, "The player has joined the ladder");
+
+                Ok(tr!(locale, "ladder.joined", position = position))
+            },
+            None => Err(tr!(locale, "ladder.already_joined")),
+        }),
+        "leave" => storage::leave_ladder(guild_id.0, cmd.user.id.0).map(|left| {
+            if left {
+                info!("The player has left the ladder");
+
+                Ok(tr!(locale, "ladder.left"))
+            } else {
+                Err(tr!(locale, "ladder.not_joined"))
+            }
+        }),
+        _ => return,
+    };
+
+    let description = match result {
+        Ok(Ok(description)) => description,
+        Ok(Err(description)) => {
+            reply_failure(ctx, cmd, &palette, &description).await;
+
+            return;
+        },
+        Err(err) => {
+            error!("The ladder could not have been saved: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(locale, "ladder.save_failed")).await;
+
+            return;
+        },
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(locale, "embeds.ladder")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+async fn view(ctx: &Context, cmd: &ApplicationCommandInteraction, guild_id: GuildId, locale: &str) {
+    let palette = Palette::for_guild(cmd.guild_id);
+
+    defer(ctx, cmd).await;
+
+    let players = match storage::ladder(guild_id.0) {
+        Ok(players) => players,
+        Err(err) => {
+            error!("The ladder could not have been read: {:?}", err);
+
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "ladder.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let description = if players.is_empty() {
+        tr!(locale, "ladder.empty")
+    } else {
+        players.iter()
+            .take(LISTED_PLAYERS)
+            .map(|(user_id, position)| tr!(locale, "ladder.entry", position = position, user = UserId(*user_id).mention()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // The players below the listed ones still want to know where they stand
+    let own_position = players.iter()
+        .skip(LISTED_PLAYERS)
+        .find(|(user_id, _)| *user_id == cmd.user.id.0)
+        .map(|(_, position)| *position);
+
+    followup(ctx, cmd, |embed| {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.ladder")))
+            .color(palette.success)
+            .description(description)
+            .footer(|footer| footer.text(tr!(locale, "ladder.footer", range = range(guild_id))));
+
+        if let Some(position) = own_position {
+            embed.field(tr!(locale, "ladder.own_position"), format!("#{}", position), false);
+        }
+
+        embed
+    }).await;
+}
+
+/// Makes sure the opponent is above the starter on the ladder and within their range,
+/// returning the reason to show the starter otherwise.
+pub fn check_challenge(guild_id: Option<GuildId>, starter: &User, opponent: &User, locale: &str) -> Result<(), String> {
+    let guild_id = guild_id.ok_or_else(|| tr!(locale, "settings.guild_only"))?;
+
+    let positions = storage::ladder_position(guild_id.0, starter.id.0).and_then(|starter_position| {
+        Ok((starter_position, storage::ladder_position(guild_id.0, opponent.id.0)?))
+    });
+
+    let (starter_position, opponent_position) = match positions {
+        Ok((Some(starter_position), Some(opponent_position))) => (starter_position, opponent_position),
+        Ok((None, _)) => return Err(tr!(locale, "ladder.not_joined")),
+        Ok((_, None)) => return Err(tr!(locale, "ladder.opponent_not_joined", user = opponent.mention())),
+        Err(err) => {
+            error!("The ladder could not have been read: {:?}", err);
+
+            return Err(tr!(locale, "ladder.load_failed"));
+        },
+    };
+
+    if opponent_position >= starter_position {
+        return Err(tr!(locale, "ladder.not_above", user = opponent.mention()));
+    }
+
+    let range = range(guild_id);
+
+    if starter_position - opponent_position > range {
+        return Err(tr!(locale, "ladder.out_of_range", user = opponent.mention(), range = range));
+    }
+
+    Ok(())
+}
+
+/// Moves the starter of a won ladder challenge to the position of the opponent, and the opponent to theirs.
+pub fn climb(session: &Session) {
+    let guild_id = match session.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    match storage::climb_ladder(guild_id.0, session.starter.id.0, session.opponent.id.0) {
+        Ok(true) => info!("The starter has climbed the ladder"),
+        Ok(false) => {},
+        Err(err) => error!("The ladder could not have been updated: {:?}", err),
+    }
+}
+
+/// Describes where the winner of a ladder challenge stands now, or `None` if the game has not been one.
+pub fn standing(session: &Session, winner: &User, starter_wins: bool) -> Option<String> {
+    if !session.ladder {
+        return None;
+    }
+
+    let position = storage::ladder_position(session.guild_id?.0, winner.id.0).ok()??;
+    let locale = session.locale.as_str();
+
+    let standing = if starter_wins {
+        tr!(locale, "ladder.climbed", user = winner.mention(), position = position)
+    } else {
+        tr!(locale, "ladder.defended", user = winner.mention(), position = position)
+    };
+
+    Some(standing)
+}
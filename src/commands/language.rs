@@ -0,0 +1,90 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::storage;
+use crate::theme::Palette;
+
+/// The choice that removes the preference and makes the bot follow the locale of the client again.
+const AUTOMATIC: &str = "auto";
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.language")
+        .create_option(|option| {
+            option
+                .localized("commands.language.language")
+                .kind(CommandOptionType::String)
+                .add_string_choice(tr!(DEFAULT_LOCALE, "language.automatic"), AUTOMATIC);
+
+            for locale in i18n::locales() {
+                option.add_string_choice(tr!(locale, "language.name"), locale);
+            }
+
+            option
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+
+    let mut preferences = match storage::user_preferences(cmd.user.id.0) {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&cmd.locale, "language.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let choice = cmd.data.options.first().and_then(|option| match &option.resolved {
+        Some(CommandDataOptionValue::String(choice)) => Some(choice),
+        _ => None,
+    });
+
+    if let Some(choice) = choice {
+        preferences.locale = (choice.as_str() != AUTOMATIC).then(|| choice.clone());
+
+        if let Err(err) = storage::save_user_preferences(cmd.user.id.0, &preferences) {
+            error!("The user preferences could not have been saved: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&cmd.locale, "language.save_failed")).await;
+
+            return;
+        }
+    }
+
+    let locale = preferences.locale.clone().unwrap_or_else(|| cmd.locale.clone());
+    let language = tr!(&locale, "language.name");
+
+    let description = match (choice.is_some(), preferences.locale.is_some()) {
+        (true, true) => tr!(&locale, "language.updated", language = language),
+        (true, false) => tr!(&locale, "language.reset", language = language),
+        (false, true) => tr!(&locale, "language.current", language = language),
+        (false, false) => tr!(&locale, "language.current_automatic", language = language),
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.language")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,95 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::storage;
+use crate::theme::{Layout, Palette};
+
+/// The choice that removes the preference and makes the games follow the layout of their server again.
+const SERVER: &str = "server";
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.layout")
+        .create_option(|option| {
+            option
+                .localized("commands.layout.layout")
+                .kind(CommandOptionType::String)
+                .add_string_choice(tr!(DEFAULT_LOCALE, "layout.server"), SERVER);
+
+            for layout in Layout::ALL {
+                option.add_string_choice(name(layout, DEFAULT_LOCALE), layout.as_setting());
+            }
+
+            option
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let mut preferences = match storage::user_preferences(cmd.user.id.0) {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let choice = cmd.data.options.first().and_then(|option| match &option.resolved {
+        Some(CommandDataOptionValue::String(choice)) => Some(choice),
+        _ => None,
+    });
+
+    if let Some(choice) = choice {
+        preferences.layout = Layout::from_setting(choice).map(|layout| layout.as_setting().to_string());
+
+        if let Err(err) = storage::save_user_preferences(cmd.user.id.0, &preferences) {
+            error!("The user preferences could not have been saved: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.save_failed")).await;
+
+            return;
+        }
+    }
+
+    let layout = preferences.layout.as_deref().and_then(Layout::from_setting);
+
+    let description = match (choice.is_some(), layout) {
+        (true, Some(layout)) => tr!(&locale, "layout.updated", layout = name(layout, &locale)),
+        (false, Some(layout)) => tr!(&locale, "layout.current", layout = name(layout, &locale)),
+        (true, None) => tr!(&locale, "layout.following_server"),
+        (false, None) => tr!(&locale, "layout.current_server"),
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.layout")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// The localized name of the layout, as shown in the choices and the responses.
+pub fn name(layout: Layout, locale: &str) -> String {
+    tr!(locale, &format!("layout.{}", layout.as_setting()))
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::model::Timestamp;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{defer, followup, followup_failure, Localized};
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::storage::{self, Standing, StorageResult};
+use crate::theme::Palette;
+
+/// How many players are listed, from the one with the most wins.
+const LISTED_PLAYERS: usize = 10;
+
+/// How long the standings of the heavier periods are reused before they are read again.
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+lazy_static! {
+    /// The standings of the heavier periods per guild, or `None` for the global ones.
+    static ref CACHE: Mutex<HashMap<(Option<GuildId>, Period), CachedStandings>> = Mutex::new(HashMap::new());
+}
+
+struct CachedStandings {
+    read_at: Instant,
+    standings: Vec<Standing>,
+}
+
+/// The rolling window the standings are computed over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum Period {
+    Weekly,
+    Monthly,
+    #[default]
+    AllTime,
+}
+
+impl Period {
+    const ALL: [Self; 3] = [Self::Weekly, Self::Monthly, Self::AllTime];
+
+    fn from_setting(setting: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|period| period.as_setting() == setting)
+    }
+
+    fn as_setting(self) -> &'static str {
+        match self {
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::AllTime => "all_time",
+        }
+    }
+
+    /// How far back the period reaches, or `None` for the whole history.
+    fn window(self) -> Option<Duration> {
+        match self {
+            Self::Weekly => Some(Duration::from_secs(7 * 86400)),
+            Self::Monthly => Some(Duration::from_secs(30 * 86400)),
+            Self::AllTime => None,
+        }
+    }
+
+    /// Whether the standings are cached: the week is cheap enough to be read every time and is expected to move fast.
+    fn is_cached(self) -> bool {
+        self != Self::Weekly
+    }
+
+    fn name(self, locale: &str) -> String {
+        tr!(locale, &format!("leaderboard.{}", self.as_setting()))
+    }
+}
+
+/// Drops the cached standings, so they are read again with the current config.
+pub fn clear_cache() {
+    CACHE.lock().unwrap().clear();
+}
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.leaderboard")
+        .create_option(|option| {
+            option
+                .localized("commands.leaderboard.period")
+                .kind(CommandOptionType::String);
+
+            for period in Period::ALL {
+                option.add_string_choice(period.name(DEFAULT_LOCALE), period.as_setting());
+            }
+
+            option
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let period = cmd.data.options.first()
+        .and_then(|option| match &option.resolved {
+            Some(CommandDataOptionValue::String(choice)) => Period::from_setting(choice),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    defer(ctx, cmd).await;
+
+    let standings = match standings(cmd.guild_id, period) {
+        Ok(standings) => standings,
+        Err(err) => {
+            error!("The leaderboard could not have been read: {:?}", err);
+
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "leaderboard.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let description = if standings.is_empty() {
+        tr!(locale, "leaderboard.empty")
+    } else {
+        standings.iter()
+            .enumerate()
+            .map(|(index, standing)| {
+                tr!(
+                    locale,
+                    "leaderboard.entry",
+                    position = index + 1,
+                    user = UserId(standing.user_id).mention(),
+                    wins = standing.wins,
+                    losses = standing.losses,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let scope = if cmd.guild_id.is_some() {
+        tr!(locale, "leaderboard.server", period = period.name(locale))
+    } else {
+        tr!(locale, "leaderboard.global", period = period.name(locale))
+    };
+
+    followup(ctx, cmd, |embed| {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.leaderboard")))
+            .color(palette.success)
+            .title(scope)
+            .description(description)
+    }).await;
+}
+
+/// Reads the standings of the guild (or the global ones outside of guilds) over the period,
+/// reusing those of the heavier periods for a while.
+fn standings(guild_id: Option<GuildId>, period: Period) -> StorageResult<Vec<Standing>> {
+    if period.is_cached() {
+        let cache = CACHE.lock().unwrap();
+
+        if let Some(cached) = cache.get(&(guild_id, period)) {
+            if cached.read_at.elapsed() < CACHE_TTL {
+                return Ok(cached.standings.clone());
+            }
+        }
+    }
+
+    let since = period.window().map(|window| Timestamp::now().unix_timestamp() - window.as_secs() as i64);
+
+    let standings = storage::leaderboard(guild_id.map(|guild_id| guild_id.0), since, LISTED_PLAYERS)?;
+
+    if period.is_cached() {
+        let mut cache = CACHE.lock().unwrap();
+
+        // The expired standings of other guilds are dropped along the way, so the cache does not grow forever
+        cache.retain(|_, cached| cached.read_at.elapsed() < CACHE_TTL);
+        cache.insert((guild_id, period), CachedStandings { read_at: Instant::now(), standings: standings.clone() });
+    }
+
+    Ok(standings)
+}
@@ -0,0 +1,69 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::permissions::Permissions;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::info;
+
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::flags;
+use crate::i18n::{self, tr};
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.maintenance")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .create_option(|option| {
+            option
+                .localized("commands.maintenance.enabled")
+                .kind(CommandOptionType::Boolean)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    if !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "maintenance.owner_only")).await;
+
+        return;
+    }
+
+    let choice = cmd.data.options.first().and_then(|option| match option.resolved {
+        Some(CommandDataOptionValue::Boolean(enabled)) => Some(enabled),
+        _ => None,
+    });
+
+    if let Some(enabled) = choice {
+        flags::set_maintenance(enabled);
+
+        info!("The maintenance mode has been {} by {}", if enabled { "enabled" } else { "disabled" }, cmd.user.tag());
+    }
+
+    let description = match (choice.is_some(), flags::maintenance()) {
+        (true, true) => tr!(&locale, "maintenance.enabled"),
+        (true, false) => tr!(&locale, "maintenance.disabled"),
+        (false, true) => tr!(&locale, "maintenance.current_enabled"),
+        (false, false) => tr!(&locale, "maintenance.current_disabled"),
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.maintenance")))
+                            .color(palette.warning)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,306 @@
+pub mod admin;
+pub mod backup;
+pub mod blocks;
+pub mod challenges;
+pub mod debug;
+pub mod flags;
+pub mod forfeit;
+pub mod highlights;
+pub mod insights;
+pub mod invite;
+pub mod ladder;
+pub mod language;
+pub mod layout;
+pub mod leaderboard;
+pub mod maintenance;
+pub mod notifications;
+pub mod profile;
+pub mod prune;
+pub mod replay;
+pub mod resets;
+pub mod rps;
+pub mod settings;
+pub mod setup;
+pub mod spectate;
+pub mod stats;
+pub mod top;
+#[cfg(feature = "topgg")]
+pub mod vote;
+pub mod vs;
+
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption, CreateApplicationCommands, CreateEmbed};
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::application::command::Command;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::permissions::Permissions;
+
+use tracing::error;
+
+use crate::config;
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::theme::Palette;
+#[cfg(feature = "topgg")]
+use crate::votes;
+
+/// The permissions the bot needs in the channels it is used in: the game itself only answers interactions,
+/// but the welcome message is sent as a regular message, deleted games can only be noticed in visible channels,
+/// and the rematches are offered with reactions.
+pub const PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::EMBED_LINKS)
+    .union(REMATCH_PERMISSIONS);
+
+/// The permissions offering a rematch takes: the bot reacts to the result of the game with the rematch emoji,
+/// which Discord only allows on the messages whose history it can read.
+pub const REMATCH_PERMISSIONS: Permissions = Permissions::ADD_REACTIONS
+    .union(Permissions::READ_MESSAGE_HISTORY);
+
+/// The permissions a game needs in its channel, which are checked before it starts,
+/// as the buttons and the custom move emojis would otherwise only fail mid-game.
+pub const GAME_PERMISSIONS: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::EMBED_LINKS)
+    .union(Permissions::USE_EXTERNAL_EMOJIS);
+
+/// The resource keys of the names of the [`GAME_PERMISSIONS`], in the order they are listed in.
+const PERMISSION_NAMES: [(Permissions, &str); 3] = [
+    (Permissions::SEND_MESSAGES, "permissions.send_messages"),
+    (Permissions::EMBED_LINKS, "permissions.embed_links"),
+    (Permissions::USE_EXTERNAL_EMOJIS, "permissions.use_external_emojis"),
+];
+
+pub fn register_all(cmds: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    cmds
+        .create_application_command(blocks::register_block)
+        .create_application_command(blocks::register_unblock)
+        .create_application_command(challenges::register)
+        .create_application_command(forfeit::register)
+        .create_application_command(insights::register)
+        .create_application_command(invite::register)
+        .create_application_command(ladder::register)
+        .create_application_command(language::register)
+        .create_application_command(layout::register)
+        .create_application_command(leaderboard::register)
+        .create_application_command(notifications::register)
+        .create_application_command(profile::register)
+        .create_application_command(replay::register)
+        .create_application_command(resets::register_reset)
+        .create_application_command(rps::register)
+        .create_application_command(settings::register)
+        .create_application_command(setup::register)
+        .create_application_command(spectate::register)
+        .create_application_command(stats::register)
+        .create_application_command(top::register)
+        .create_application_command(vs::register);
+
+    #[cfg(feature = "topgg")]
+    if votes::enabled() {
+        cmds.create_application_command(vote::register);
+    }
+
+    cmds
+}
+
+/// The commands only the owners of the bot may use, which are registered in the guild of `owner_guild` alone
+/// rather than offered to the administrators of every guild; the commands still check the owners themselves.
+pub fn register_owner(cmds: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    cmds
+        .create_application_command(admin::register)
+        .create_application_command(backup::register)
+        .create_application_command(debug::register)
+        .create_application_command(flags::register)
+        .create_application_command(maintenance::register)
+        .create_application_command(prune::register)
+        .create_application_command(resets::register_restore)
+}
+
+/// The guild the owner-only commands are registered in: the one of `owner_guild`, or the one of `commands_guild`
+/// if it is unset. They are not registered at all without either.
+fn owner_guild(config: &config::Config) -> Option<u64> {
+    config.owner_guild.or(config.commands_guild)
+}
+
+/// Registers the commands in the guild of `commands_guild`, or globally if it is unset, and the owner-only ones
+/// in the [`owner_guild`], returning how many there are.
+pub async fn sync(http: &Http) -> serenity::Result<usize> {
+    let config = config::get();
+    let owner_guild = owner_guild(&config);
+
+    let registered = match config.commands_guild {
+        Some(guild_id) if owner_guild == Some(guild_id) => GuildId(guild_id)
+            .set_application_commands(http, |cmds| register_owner(register_all(cmds)))
+            .await?,
+        Some(guild_id) => GuildId(guild_id).set_application_commands(http, register_all).await?,
+        None => Command::set_global_application_commands(http, register_all).await?,
+    };
+
+    let mut count = registered.len();
+
+    if let Some(guild_id) = owner_guild.filter(|guild_id| config.commands_guild != Some(*guild_id)) {
+        count += GuildId(guild_id).set_application_commands(http, register_owner).await?.len();
+    }
+
+    Ok(count)
+}
+
+/// Removes the commands from where [`sync`] registers them.
+pub async fn clear(http: &Http) -> serenity::Result<()> {
+    let config = config::get();
+
+    match config.commands_guild {
+        Some(guild_id) => GuildId(guild_id).set_application_commands(http, |cmds| cmds).await?,
+        None => Command::set_global_application_commands(http, |cmds| cmds).await?,
+    };
+
+    if let Some(guild_id) = owner_guild(&config).filter(|guild_id| config.commands_guild != Some(*guild_id)) {
+        GuildId(guild_id).set_application_commands(http, |cmds| cmds).await?;
+    }
+
+    Ok(())
+}
+
+/// Fills in the name and description of a command or an option from the `<key>.name`
+/// and `<key>.description` entries of the resource files, including every translation.
+pub trait Localized {
+    fn localized(&mut self, key: &str) -> &mut Self;
+}
+
+macro_rules! impl_localized {
+    ($($builder:ty),*) => {
+        $(impl Localized for $builder {
+            fn localized(&mut self, key: &str) -> &mut Self {
+                let name_key = format!("{}.name", key);
+                let description_key = format!("{}.description", key);
+
+                self
+                    .name(i18n::translate(DEFAULT_LOCALE, &name_key, &[]))
+                    .description(i18n::translate(DEFAULT_LOCALE, &description_key, &[]));
+
+                for (locale, name) in i18n::localizations(&name_key) {
+                    self.name_localized(locale, name);
+                }
+
+                for (locale, description) in i18n::localizations(&description_key) {
+                    self.description_localized(locale, description);
+                }
+
+                self
+            }
+        })*
+    };
+}
+
+impl_localized!(CreateApplicationCommand, CreateApplicationCommandOption);
+
+/// Responds to the command with an ephemeral failure embed.
+pub async fn reply_failure(ctx: &Context, cmd: &ApplicationCommandInteraction, palette: &Palette, description: &str) {
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.failure")))
+                            .color(palette.failure)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// Responds to a component none of the handlers has accepted, as its custom ID is not signed with the current key:
+/// it has been tampered with, or it has been sent before the key changed.
+pub async fn reject_component(ctx: &Context, interaction: &MessageComponentInteraction) {
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+    let palette = Palette::for_guild(interaction.guild_id);
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.failure")))
+                            .color(palette.failure)
+                            .description(tr!(&locale, "components.invalid"))
+                    })
+            })
+    }).await;
+}
+
+/// Acknowledges the command at once, showing that the bot is thinking, for the commands whose response depends on
+/// reading enough of the database to risk missing the 3 seconds Discord gives to acknowledge it.
+/// They then answer with [`followup`] or [`followup_failure`] instead of responding.
+pub async fn defer(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+    }).await;
+}
+
+/// Replaces the thinking state of a deferred command with the embed of its response.
+pub async fn followup<F>(ctx: &Context, cmd: &ApplicationCommandInteraction, build: F)
+where
+    F: FnOnce(&mut CreateEmbed) -> &mut CreateEmbed,
+{
+    let _ = cmd.edit_original_interaction_response(&ctx.http, |response| response.embed(build)).await;
+}
+
+/// Answers a deferred command with an ephemeral failure embed, like [`reply_failure`]; the thinking state is public,
+/// so it is deleted rather than replaced, and the failure is sent as a followup only the user can see.
+pub async fn followup_failure(ctx: &Context, cmd: &ApplicationCommandInteraction, palette: &Palette, description: &str) {
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let _ = cmd.delete_original_interaction_response(&ctx.http).await;
+
+    let _ = cmd.create_followup_message(&ctx.http, |msg| {
+        msg
+            .ephemeral(true)
+            .embed(|embed| {
+                embed
+                    .author(|a| a.name(tr!(&locale, "embeds.failure")))
+                    .color(palette.failure)
+                    .description(description)
+            })
+    }).await;
+}
+
+/// The localized names of the [`GAME_PERMISSIONS`] the bot lacks in the channel of the command, if any.
+/// Nothing is reported when Discord has not sent the permissions, so the command is not blocked for nothing.
+pub fn missing_permissions(cmd: &ApplicationCommandInteraction, locale: &str) -> Option<String> {
+    let missing = GAME_PERMISSIONS - cmd.app_permissions?;
+
+    if missing.is_empty() {
+        return None;
+    }
+
+    let names = PERMISSION_NAMES.iter()
+        .filter(|(permission, _)| missing.contains(*permission))
+        .map(|(_, key)| format!("**{}**", tr!(locale, key)))
+        .collect::<Vec<_>>();
+
+    Some(names.join(", "))
+}
+
+/// Whether the user owns the application (or belongs to the team owning it).
+pub async fn is_owner(ctx: &Context, user_id: UserId) -> bool {
+    match ctx.http.get_current_application_info().await {
+        Ok(info) => match info.team {
+            Some(team) => team.members.iter().any(|member| member.user.id == user_id),
+            None => info.owner.id == user_id,
+        },
+        Err(err) => {
+            error!("The application info could not have been fetched: {:?}", err);
+
+            false
+        },
+    }
+}
@@ -0,0 +1,109 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::storage;
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.notifications")
+        .create_option(|option| {
+            option
+                .localized("commands.notifications.invitations")
+                .kind(CommandOptionType::Boolean)
+        })
+        .create_option(|option| {
+            option
+                .localized("commands.notifications.dethronements")
+                .kind(CommandOptionType::Boolean)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let mut preferences = match storage::user_preferences(cmd.user.id.0) {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let choices = cmd.data.options.iter()
+        .filter_map(|option| match option.resolved {
+            Some(CommandDataOptionValue::Boolean(enabled)) => Some((option.name.as_str(), enabled)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (name, enabled) in &choices {
+        match *name {
+            "invitations" => preferences.dm_invitations = *enabled,
+            "dethronements" => preferences.dm_dethronements = *enabled,
+            _ => {},
+        }
+    }
+
+    if !choices.is_empty() {
+        if let Err(err) = storage::save_user_preferences(cmd.user.id.0, &preferences) {
+            error!("The user preferences could not have been saved: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "language.save_failed")).await;
+
+            return;
+        }
+    }
+
+    // Only what has been changed is told, or everything if nothing has
+    let chosen = |name: &str| choices.is_empty() || choices.iter().any(|(chosen, _)| *chosen == name);
+
+    let mut lines = Vec::new();
+
+    if chosen("invitations") {
+        lines.push(match (choices.is_empty(), preferences.dm_invitations) {
+            (false, true) => tr!(&locale, "notifications.enabled"),
+            (false, false) => tr!(&locale, "notifications.disabled"),
+            (true, true) => tr!(&locale, "notifications.current_enabled"),
+            (true, false) => tr!(&locale, "notifications.current_disabled"),
+        });
+    }
+
+    if chosen("dethronements") {
+        lines.push(match (choices.is_empty(), preferences.dm_dethronements) {
+            (false, true) => tr!(&locale, "notifications.dethronements.enabled"),
+            (false, false) => tr!(&locale, "notifications.dethronements.disabled"),
+            (true, true) => tr!(&locale, "notifications.dethronements.current_enabled"),
+            (true, false) => tr!(&locale, "notifications.dethronements.current_disabled"),
+        });
+    }
+
+    let description = lines.join("\n\n");
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.notifications")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,209 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOption};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::error;
+
+use crate::commands::{defer, followup, followup_failure, reply_failure, Localized};
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::storage::{self, Record};
+use crate::theme::Palette;
+use crate::titles::{self, Title};
+
+/// The choice taking the current title off.
+const NO_TITLE: &str = "none";
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.profile")
+        .create_option(|sub| {
+            sub
+                .localized("commands.profile.view")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.profile.view.user")
+                        .kind(CommandOptionType::User)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.profile.title")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.profile.title.title")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+
+                    for title in Title::ALL {
+                        option.add_string_choice(title.name(DEFAULT_LOCALE), title.as_setting());
+                    }
+
+                    option.add_string_choice(tr!(DEFAULT_LOCALE, "profile.no_title"), NO_TITLE)
+                })
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let subcommand = &cmd.data.options[0];
+
+    match subcommand.name.as_str() {
+        "view" => view(ctx, cmd, &subcommand.options).await,
+        "title" => title(ctx, cmd, &subcommand.options).await,
+        _ => {}
+    }
+}
+
+async fn view(ctx: &Context, cmd: &ApplicationCommandInteraction, options: &[CommandDataOption]) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let user = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::User(user, _)) => user,
+        _ => &cmd.user,
+    };
+
+    defer(ctx, cmd).await;
+
+    let guild_id = cmd.guild_id.map(|guild_id| guild_id.0);
+
+    let records = storage::record(user.id.0, None).and_then(|global| {
+        let guild = guild_id.map(|guild_id| storage::record(user.id.0, Some(guild_id))).transpose()?;
+
+        Ok((global, guild))
+    });
+
+    let (global, guild) = match records {
+        Ok(records) => records,
+        Err(err) => {
+            error!("The match records could not have been read: {:?}", err);
+
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "profile.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let unlocked = Title::ALL.into_iter()
+        .filter(|title| title.is_unlocked(&global))
+        .map(|title| title.name(locale))
+        .collect::<Vec<_>>();
+
+    let unlocked = if unlocked.is_empty() {
+        tr!(locale, "profile.no_titles")
+    } else {
+        unlocked.join(", ")
+    };
+
+    let streak = match global.streak {
+        0 => tr!(locale, "profile.no_streak"),
+        streak if streak > 0 => tr!(locale, "profile.win_streak", count = streak),
+        streak => tr!(locale, "profile.loss_streak", count = -streak),
+    };
+
+    followup(ctx, cmd, |embed| {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.profile")).icon_url(user.face()))
+            .color(palette.success)
+            .description(titles::decorated(user, locale))
+            .field(tr!(locale, "profile.global"), summary(&global, locale), true);
+
+        if let Some(guild) = &guild {
+            embed.field(tr!(locale, "profile.server"), summary(guild, locale), true);
+        }
+
+        embed
+            .field(tr!(locale, "profile.streak"), streak, false)
+            .field(tr!(locale, "profile.titles"), unlocked, false)
+    }).await;
+}
+
+/// Equips an unlocked title, or takes the current one off.
+async fn title(ctx: &Context, cmd: &ApplicationCommandInteraction, options: &[CommandDataOption]) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let title = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::String(value)) => Title::from_setting(value),
+        _ => return,
+    };
+
+    if let Some(title) = title {
+        let unlocked = match storage::record(cmd.user.id.0, None) {
+            Ok(record) => title.is_unlocked(&record),
+            Err(err) => {
+                error!("The match records could not have been read: {:?}", err);
+
+                reply_failure(ctx, cmd, &palette, &tr!(locale, "profile.load_failed")).await;
+
+                return;
+            },
+        };
+
+        if !unlocked {
+            let description = tr!(
+                locale,
+                "profile.title_locked",
+                title = title.name(locale),
+                requirement = title.requirement(locale),
+            );
+
+            reply_failure(ctx, cmd, &palette, &description).await;
+
+            return;
+        }
+    }
+
+    let saved = storage::user_preferences(cmd.user.id.0).and_then(|mut preferences| {
+        preferences.title = title.map(|title| title.as_setting().to_string());
+
+        storage::save_user_preferences(cmd.user.id.0, &preferences)
+    });
+
+    if let Err(err) = saved {
+        error!("The user preferences could not have been saved: {:?}", err);
+
+        reply_failure(ctx, cmd, &palette, &tr!(locale, "language.save_failed")).await;
+
+        return;
+    }
+
+    let description = match title {
+        Some(title) => tr!(locale, "profile.title_equipped", title = title.name(locale)),
+        None => tr!(locale, "profile.title_removed"),
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(locale, "embeds.profile")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// Formats the wins, losses and win rate of a record.
+fn summary(record: &Record, locale: &str) -> String {
+    let games = record.wins + record.losses;
+
+    if games == 0 {
+        return tr!(locale, "profile.no_games");
+    }
+
+    let rate = (record.wins * 100 + games / 2) / games;
+
+    tr!(locale, "profile.record", wins = record.wins, losses = record.losses, rate = rate)
+}
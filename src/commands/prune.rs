@@ -0,0 +1,76 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::permissions::Permissions;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+
+use tracing::{error, info};
+
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::retention;
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.prune")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .create_option(|option| {
+            option
+                .localized("commands.prune.dry_run")
+                .kind(CommandOptionType::Boolean)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    if !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "prune.owner_only")).await;
+
+        return;
+    }
+
+    // Nothing is deleted unless explicitly asked for
+    let dry_run = !matches!(
+        cmd.data.options.first().and_then(|option| option.resolved.as_ref()),
+        Some(CommandDataOptionValue::Boolean(false)),
+    );
+
+    let count = match retention::prune(dry_run) {
+        Ok(count) => count,
+        Err(err) => {
+            error!("The expired matches could not have been pruned: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "prune.failed")).await;
+
+            return;
+        },
+    };
+
+    let description = if dry_run {
+        tr!(&locale, "prune.dry_run", count = count)
+    } else {
+        info!("{} expired matches have been pruned by {}", count, cmd.user.tag());
+
+        tr!(&locale, "prune.pruned", count = count)
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.prune")))
+                            .color(palette.warning)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,222 @@
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOptionValue};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::channel::ReactionType;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::custom_ids::{self, CustomId};
+use crate::i18n::{self, tr};
+use crate::rules;
+use crate::storage::{self, Match, Round, StorageResult};
+use crate::theme::{MoveEmojis, Theme};
+
+/// The prefix of the custom IDs of the buttons stepping through the rounds.
+pub const REPLAY: &str = "replay";
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.replay")
+        .create_option(|option| {
+            option
+                .localized("commands.replay.match")
+                // The IDs of the matches are snowflakes, which exceed the integers Discord accepts
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let theme = Theme::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let match_id = cmd.data.options.first()
+        .and_then(|option| match &option.resolved {
+            Some(CommandDataOptionValue::String(match_id)) => match_id.trim().trim_start_matches('#').parse().ok(),
+            _ => None,
+        });
+
+    let replay = match match_id.map(|match_id| load(match_id, cmd.user.id, cmd.guild_id)) {
+        Some(Ok(Some(replay))) => replay,
+        Some(Err(err)) => {
+            error!("The replay could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &theme.palette, &tr!(&locale, "replay.load_failed")).await;
+
+            return;
+        },
+        _ => {
+            reply_failure(ctx, cmd, &theme.palette, &tr!(&locale, "replay.not_found")).await;
+
+            return;
+        },
+    };
+
+    let (embed, components) = page(&replay, &theme, &locale, 0);
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| msg.ephemeral(true).set_embed(embed).set_components(components))
+    }).await;
+}
+
+/// Steps through the rounds; only the user who has run the command can see the ephemeral message,
+/// so the presses need no further check than the one made when it was sent.
+pub async fn handle_component(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let theme = Theme::for_guild(interaction.guild_id);
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+
+    let replay = match load(custom_id.session_id, interaction.user.id, interaction.guild_id) {
+        Ok(Some(replay)) => replay,
+        result => {
+            let reason = match result {
+                Err(err) => {
+                    error!("The replay could not have been read: {:?}", err);
+
+                    "replay.load_failed"
+                },
+                _ => "replay.not_found",
+            };
+
+            let _ = interaction.create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|msg| {
+                        msg
+                            .components(|comp| comp)
+                            .embed(|embed| {
+                                embed
+                                    .author(|a| a.name(tr!(&locale, "embeds.failure")))
+                                    .color(theme.palette.failure)
+                                    .description(tr!(&locale, reason))
+                            })
+                    })
+            }).await;
+
+            return;
+        },
+    };
+
+    let (embed, components) = page(&replay, &theme, &locale, custom_id.turn as usize);
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|msg| msg.set_embed(embed).set_components(components))
+    }).await;
+}
+
+struct Replay {
+    game: Match,
+    rounds: Vec<Round>,
+}
+
+/// Reads the match with its rounds, as long as the user may see it: they have played it,
+/// or it has been played in the guild the command is used in.
+fn load(match_id: u64, user_id: UserId, guild_id: Option<GuildId>) -> StorageResult<Option<Replay>> {
+    let game = storage::find_match(match_id)?.filter(|game| {
+        game.starter_id == user_id.0
+            || game.opponent_id == user_id.0
+            || game.guild_id.is_some() && game.guild_id == guild_id.map(|guild_id| guild_id.0)
+    });
+
+    match game {
+        Some(game) => Ok(Some(Replay { rounds: storage::match_rounds(game.id)?, game })),
+        None => Ok(None),
+    }
+}
+
+/// Builds the page of a round, the last one if there are fewer rounds than asked for.
+fn page(replay: &Replay, theme: &Theme, locale: &str, page: usize) -> (CreateEmbed, CreateComponents) {
+    let Replay { game, rounds } = replay;
+
+    let starter = UserId(game.starter_id).mention();
+    let opponent = UserId(game.opponent_id).mention();
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.replay")))
+        .color(theme.palette.success);
+
+    if rounds.is_empty() {
+        embed.description(tr!(
+            locale,
+            "replay.no_rounds",
+            starter = starter,
+            opponent = opponent,
+            winner = UserId(game.winner_id).mention(),
+        ));
+
+        return (embed, CreateComponents::default());
+    }
+
+    let page = page.min(rounds.len() - 1);
+    let round = &rounds[page];
+
+    let formatted_move = |name: &str| format!("{} {}", emoji(&theme.emojis, name), tr!(locale, &format!("moves.{}", name)));
+
+    let outcome = match rules::wins(&round.starter_move, &round.opponent_move) {
+        Some(true) => tr!(locale, "replay.round_won", user = starter),
+        Some(false) => tr!(locale, "replay.round_won", user = opponent),
+        None => tr!(locale, "replay.round_drawn"),
+    };
+
+    embed
+        .description(tr!(
+            locale,
+            "replay.matchup",
+            starter = starter,
+            opponent = opponent,
+            finished = format!("<t:{}:R>", game.finished_at),
+        ))
+        .field(tr!(locale, "replay.starter_move"), formatted_move(&round.starter_move), true)
+        .field(tr!(locale, "replay.opponent_move"), formatted_move(&round.opponent_move), true)
+        .field(tr!(locale, "replay.outcome"), outcome, false);
+
+    if page + 1 == rounds.len() {
+        embed.field(tr!(locale, "replay.result"), tr!(locale, "replay.match_won", user = UserId(game.winner_id).mention()), false);
+    }
+
+    embed.footer(|f| f.text(tr!(locale, "replay.page", round = round.round, rounds = game.rounds, id = game.id)));
+
+    let mut components = CreateComponents::default();
+
+    if rounds.len() > 1 {
+        components.create_action_row(|row| {
+            row
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label(tr!(locale, "replay.previous"))
+                        .disabled(page == 0)
+                        .custom_id(custom_ids::encode(REPLAY, game.id, page.saturating_sub(1) as u32, "page"))
+                })
+                .create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label(tr!(locale, "replay.next"))
+                        .disabled(page + 1 == rounds.len())
+                        .custom_id(custom_ids::encode(REPLAY, game.id, (page + 1) as u32, "page"))
+                })
+        });
+    }
+
+    (embed, components)
+}
+
+fn emoji<'a>(emojis: &'a MoveEmojis, name: &str) -> &'a ReactionType {
+    match name {
+        "rock" => &emojis.rock,
+        "paper" => &emojis.paper,
+        _ => &emojis.scissors,
+    }
+}
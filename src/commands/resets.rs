@@ -0,0 +1,125 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::permissions::Permissions;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::model::Timestamp;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info};
+
+use crate::commands::{is_owner, reply_failure, Localized};
+use crate::config;
+use crate::i18n::{self, tr};
+use crate::retention;
+use crate::storage;
+use crate::theme::Palette;
+
+pub fn register_reset(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.reset_stats")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|option| {
+            option
+                .localized("commands.reset_stats.user")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+}
+
+pub fn register_restore(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.restore_stats")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .create_option(|option| {
+            option
+                .localized("commands.restore_stats.user")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+}
+
+/// Handles both `/reset-stats`, left to the managers of the guild, and `/restore-stats`, left to the owners of the bot
+/// so a reset cannot be undone by whoever it was meant to be kept from.
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let guild_id = match cmd.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.guild_only")).await;
+
+            return;
+        },
+    };
+
+    let user = match cmd.data.options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::User(user, _)) => user,
+        _ => return,
+    };
+
+    let restore = cmd.data.name == "restore-stats";
+
+    if restore && !is_owner(ctx, cmd.user.id).await {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "resets.owner_only")).await;
+
+        return;
+    }
+
+    let now = Timestamp::now().unix_timestamp();
+    let grace_seconds = retention::grace_seconds();
+
+    let description = if restore {
+        match storage::restore_stats(guild_id.0, user.id.0, now, grace_seconds) {
+            Ok(true) => {
+                info!("The statistics of {} in {} have been restored by {}", user.id, guild_id, cmd.user.tag());
+
+                tr!(&locale, "resets.restored", user = user.mention())
+            },
+            Ok(false) => {
+                reply_failure(ctx, cmd, &palette, &tr!(&locale, "resets.nothing_to_restore", user = user.mention())).await;
+
+                return;
+            },
+            Err(err) => {
+                error!("The statistics could not have been restored: {:?}", err);
+
+                reply_failure(ctx, cmd, &palette, &tr!(&locale, "resets.failed")).await;
+
+                return;
+            },
+        }
+    } else {
+        if let Err(err) = storage::reset_stats(guild_id.0, user.id.0, cmd.user.id.0, now, grace_seconds) {
+            error!("The statistics could not have been reset: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "resets.failed")).await;
+
+            return;
+        }
+
+        info!("The statistics of {} in {} have been reset by {}", user.id, guild_id, cmd.user.tag());
+
+        tr!(&locale, "resets.reset", user = user.mention(), days = config::get().stats_reset_grace_days)
+    };
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.resets")))
+                            .color(palette.warning)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
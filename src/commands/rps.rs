@@ -0,0 +1,3073 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use serenity::builder::{
+    CreateActionRow, CreateApplicationCommand, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    EditInteractionResponse,
+};
+use serenity::client::Context;
+use serenity::json::{self, Value};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOption};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::channel::{Reaction, ReactionType};
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::model::id::{ChannelId, GuildId, InteractionId, MessageId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::model::user::User;
+use serenity::model::Timestamp;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info, warn, Span};
+
+use crate::champions;
+use crate::commands::{self, highlights, insights, ladder, reply_failure, Localized, REMATCH_PERMISSIONS};
+use crate::config;
+use crate::custom_ids::{self, CustomId};
+use crate::flags;
+use crate::i18n::{self, tr};
+use crate::metrics::{self, RPS};
+use crate::replays;
+use crate::reporting;
+use crate::rules::{self, MOVES};
+use crate::sessions::{self, Session, Start, State};
+use crate::spectators;
+use crate::storage::{self, GuildSettings, HandedOffSession, Match, Round, ScheduledGame, StorageResult};
+use crate::stream;
+use crate::theme::{self, Palette, Theme};
+use crate::titles;
+use crate::transport::Transport;
+
+/// How long a game may last before it expires, unless the guild has chosen another timeout with `/setup`.
+pub const TIMEOUT: Duration = Duration::from_secs(60 * 5);
+
+/// How long before the end of the turn timer the player who has not moved yet is warned, unless the guild has chosen.
+pub const AFK_WARNING: Duration = Duration::from_secs(30);
+
+/// The longest delay a game can be scheduled with.
+const MAX_SCHEDULE_DELAY: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// How long the token of an interaction can be used to edit its response, with a minute to spare.
+const INTERACTION_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 14);
+
+/// How many times an edit of the game message is tried before the game is posted again.
+const EDIT_ATTEMPTS: u32 = 3;
+
+/// How long the players of a finished game can react to its result to start a rematch.
+pub const REMATCH_WINDOW: Duration = Duration::from_secs(60 * 2);
+
+lazy_static! {
+    /// The games that can still be rematched, keyed by the message showing their result.
+    static ref REMATCHES: Mutex<HashMap<MessageId, Rematch>> = Mutex::new(HashMap::new());
+}
+
+/// A finished game whose players can start a rematch by reacting to its result with the emoji.
+struct Rematch {
+    session: Session,
+    emoji: ReactionType,
+}
+
+/// Fills a message builder with a [`GameMessage`]; the builders share these methods without sharing a trait.
+macro_rules! fill {
+    ($builder:expr, $message:expr) => {{
+        let GameMessage { content, embed, row } = $message;
+
+        $builder
+            .content(content)
+            .set_embed(embed)
+            .components(|comp| {
+                if let Some(row) = row {
+                    comp.add_action_row(row);
+                }
+
+                comp
+            })
+    }};
+}
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.rps")
+        .create_option(|sub| {
+            sub
+                .localized("commands.rps.play")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.rps.opponent")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.rps.play.ladder")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.rps.schedule")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.rps.opponent")
+                        .kind(CommandOptionType::User)
+                        .required(true)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.rps.schedule.time")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                })
+        })
+}
+
+/// How long a game may last in the guild with the settings.
+pub fn timeout(settings: &GuildSettings) -> Duration {
+    settings.game_timeout.map_or(TIMEOUT, Duration::from_secs)
+}
+
+/// Whether the moves of the games in the guild with the settings are typed into a form, so that the onlookers
+/// cannot tell which button has been pressed.
+fn modal_moves(settings: &GuildSettings) -> bool {
+    settings.modal_moves.unwrap_or(false)
+}
+
+/// Builds the signed custom ID of a button of the session, valid for its current turn only.
+pub fn custom_id(session: &Session, action: &str) -> String {
+    custom_ids::encode(RPS, session.id, session.turn, action)
+}
+
+/// Encodes everything needed to rebuild the session besides its ID, as
+/// `rps:<starter>:<opponent>:<round>:<turn>:<locale>:<state>`, followed by `:l` for ladder challenges,
+/// for the footer of the game message.
+/// The move of the starter is masked with a secret derived from the session and the turn.
+fn snapshot(session: &Session) -> String {
+    let state = match session.state {
+        State::Invited => "i".to_string(),
+        State::StarterTurn => "s".to_string(),
+        State::OpponentTurn { starter_move } => {
+            let index = MOVES.iter().position(|name| *name == starter_move).unwrap_or_default() as u8;
+            let mask = custom_ids::mask(&format!("{}:{}", session.id, session.turn), MOVES.len() as u8);
+
+            format!("o{}", (index + mask) % MOVES.len() as u8)
+        },
+    };
+
+    format!(
+        "{}:{}:{}:{}:{}:{}:{}{}",
+        RPS,
+        session.starter.id,
+        session.opponent.id,
+        session.round,
+        session.turn,
+        session.locale,
+        state,
+        if session.ladder { ":l" } else { "" },
+    )
+}
+
+/// The session as encoded by [`snapshot`], before its users have been fetched.
+struct Snapshot {
+    starter_id: UserId,
+    opponent_id: UserId,
+    round: usize,
+    turn: u32,
+    locale: String,
+    state: State,
+    ladder: bool,
+}
+
+fn parse_snapshot(session_id: u64, text: &str) -> Option<Snapshot> {
+    let mut parts = text.split(':');
+
+    if parts.next() != Some(RPS) {
+        return None;
+    }
+
+    let starter_id = UserId(parts.next()?.parse().ok()?);
+    let opponent_id = UserId(parts.next()?.parse().ok()?);
+    let round = parts.next()?.parse().ok()?;
+    let turn = parts.next()?.parse().ok()?;
+    let locale = parts.next()?.to_string();
+
+    let state = match parts.next()? {
+        "i" => State::Invited,
+        "s" => State::StarterTurn,
+        masked => {
+            let masked = masked.strip_prefix('o')?.parse::<u8>().ok().filter(|masked| (*masked as usize) < MOVES.len())?;
+            let mask = custom_ids::mask(&format!("{}:{}", session_id, turn), MOVES.len() as u8);
+            let index = (masked + MOVES.len() as u8 - mask) % MOVES.len() as u8;
+
+            State::OpponentTurn { starter_move: MOVES[index as usize] }
+        },
+    };
+
+    let ladder = parts.next() == Some("l");
+
+    Some(Snapshot { starter_id, opponent_id, round, turn, locale, state, ladder })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let settings = theme::settings(cmd.guild_id);
+    let palette = Palette::from(&settings);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    if flags::maintenance() {
+        let _ = cmd.create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|msg| {
+                    msg
+                        .ephemeral(true)
+                        .embed(|embed| {
+                            embed
+                                .author(|a| a.name(tr!(locale, "embeds.maintenance")))
+                                .color(palette.warning)
+                                .description(tr!(locale, "rps.maintenance"))
+                        })
+                })
+        }).await;
+
+        return;
+    }
+
+    if let Some(games_channel_id) = settings.games_channel_id.filter(|id| *id != cmd.channel_id.0) {
+        let description = tr!(locale, "setup.wrong_channel", channel = ChannelId(games_channel_id).mention());
+
+        reply_failure(ctx, cmd, &palette, &description).await;
+
+        return;
+    }
+
+    if let Some(permissions) = commands::missing_permissions(cmd, locale) {
+        reply_failure(ctx, cmd, &palette, &tr!(locale, "rps.missing_permissions", permissions = permissions)).await;
+
+        return;
+    }
+
+    let subcommand = &cmd.data.options[0];
+
+    let opponent = subcommand.options.iter()
+        .find(|option| option.name == "opponent")
+        .and_then(|option| match &option.resolved {
+            Some(CommandDataOptionValue::User(opponent, _)) => Some(opponent),
+            _ => None,
+        });
+
+    let opponent = match opponent {
+        Some(opponent) => opponent,
+        None => return,
+    };
+
+    if opponent.bot || opponent.id == cmd.user.id {
+        reply_failure(ctx, cmd, &palette, &tr!(locale, "rps.invalid_opponent")).await;
+
+        return;
+    }
+
+    // A block list that cannot be read does not prevent anyone from playing
+    let blocked = storage::is_blocked(opponent.id.0, cmd.user.id.0).unwrap_or_else(|err| {
+        error!("The block list could not have been read: {:?}", err);
+
+        false
+    });
+
+    if blocked {
+        reply_failure(ctx, cmd, &palette, &tr!(locale, "blocks.declined", user = opponent.mention())).await;
+
+        return;
+    }
+
+    match subcommand.name.as_str() {
+        "play" => play(ctx, cmd, opponent, &subcommand.options, locale, &settings).await,
+        "schedule" => schedule(ctx, cmd, opponent, &subcommand.options, locale).await,
+        _ => {}
+    }
+}
+
+async fn play(
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    opponent: &User,
+    options: &[CommandDataOption],
+    locale: &str,
+    settings: &GuildSettings,
+) {
+    let palette = Palette::from(settings);
+    let starter = &cmd.user;
+
+    let ladder = options.iter()
+        .any(|option| option.name == "ladder" && matches!(option.resolved, Some(CommandDataOptionValue::Boolean(true))));
+
+    if ladder {
+        if let Err(reason) = ladder::check_challenge(cmd.guild_id, starter, opponent, locale) {
+            reply_failure(ctx, cmd, &palette, &reason).await;
+
+            return;
+        }
+    }
+
+    let session = Session {
+        id: cmd.id.0,
+        starter: starter.clone(),
+        opponent: opponent.clone(),
+        guild_id: cmd.guild_id,
+        channel_id: cmd.channel_id,
+        message_id: None,
+        token: Some(cmd.token.clone()),
+        started_at: Timestamp::now(),
+        locale: locale.to_string(),
+        round: 1,
+        turn: 0,
+        turn_started_at: Instant::now(),
+        state: State::Invited,
+        ladder,
+        moves: Vec::new(),
+        rivalry: None,
+    };
+
+    match sessions::start_or_queue(session.clone(), queues_games(settings)) {
+        Start::Started => {},
+        Start::Queued(position) => {
+            info!(opponent = %opponent.id, position, "The game has been queued");
+
+            let description = tr!(
+                locale,
+                "queue.waiting",
+                starter = starter.mention(),
+                opponent = opponent.mention(),
+                position = position,
+            );
+
+            let _ = cmd.create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|msg| {
+                        msg.embed(|embed| {
+                            embed
+                                .author(|a| a.name(tr!(locale, "embeds.queue")))
+                                .color(palette.confirmation)
+                                .description(description)
+                        })
+                    })
+            }).await;
+
+            return;
+        },
+        Start::Busy => {
+            let reason = if sessions::has_pending_invitation(starter.id) {
+                "rps.pending_invitation"
+            } else if sessions::is_queued(starter.id) || sessions::is_queued(opponent.id) {
+                "queue.already_waiting"
+            } else {
+                "rps.already_playing"
+            };
+
+            reply_failure(ctx, cmd, &palette, &tr!(locale, reason)).await;
+
+            return;
+        },
+    }
+
+    let message = invitation(&session, &Presentation::load(&session, None, settings).await, opponent.mention().to_string());
+
+    if cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| fill!(msg, message))
+    }).await.is_err() {
+        sessions::end(cmd.id.0);
+
+        return;
+    }
+
+    metrics::SESSIONS_STARTED.with_label_values(&[RPS]).inc();
+
+    Span::current().record("session", cmd.id.0);
+    reporting::set_session(cmd.id.0, "invited");
+
+    info!(opponent = %opponent.id, "The invitation has been sent");
+
+    // The message ID is only needed to end the session if the message gets deleted
+    if let Ok(response) = cmd.get_interaction_response(&ctx.http).await {
+        sessions::update(cmd.id.0, |session| session.message_id = Some(response.id));
+
+        tokio::spawn(notify_opponent(ctx.clone(), cmd.clone(), opponent.clone(), response.id));
+        tokio::spawn(show_rivalry(ctx.clone(), cmd.id.0));
+    }
+
+    tokio::spawn(expire(ctx.clone(), cmd.id.0, timeout(settings)));
+}
+
+/// Books a game for later, which [`crate::scheduler`] starts with [`start_scheduled`].
+async fn schedule(
+    ctx: &Context,
+    cmd: &ApplicationCommandInteraction,
+    opponent: &User,
+    options: &[CommandDataOption],
+    locale: &str,
+) {
+    let palette = Palette::for_guild(cmd.guild_id);
+
+    // The invitation is posted as a regular message, which needs a channel the bot is a member of
+    let guild_id = match cmd.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(locale, "schedule.guild_only")).await;
+
+            return;
+        },
+    };
+
+    let time = options.iter()
+        .find(|option| option.name == "time")
+        .and_then(|option| match &option.resolved {
+            Some(CommandDataOptionValue::String(time)) => Some(time.as_str()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let delay = match parse_delay(time) {
+        Some(delay) => delay,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(locale, "schedule.invalid_time", value = time)).await;
+
+            return;
+        },
+    };
+
+    let game = ScheduledGame {
+        id: cmd.id.0,
+        guild_id: guild_id.0,
+        channel_id: cmd.channel_id.0,
+        starter_id: cmd.user.id.0,
+        opponent_id: opponent.id.0,
+        locale: locale.to_string(),
+        starts_at: Timestamp::now().unix_timestamp() + delay.as_secs() as i64,
+    };
+
+    if let Err(err) = storage::save_scheduled_game(&game) {
+        error!("The scheduled game could not have been saved: {:?}", err);
+
+        reply_failure(ctx, cmd, &palette, &tr!(locale, "schedule.failed")).await;
+
+        return;
+    }
+
+    info!(opponent = %opponent.id, starts_at = game.starts_at, "The game has been scheduled");
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .content(opponent.mention())
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(locale, "embeds.schedule")))
+                            .color(palette.confirmation)
+                            .description(tr!(
+                                locale,
+                                "schedule.scheduled",
+                                starter = cmd.user.mention(),
+                                opponent = opponent.mention(),
+                                time = format!("<t:{}:R>", game.starts_at),
+                            ))
+                    })
+            })
+    }).await;
+}
+
+/// Parses a delay such as `30m`, `2h`, or `1d12h`, from one minute up to [`MAX_SCHEDULE_DELAY`].
+fn parse_delay(text: &str) -> Option<Duration> {
+    let mut seconds = 0;
+    let mut number = String::new();
+
+    for char in text.trim().to_lowercase().chars() {
+        if char.is_ascii_digit() {
+            number.push(char);
+
+            continue;
+        }
+
+        let unit = match char {
+            'd' => 60 * 60 * 24,
+            'h' => 60 * 60,
+            'm' => 60,
+            _ => return None,
+        };
+
+        seconds = number.parse::<u64>().ok()?.checked_mul(unit)?.checked_add(seconds)?;
+        number.clear();
+    }
+
+    let delay = Duration::from_secs(seconds);
+
+    (number.is_empty() && delay >= Duration::from_secs(60) && delay <= MAX_SCHEDULE_DELAY).then_some(delay)
+}
+
+/// Posts the invitation of a scheduled game in the channel it has been booked in, mentioning both players.
+pub async fn start_scheduled(ctx: &Context, game: ScheduledGame) {
+    // The opponent may have blocked the starter since the game has been booked
+    if let Ok(true) = storage::is_blocked(game.opponent_id, game.starter_id) {
+        info!(session = game.id, "The scheduled game has been dropped, as the opponent has blocked the starter");
+
+        return;
+    }
+
+    let guild_id = Some(GuildId(game.guild_id));
+    let channel_id = ChannelId(game.channel_id);
+
+    let locale = game.locale.as_str();
+
+    let (starter, opponent) = match (UserId(game.starter_id).to_user(ctx).await, UserId(game.opponent_id).to_user(ctx).await) {
+        (Ok(starter), Ok(opponent)) => (starter, opponent),
+        (Err(err), _) | (_, Err(err)) => {
+            error!(session = game.id, "The players of the scheduled game could not have been fetched: {:?}", err);
+
+            return;
+        },
+    };
+
+    let content = tr!(locale, "schedule.reminder", starter = starter.mention(), opponent = opponent.mention());
+    let busy = tr!(locale, "schedule.busy", starter = starter.mention(), opponent = opponent.mention());
+
+    let session = Session {
+        id: game.id,
+        starter,
+        opponent,
+        guild_id,
+        channel_id,
+        message_id: None,
+        token: None,
+        started_at: Timestamp::now(),
+        locale: game.locale.clone(),
+        round: 1,
+        turn: 0,
+        turn_started_at: Instant::now(),
+        state: State::Invited,
+        ladder: false,
+        moves: Vec::new(),
+        rivalry: None,
+    };
+
+    post_invitation(ctx, session, content, busy).await;
+}
+
+/// Posts the invitation of a game the bot starts by itself rather than in response to a command,
+/// or a warning with the `busy` description if either player is already playing.
+async fn post_invitation(ctx: &Context, session: Session, content: String, busy: String) {
+    let settings = theme::settings(session.guild_id);
+    let palette = Palette::from(&settings);
+    let locale = session.locale.as_str();
+    let channel_id = session.channel_id;
+
+    let failure = if flags::maintenance() {
+        Some(tr!(locale, "rps.maintenance"))
+    } else if !sessions::start(session.clone()) {
+        Some(busy)
+    } else {
+        None
+    };
+
+    if let Some(description) = failure {
+        let _ = channel_id.send_message(&ctx.http, |msg| {
+            msg
+                .content(content)
+                .embed(|embed| {
+                    embed
+                        .author(|a| a.name(tr!(locale, "embeds.warning")))
+                        .color(palette.warning)
+                        .description(description)
+                })
+        }).await;
+
+        return;
+    }
+
+    let message = invitation(&session, &Presentation::load(&session, None, &settings).await, content);
+
+    match channel_id.send_message(&ctx.http, |msg| fill!(msg, message)).await {
+        Ok(message) => {
+            sessions::update(session.id, |session| session.message_id = Some(message.id));
+
+            metrics::SESSIONS_STARTED.with_label_values(&[RPS]).inc();
+            info!(session = session.id, "The invitation has been posted");
+
+            tokio::spawn(show_rivalry(ctx.clone(), session.id));
+
+            tokio::spawn(expire(ctx.clone(), session.id, timeout(&settings)));
+        },
+        Err(err) => {
+            sessions::end(session.id);
+
+            error!(session = session.id, "The invitation could not have been posted: {:?}", err);
+        },
+    }
+}
+
+/// Starts the games queued in the channel once the game in progress there has ended; a queued game whose players
+/// have started another one elsewhere in the meantime is dropped with a warning, and the next one is started instead.
+pub fn start_queued(ctx: &Context, channel_id: ChannelId) {
+    tokio::spawn(post_queued(ctx.clone(), channel_id));
+}
+
+async fn post_queued(ctx: Context, channel_id: ChannelId) {
+    while let Some(session) = sessions::next_queued(channel_id) {
+        let locale = session.locale.clone();
+        let content = tr!(&locale, "queue.ready", starter = session.starter.mention(), opponent = session.opponent.mention());
+        let busy = tr!(&locale, "queue.busy", starter = session.starter.mention(), opponent = session.opponent.mention());
+
+        info!(session = session.id, "The queued game is starting");
+
+        // The game has only started now, which its timeout and the lifetime of its message count from
+        post_invitation(&ctx, Session { started_at: Timestamp::now(), ..session }, content, busy).await;
+    }
+}
+
+/// Whether a single game may be in progress in every channel of the guild with the settings, the others being queued.
+fn queues_games(settings: &GuildSettings) -> bool {
+    settings.queue_games.unwrap_or(false)
+}
+
+/// Lets the players rematch the game by reacting to its result for a while, if its guild has chosen a rematch emoji
+/// and the bot has the [`REMATCH_PERMISSIONS`] in its channel, as far as Discord has sent them with the press.
+fn offer_rematch(ctx: &Context, session: &Session, settings: &GuildSettings, permissions: Option<Permissions>) {
+    if !config::get().gateway.rematch_reactions {
+        return;
+    }
+
+    if permissions.is_some_and(|permissions| !permissions.contains(REMATCH_PERMISSIONS)) {
+        info!(session = session.id, "No rematch has been offered due to missing permissions");
+
+        return;
+    }
+
+    let emoji = settings.rematch_emoji.as_deref()
+        .and_then(|emoji| ReactionType::try_from(emoji).ok());
+
+    let (emoji, message_id) = match (emoji, session.message_id) {
+        (Some(emoji), Some(message_id)) => (emoji, message_id),
+        _ => return,
+    };
+
+    REMATCHES.lock().unwrap().insert(message_id, Rematch { session: session.clone(), emoji: emoji.clone() });
+
+    let ctx = ctx.clone();
+    let channel_id = session.channel_id;
+
+    tokio::spawn(async move {
+        // The reaction of the bot shows the players which emoji to react with
+        let _ = channel_id.create_reaction(&ctx.http, message_id, emoji).await;
+
+        tokio::time::sleep(REMATCH_WINDOW).await;
+
+        REMATCHES.lock().unwrap().remove(&message_id);
+    });
+}
+
+/// Starts a rematch when either player of a recently finished game reacts to its result with the rematch emoji,
+/// inviting the other player in the name of the one who has reacted.
+pub async fn handle_reaction(ctx: &Context, reaction: &Reaction) {
+    let user_id = match reaction.user_id {
+        Some(user_id) => user_id,
+        None => return,
+    };
+
+    let rematch = {
+        let mut rematches = REMATCHES.lock().unwrap();
+
+        match rematches.get(&reaction.message_id) {
+            Some(rematch) if rematch.session.is_participant(user_id) && same_emoji(&rematch.emoji, &reaction.emoji) => {
+                rematches.remove(&reaction.message_id)
+            },
+            _ => None,
+        }
+    };
+
+    let finished = match rematch {
+        Some(rematch) => rematch.session,
+        None => return,
+    };
+
+    let (starter, opponent) = if finished.starter.id == user_id {
+        (finished.starter, finished.opponent)
+    } else {
+        (finished.opponent, finished.starter)
+    };
+
+    if let Ok(true) = storage::is_blocked(opponent.id.0, starter.id.0) {
+        return;
+    }
+
+    let locale = i18n::user_locale(starter.id, &finished.locale);
+
+    let content = tr!(&locale, "rematch.requested", starter = starter.mention(), opponent = opponent.mention());
+    let busy = tr!(&locale, "rematch.busy", starter = starter.mention(), opponent = opponent.mention());
+
+    // The result message is only rematched once, so its ID is free to identify the new session
+    let session = Session {
+        id: reaction.message_id.0,
+        starter,
+        opponent,
+        guild_id: finished.guild_id,
+        channel_id: finished.channel_id,
+        message_id: None,
+        token: None,
+        started_at: Timestamp::now(),
+        locale,
+        round: 1,
+        turn: 0,
+        turn_started_at: Instant::now(),
+        state: State::Invited,
+        ladder: false,
+        moves: Vec::new(),
+        rivalry: None,
+    };
+
+    post_invitation(ctx, session, content, busy).await;
+}
+
+/// Compares custom emojis by ID only, as the reactions carry their current name rather than the stored one.
+fn same_emoji(a: &ReactionType, b: &ReactionType) -> bool {
+    match (a, b) {
+        (ReactionType::Custom { id: a, .. }, ReactionType::Custom { id: b, .. }) => a == b,
+        (ReactionType::Unicode(a), ReactionType::Unicode(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// The invitation to the game, with the buttons answering or withdrawing it.
+fn invitation(session: &Session, presentation: &Presentation, content: String) -> GameMessage {
+    let locale = session.locale.as_str();
+
+    let embed = invitation_embed(session, presentation);
+
+    let mut row = CreateActionRow::default();
+
+    row
+        .create_button(|button| {
+            button
+                .label(tr!(locale, "rps.accept"))
+                .custom_id(custom_id(session, "play"))
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|button| {
+            button
+                .label(tr!(locale, "rps.deny"))
+                .custom_id(custom_id(session, "deny"))
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|button| {
+            button
+                .label(tr!(locale, "rps.cancel"))
+                .custom_id(custom_id(session, "cancel"))
+                .style(ButtonStyle::Secondary)
+        });
+
+    GameMessage { content, embed, row: Some(row) }
+}
+
+fn invitation_embed(session: &Session, presentation: &Presentation) -> CreateEmbed {
+    let Theme { palette, layout, .. } = &presentation.theme;
+    let locale = session.locale.as_str();
+
+    let rivalry = session.rivalry.map(|(starter_wins, opponent_wins)| {
+        let (leader, wins, losses) = match starter_wins.cmp(&opponent_wins) {
+            Ordering::Greater => (&session.starter, starter_wins, opponent_wins),
+            Ordering::Less => (&session.opponent, opponent_wins, starter_wins),
+            Ordering::Equal => return tr!(locale, "rps.rivalry_tied", wins = starter_wins),
+        };
+
+        tr!(locale, "rps.rivalry_lead", user = leader.mention(), wins = wins, losses = losses)
+    });
+
+    let mut embed = CreateEmbed::default();
+
+    if layout.is_compact() {
+        let mut description = tr!(locale, "compact.invitation", user = session.starter.mention());
+
+        if let Some(rivalry) = rivalry {
+            description = format!("{}\n{}", description, rivalry);
+        }
+
+        embed.description(description);
+    } else {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.confirmation")))
+            .description(tr!(locale, "rps.invitation", user = presentation.starter));
+
+        if let Some(rivalry) = rivalry {
+            embed.field(tr!(locale, "rps.rivalry"), rivalry, false);
+        }
+    }
+
+    embed
+        .color(palette.confirmation)
+        .footer(|footer| footer.text(snapshot(session)));
+
+    embed
+}
+
+/// The embed of the game message as it currently stands, for the mirrors of `/spectate`.
+pub fn spectator_view(session: &Session) -> CreateEmbed {
+    let presentation = Presentation::read(session, None, &theme::settings(session.guild_id));
+
+    match current_player(session) {
+        Some(player) => round_embed(session, &presentation, player),
+        None => invitation_embed(session, &presentation),
+    }
+}
+
+/// Adds the record of the players against each other to the invitation once it has been read,
+/// so the invitation is never delayed by the database; nothing is shown if they have never played each other.
+async fn show_rivalry(ctx: Context, session_id: u64) {
+    let players = sessions::update(session_id, |session| (session.starter.id, session.opponent.id, session.guild_id));
+
+    let (starter_id, opponent_id, guild_id) = match players {
+        Some(players) => players,
+        None => return,
+    };
+
+    let results = match storage::head_to_head(starter_id.0, opponent_id.0, guild_id.map(|guild_id| guild_id.0)) {
+        Ok(history) => history.results,
+        Err(err) => {
+            error!(session = session_id, "The head-to-head record could not have been read: {:?}", err);
+
+            return;
+        },
+    };
+
+    if results.is_empty() {
+        return;
+    }
+
+    let starter_wins = results.iter().filter(|won| **won).count();
+    let rivalry = (starter_wins, results.len() - starter_wins);
+
+    // Only the invitation shows it, which must not overwrite a game that has started in the meantime
+    let session = sessions::update(session_id, |session| {
+        session.rivalry = Some(rivalry);
+
+        (session.state == State::Invited).then(|| session.clone())
+    });
+
+    if let Some(session) = session.flatten() {
+        let presentation = Presentation::load(&session, None, &theme::settings(session.guild_id)).await;
+        let embed = invitation_embed(&session, &presentation);
+
+        if let Some(token) = usable_token(&session) {
+            let mut response = EditInteractionResponse::default();
+
+            response.set_embed(embed);
+
+            let map = Value::from(json::hashmap_to_json_map(response.0));
+
+            let _ = ctx.http.edit_original_interaction_response(token, &map).await;
+        } else if let Some(message_id) = session.message_id {
+            let _ = session.channel_id.edit_message(&ctx.http, message_id, |msg| msg.set_embed(embed)).await;
+        }
+    }
+}
+
+/// Sends the opponent a direct message linking to the invitation if they have opted in with `/notifications`.
+async fn notify_opponent(ctx: Context, cmd: ApplicationCommandInteraction, opponent: User, message_id: MessageId) {
+    let preferences = match storage::user_preferences(opponent.id.0) {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            return;
+        },
+    };
+
+    if !preferences.dm_invitations {
+        return;
+    }
+
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = preferences.locale.unwrap_or_else(|| cmd.locale.clone());
+    let locale = locale.as_str();
+
+    if let Err(err) = opponent.direct_message(&ctx, |msg| {
+        msg
+            .embed(|embed| {
+                embed
+                    .author(|a| a.name(tr!(locale, "embeds.confirmation")))
+                    .color(palette.confirmation)
+                    .description(tr!(
+                        locale,
+                        "notifications.invitation",
+                        user = cmd.user.mention(),
+                        channel = cmd.channel_id.mention(),
+                    ))
+            })
+            .components(|comp| {
+                comp.create_action_row(|row| {
+                    row.create_button(|button| {
+                        button
+                            .style(ButtonStyle::Link)
+                            .label(tr!(locale, "notifications.open"))
+                            .url(message_id.link(cmd.channel_id, cmd.guild_id))
+                    })
+                })
+            })
+    }).await {
+        // Most likely the opponent does not accept direct messages from the server
+        info!(opponent = %opponent.id, "The invitation could not have been sent as a direct message: {:?}", err);
+    }
+}
+
+/// Ends the session once it has timed out, unless the game has been finished in the meantime.
+async fn expire(ctx: Context, session_id: u64, delay: Duration) {
+    tokio::time::sleep(delay).await;
+
+    let session = match sessions::end(session_id) {
+        Some(session) => session,
+        None => return,
+    };
+
+    let settings = theme::settings(session.guild_id);
+    let palette = Palette::from(&settings);
+
+    metrics::SESSIONS_ENDED.with_label_values(&[RPS, "expired"]).inc();
+    info!(session = session.id, "The game has ended: expired");
+
+    log_end(&ctx, &session, &settings, "expired", None).await;
+
+    let message = ending(&session, &palette, "expired");
+
+    spectators::mirror(&ctx, &session, message.embed.clone());
+
+    edit_game_message(ctx.http.as_ref(), &session, message).await;
+
+    start_queued(&ctx, session.channel_id);
+}
+
+/// Ends the game on behalf of the operators of the bot, e.g. through the admin API, returning whether it was in progress.
+pub async fn force_end(ctx: &Context, session_id: u64) -> bool {
+    end_for(ctx, session_id, "moderated").await
+}
+
+/// Ends the game that has outlasted `max_session_minutes` without expiring, returning whether it was in progress.
+pub async fn end_stale(ctx: &Context, session_id: u64) -> bool {
+    end_for(ctx, session_id, "stale").await
+}
+
+/// Ends the game for a reason its players had no part in, explained in its message by `rps.<reason>`.
+async fn end_for(ctx: &Context, session_id: u64, reason: &str) -> bool {
+    let session = match sessions::end(session_id) {
+        Some(session) => session,
+        None => return false,
+    };
+
+    let settings = theme::settings(session.guild_id);
+    let palette = Palette::from(&settings);
+
+    metrics::SESSIONS_ENDED.with_label_values(&[RPS, reason]).inc();
+    info!(session = session.id, "The game has ended: {}", reason);
+
+    log_end(ctx, &session, &settings, reason, None).await;
+
+    let message = ending(&session, &palette, reason);
+
+    spectators::mirror(ctx, &session, message.embed.clone());
+
+    edit_game_message(ctx.http.as_ref(), &session, message).await;
+
+    start_queued(ctx, session.channel_id);
+
+    true
+}
+
+/// The game message of a game ended without a winner, explained by `rps.<reason>`.
+fn ending(session: &Session, palette: &Palette, reason: &str) -> GameMessage {
+    let locale = session.locale.as_str();
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.warning")))
+        .color(palette.warning)
+        .description(tr!(locale, &format!("rps.{}", reason)));
+
+    GameMessage { content: String::new(), embed, row: None }
+}
+
+/// Concedes the game the user is playing, crediting the win to the other player as if they had won it,
+/// and returns the other player, or `None` if the user is not playing any game.
+pub async fn forfeit(ctx: &Context, user: &User) -> Option<User> {
+    let session = sessions::end_played_by(user.id)?;
+
+    Some(concede(ctx, session, user, "forfeited").await)
+}
+
+/// Settles the game that has been ended as lost by `loser`, for the reason it is explained with, and returns the winner.
+async fn concede(ctx: &Context, session: Session, loser: &User, reason: &'static str) -> User {
+    let starter_wins = session.opponent.id == loser.id;
+    let winner = if starter_wins { &session.starter } else { &session.opponent };
+    let settings = theme::settings(session.guild_id);
+
+    metrics::SESSIONS_ENDED.with_label_values(&[RPS, reason]).inc();
+    info!(session = session.id, "The game has ended: {}", reason);
+
+    record_and_crown(ctx, &session, starter_wins).await;
+
+    if session.ladder && starter_wins {
+        ladder::climb(&session);
+    }
+
+    log_end(ctx, &session, &settings, reason, None).await;
+
+    let step = Step::Forfeited { starter_wins, reason };
+    let message = render(&step, &session, loser, &Presentation::load(&session, Some(&step), &settings).await);
+
+    spectators::mirror(ctx, &session, message.embed.clone());
+
+    edit_game_message(ctx.http.as_ref(), &session, message).await;
+
+    if stream::enabled(&settings) {
+        stream::record_win(ctx, &session, winner, None).await;
+    }
+
+    start_queued(ctx, session.channel_id);
+
+    winner.clone()
+}
+
+/// Times the current turn, if the guild has a turn timer: the player who has not moved for most of it is warned
+/// once, then forfeits the game when it runs out. A turn restored after a restart only has the rest of its time left.
+fn watch_turn(ctx: &Context, session: &Session, settings: &GuildSettings) {
+    let remaining = match settings.turn_timeout {
+        Some(timeout) => Duration::from_secs(timeout).saturating_sub(session.turn_started_at.elapsed()),
+        None => return,
+    };
+
+    let warning = settings.afk_warning.map_or(AFK_WARNING, Duration::from_secs).min(remaining);
+
+    tokio::spawn(time_turn(ctx.clone(), session.id, session.turn, remaining - warning, warning));
+}
+
+async fn time_turn(ctx: Context, session_id: u64, turn: u32, until_warning: Duration, warning: Duration) {
+    tokio::time::sleep(until_warning).await;
+
+    // The turn is over once anything has been pressed since
+    let session = match sessions::update(session_id, |session| (session.turn == turn).then(|| session.clone())) {
+        Some(Some(session)) => session,
+        _ => return,
+    };
+
+    let player = match current_player(&session) {
+        Some(player) => player.clone(),
+        None => return,
+    };
+
+    let locale = session.locale.as_str();
+    let content = tr!(locale, "rps.afk_warning", user = player.mention(), seconds = warning.as_secs());
+
+    // A restored turn may have run out already, which leaves nothing to warn about
+    let warning_message = if warning.is_zero() {
+        None
+    } else {
+        session.channel_id.send_message(&ctx.http, |msg| msg.content(content)).await.ok()
+    };
+
+    tokio::time::sleep(warning).await;
+
+    if let Some(session) = sessions::end_if(session_id, |session| session.turn == turn) {
+        info!(session = session_id, player = %player.id, "The player has not moved in time");
+
+        concede(&ctx, session, &player, "afk").await;
+    }
+
+    // The warning is moot either way by now
+    if let Some(warning_message) = warning_message {
+        let _ = warning_message.delete(&ctx.http).await;
+    }
+}
+
+/// Tells the player, once and only to them, that their game has ended because its message has been deleted
+/// in bulk, which the bot cannot answer in the channel; sent after the response to their next interaction.
+pub async fn notify_purged(ctx: &Context, user: &User, locale: &str, guild_id: Option<GuildId>, token: &str) {
+    if !sessions::take_purged(user.id) {
+        return;
+    }
+
+    let palette = Palette::for_guild(guild_id);
+    let locale = i18n::user_locale(user.id, locale);
+
+    let mut followup = CreateInteractionResponseFollowup::default();
+
+    followup
+        .ephemeral(true)
+        .embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(&locale, "embeds.warning")))
+                .color(palette.warning)
+                .description(tr!(&locale, "rps.purged"))
+        });
+
+    let map = Value::from(json::hashmap_to_json_map(followup.0));
+
+    let _ = ctx.http.create_followup_message(token, &map).await;
+}
+
+/// Posts how the game has ended in the log channel of the guild, if it has one chosen with `/setup`.
+async fn log_end(ctx: &Context, session: &Session, settings: &GuildSettings, reason: &str, starter_wins: Option<bool>) {
+    let log_channel_id = match settings.log_channel_id {
+        Some(log_channel_id) => ChannelId(log_channel_id),
+        None => return,
+    };
+
+    let palette = Palette::from(settings);
+    let locale = session.locale.as_str();
+
+    let description = match starter_wins {
+        Some(starter_wins) => {
+            let (winner, loser) = if starter_wins {
+                (&session.starter, &session.opponent)
+            } else {
+                (&session.opponent, &session.starter)
+            };
+
+            tr!(locale, "logs.won", winner = winner.mention(), loser = loser.mention(), round = session.round)
+        },
+        None => tr!(
+            locale,
+            "logs.ended",
+            starter = session.starter.mention(),
+            opponent = session.opponent.mention(),
+            reason = tr!(locale, &format!("logs.{}", reason)),
+        ),
+    };
+
+    // The mentions are only in the embed, so nobody is pinged by the log
+    if let Err(err) = log_channel_id.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| embed.color(palette.success).description(description))
+    }).await {
+        error!("The game could not have been logged: {:?}", err);
+    }
+}
+
+/// Rebuilds a session the bot has lost (e.g. by restarting) from the snapshot in the footer of its message,
+/// unless the game would have expired by now or either player has started another one since.
+async fn restore(ctx: &Context, interaction: &MessageComponentInteraction, session_id: u64) {
+    let snapshot = interaction.message.embeds.first()
+        .and_then(|embed| embed.footer.as_ref())
+        .and_then(|footer| parse_snapshot(session_id, &footer.text));
+
+    let snapshot = match snapshot {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+
+    let settings = theme::settings(interaction.guild_id);
+
+    let elapsed = Timestamp::now().unix_timestamp() - interaction.message.timestamp.unix_timestamp();
+    let remaining = match timeout(&settings).checked_sub(Duration::from_secs(elapsed.max(0) as u64)) {
+        Some(remaining) => remaining,
+        None => return,
+    };
+
+    let (starter, opponent) = match (snapshot.starter_id.to_user(ctx).await, snapshot.opponent_id.to_user(ctx).await) {
+        (Ok(starter), Ok(opponent)) => (starter, opponent),
+        _ => return,
+    };
+
+    let session = Session {
+        id: session_id,
+        starter,
+        opponent,
+        guild_id: interaction.guild_id,
+        channel_id: interaction.channel_id,
+        message_id: Some(interaction.message.id),
+        token: Some(interaction.token.clone()),
+        started_at: interaction.message.timestamp,
+        locale: snapshot.locale,
+        round: snapshot.round,
+        turn: snapshot.turn,
+        // The game message is edited whenever a turn begins
+        turn_started_at: instant_at(interaction.message.edited_timestamp.unwrap_or(interaction.message.timestamp).unix_timestamp()),
+        state: snapshot.state,
+        ladder: snapshot.ladder,
+        moves: Vec::new(),
+        rivalry: None,
+    };
+
+    if sessions::start(session.clone()) {
+        info!("The session has been restored from its message");
+
+        tokio::spawn(expire(ctx.clone(), session_id, remaining));
+
+        watch_turn(ctx, &session, &settings);
+    }
+}
+
+/// The instant of the UNIX timestamp, which cannot be later than now.
+fn instant_at(timestamp: i64) -> Instant {
+    let elapsed = Duration::from_secs((Timestamp::now().unix_timestamp() - timestamp).max(0) as u64);
+
+    Instant::now().checked_sub(elapsed).unwrap_or_else(Instant::now)
+}
+
+/// Stores the games in progress for the next process to take over, once this one has disconnected,
+/// returning how many there were.
+pub fn hand_off() -> StorageResult<usize> {
+    let handed_off = sessions::end_all().iter()
+        .map(|session| HandedOffSession {
+            id: session.id,
+            snapshot: snapshot(session),
+            guild_id: session.guild_id.map(|guild_id| guild_id.0),
+            channel_id: session.channel_id.0,
+            message_id: session.message_id.map(|message_id| message_id.0),
+            token: session.token.clone(),
+            started_at: session.started_at.unix_timestamp(),
+            moves: session.moves.iter()
+                .map(|(starter_move, opponent_move)| format!("{}:{}", starter_move, opponent_move))
+                .collect::<Vec<_>>()
+                .join(","),
+            turn_started_at: Some(Timestamp::now().unix_timestamp() - session.turn_started_at.elapsed().as_secs() as i64),
+        })
+        .collect::<Vec<_>>();
+
+    storage::save_handed_off_sessions(&handed_off)?;
+
+    Ok(handed_off.len())
+}
+
+/// Takes over the games handed off by a previous process, which expire when they would have there;
+/// those that have run out in the meantime expire right away, so their messages do not keep dead buttons.
+pub async fn take_over(ctx: &Context) {
+    let handed_off = match storage::take_handed_off_sessions() {
+        Ok(handed_off) => handed_off,
+        Err(err) => {
+            error!("The handed off sessions could not have been read: {:?}", err);
+
+            return;
+        },
+    };
+
+    for handed_off in handed_off {
+        let snapshot = match parse_snapshot(handed_off.id, &handed_off.snapshot) {
+            Some(snapshot) => snapshot,
+            None => continue,
+        };
+
+        let (starter, opponent) = match (snapshot.starter_id.to_user(ctx).await, snapshot.opponent_id.to_user(ctx).await) {
+            (Ok(starter), Ok(opponent)) => (starter, opponent),
+            (Err(err), _) | (_, Err(err)) => {
+                error!(session = handed_off.id, "The players of a handed off session could not have been fetched: {:?}", err);
+
+                continue;
+            },
+        };
+
+        let moves = handed_off.moves.split(',')
+            .filter_map(|pair| {
+                let (starter_move, opponent_move) = pair.split_once(':')?;
+
+                Some((
+                    MOVES.into_iter().find(|name| *name == starter_move)?,
+                    MOVES.into_iter().find(|name| *name == opponent_move)?,
+                ))
+            })
+            .collect();
+
+        let guild_id = handed_off.guild_id.map(GuildId);
+        let started_at = Timestamp::from_unix_timestamp(handed_off.started_at).unwrap_or_else(|_| Timestamp::now());
+
+        let settings = theme::settings(guild_id);
+
+        let elapsed = Timestamp::now().unix_timestamp() - handed_off.started_at;
+        let remaining = timeout(&settings).saturating_sub(Duration::from_secs(elapsed.max(0) as u64));
+
+        let session = Session {
+            id: handed_off.id,
+            starter,
+            opponent,
+            guild_id,
+            channel_id: ChannelId(handed_off.channel_id),
+            message_id: handed_off.message_id.map(MessageId),
+            token: handed_off.token,
+            started_at,
+            locale: snapshot.locale,
+            round: snapshot.round,
+            turn: snapshot.turn,
+            turn_started_at: handed_off.turn_started_at.map_or_else(Instant::now, instant_at),
+            state: snapshot.state,
+            ladder: snapshot.ladder,
+            moves,
+            rivalry: None,
+        };
+
+        // Lost if a press has already restored it from its message, or either player has started another game since
+        if sessions::start(session.clone()) {
+            info!(session = handed_off.id, "The handed off session has been taken over");
+
+            tokio::spawn(expire(ctx.clone(), handed_off.id, remaining));
+
+            watch_turn(ctx, &session, &settings);
+        }
+    }
+}
+
+/// What a button press has changed in the game, which decides how the game message is updated.
+#[derive(Clone, Copy)]
+enum Step {
+    Accepted,
+    Denied,
+    Cancelled,
+    Moved,
+    Draw,
+    Won {
+        starter_wins: bool,
+        winner_move: &'static str,
+        loser_move: &'static str,
+    },
+    Terminated,
+    /// Ended by a moderator of the channel rather than by either player.
+    Moderated,
+    /// Conceded by either player, with `/forfeit` or by not moving in time (`afk`), which counts as a win
+    /// for the other one; `reason` picks the description, `rps.<reason>`.
+    Forfeited { starter_wins: bool, reason: &'static str },
+}
+
+/// Applies a button press to the session, or returns the resource key of the reason it is rejected.
+fn advance(session: &mut Session, user: &User, action: &str) -> Result<Step, &'static str> {
+    match action {
+        "play" | "deny" => {
+            if user.id != session.opponent.id {
+                return Err("rps.not_invited");
+            }
+
+            if session.state != State::Invited {
+                return Err("rps.outdated");
+            }
+
+            if action == "play" {
+                session.state = State::StarterTurn;
+
+                Ok(Step::Accepted)
+            } else {
+                Ok(Step::Denied)
+            }
+        },
+        "cancel" => {
+            if user.id != session.starter.id {
+                return Err("rps.not_starter");
+            }
+
+            if session.state != State::Invited {
+                return Err("rps.outdated");
+            }
+
+            Ok(Step::Cancelled)
+        },
+        "stop" => {
+            if !session.is_participant(user.id) {
+                return Err("rps.not_participant");
+            }
+
+            Ok(Step::Terminated)
+        },
+        // Only ever picked by `apply`, once the permissions of the user have been checked
+        "moderate" => Ok(Step::Moderated),
+        _ => {
+            let played = MOVES.into_iter().find(|name| *name == action).ok_or("rps.outdated")?;
+
+            check_turn(session, user)?;
+
+            let starter_turn = match session.state {
+                State::OpponentTurn { starter_move } => starter_move,
+                _ => {
+                    session.state = State::OpponentTurn { starter_move: played };
+
+                    return Ok(Step::Moved);
+                },
+            };
+
+            let opponent_turn = played;
+
+            session.moves.push((starter_turn, opponent_turn));
+
+            let step = match rules::wins(starter_turn, opponent_turn) {
+                Some(true) => Step::Won {
+                    starter_wins: true,
+                    winner_move: starter_turn,
+                    loser_move: opponent_turn,
+                },
+                Some(false) => Step::Won {
+                    starter_wins: false,
+                    winner_move: opponent_turn,
+                    loser_move: starter_turn,
+                },
+                None => {
+                    session.round += 1;
+                    session.state = State::StarterTurn;
+
+                    Step::Draw
+                },
+            };
+
+            Ok(step)
+        },
+    }
+}
+
+/// The player whose turn it is to move, or `None` while the invitation has not been answered.
+fn current_player(session: &Session) -> Option<&User> {
+    match session.state {
+        State::Invited => None,
+        State::StarterTurn => Some(&session.starter),
+        State::OpponentTurn { .. } => Some(&session.opponent),
+    }
+}
+
+/// Makes sure it is the turn of the user to move, or returns the resource key of the reason they cannot.
+fn check_turn(session: &Session, user: &User) -> Result<(), &'static str> {
+    let current_player = current_player(session).ok_or("rps.outdated")?;
+
+    if user.id != current_player.id {
+        return Err(if session.is_participant(user.id) {
+            "rps.not_your_turn"
+        } else {
+            "rps.not_participant"
+        });
+    }
+
+    Ok(())
+}
+
+/// Makes sure the interaction comes from the game message, as a signed custom ID is still valid when its component
+/// has been copied to another message (e.g. by replaying the interaction) or left on a message the game has moved from.
+fn check_origin(session: &Session, channel_id: ChannelId, message_id: Option<MessageId>) -> Result<(), &'static str> {
+    if channel_id != session.channel_id {
+        return Err("rps.foreign_message");
+    }
+
+    // The message may not be known yet, e.g. right after the invitation has been sent
+    match (session.message_id, message_id) {
+        (Some(expected), Some(actual)) if expected != actual => Err("rps.foreign_message"),
+        _ => Ok(()),
+    }
+}
+
+/// The interaction a game goes on through: a button of its message, or the form the moves are typed into
+/// in the guilds with modal moves, whose submission can update the message just as a button press does.
+#[derive(Clone, Copy)]
+enum Press<'a> {
+    Button(&'a MessageComponentInteraction),
+    Modal(&'a ModalSubmitInteraction),
+}
+
+impl<'a> Press<'a> {
+    fn id(self) -> InteractionId {
+        match self {
+            Press::Button(interaction) => interaction.id,
+            Press::Modal(interaction) => interaction.id,
+        }
+    }
+
+    fn token(self) -> &'a str {
+        match self {
+            Press::Button(interaction) => &interaction.token,
+            Press::Modal(interaction) => &interaction.token,
+        }
+    }
+
+    fn user(self) -> &'a User {
+        match self {
+            Press::Button(interaction) => &interaction.user,
+            Press::Modal(interaction) => &interaction.user,
+        }
+    }
+
+    fn locale(self) -> &'a str {
+        match self {
+            Press::Button(interaction) => &interaction.locale,
+            Press::Modal(interaction) => &interaction.locale,
+        }
+    }
+
+    fn guild_id(self) -> Option<GuildId> {
+        match self {
+            Press::Button(interaction) => interaction.guild_id,
+            Press::Modal(interaction) => interaction.guild_id,
+        }
+    }
+
+    fn channel_id(self) -> ChannelId {
+        match self {
+            Press::Button(interaction) => interaction.channel_id,
+            Press::Modal(interaction) => interaction.channel_id,
+        }
+    }
+
+    /// Whether the user can manage the messages of the channel, which lets them end the games they are not playing.
+    fn is_moderator(self) -> bool {
+        let member = match self {
+            Press::Button(interaction) => interaction.member.as_ref(),
+            Press::Modal(interaction) => interaction.member.as_ref(),
+        };
+
+        member
+            .and_then(|member| member.permissions)
+            .is_some_and(|permissions| permissions.manage_messages())
+    }
+
+    /// The permissions of the bot in the channel, if Discord has sent them.
+    fn app_permissions(self) -> Option<Permissions> {
+        match self {
+            Press::Button(interaction) => interaction.app_permissions,
+            Press::Modal(interaction) => interaction.app_permissions,
+        }
+    }
+
+    /// The message of the component, which is the one that has opened the form for a modal.
+    fn message_id(self) -> Option<MessageId> {
+        match self {
+            Press::Button(interaction) => Some(interaction.message.id),
+            Press::Modal(interaction) => interaction.message.as_ref().map(|message| message.id),
+        }
+    }
+
+    async fn respond<'b, F>(self, transport: &impl Transport, f: F) -> serenity::Result<()>
+    where
+        for<'c> F: FnOnce(&'c mut CreateInteractionResponse<'b>) -> &'c mut CreateInteractionResponse<'b>,
+    {
+        let mut response = CreateInteractionResponse::default();
+
+        f(&mut response);
+
+        transport.respond(self.id(), self.token(), &Value::from(json::hashmap_to_json_map(response.0))).await
+    }
+}
+
+/// Handles a press on any game button, wherever the game has been started and whatever has happened to the bot since.
+pub async fn handle_component(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let session_id = custom_id.session_id;
+
+    if !sessions::contains(session_id) {
+        restore(ctx, interaction, session_id).await;
+    }
+
+    // In modal mode, the button only opens the form, and the move is made once it is submitted
+    if custom_id.action == "move" {
+        open_move_form(ctx, interaction, custom_id).await;
+
+        return;
+    }
+
+    // The buttons of `/challenges` answer an invitation from another message than the game one
+    let (action, from_inbox) = match custom_id.action {
+        "accept" => ("play", true),
+        "decline" => ("deny", true),
+        action => (action, false),
+    };
+
+    apply(ctx, Press::Button(interaction), custom_id, |_, _| Ok(action), from_inbox).await;
+}
+
+/// Handles the move typed into the form of a game in modal mode, by its name in the language of either
+/// the player or the game.
+pub async fn handle_modal(ctx: &Context, interaction: &ModalSubmitInteraction, custom_id: CustomId<'_>) {
+    let text = interaction.data.components.iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) => Some(input.value.as_str()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let action = |session: &Session, interaction_locale: &str| {
+        parse_move(text, &[interaction_locale, &session.locale]).ok_or("rps.unknown_move")
+    };
+
+    apply(ctx, Press::Modal(interaction), custom_id, action, false).await;
+}
+
+/// Opens the form to type the move into, once it is certain the user can move, so they are not asked for a move
+/// that would be rejected anyway.
+async fn open_move_form(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let (settings, interaction_locale) = read_press(Press::Button(interaction), custom_id.session_id).await;
+
+    let result = sessions::update(custom_id.session_id, |session| {
+        if custom_id.turn != session.turn {
+            return Err("rps.outdated");
+        }
+
+        check_origin(session, interaction.channel_id, Some(interaction.message.id))?;
+        check_turn(session, &interaction.user)
+    });
+
+    if let Err(reason) = result.unwrap_or(Err("rps.inactive")) {
+        reject(ctx, Press::Button(interaction), &Palette::from(&settings), &interaction_locale, reason).await;
+
+        return;
+    }
+
+    // The form is signed for the same turn as the button, so a form submitted after the turn has passed is outdated
+    let form_id = custom_ids::encode(RPS, custom_id.session_id, custom_id.turn, "move");
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::Modal)
+            .interaction_response_data(|form| {
+                form
+                    .custom_id(form_id)
+                    .title(tr!(&interaction_locale, "rps.move_form.title"))
+                    .components(|comp| {
+                        comp.create_action_row(|row| {
+                            row.create_input_text(|input| {
+                                input
+                                    .custom_id("move")
+                                    .style(InputTextStyle::Short)
+                                    .label(tr!(&interaction_locale, "rps.move_form.label"))
+                                    .placeholder(tr!(&interaction_locale, "rps.move_form.placeholder"))
+                                    .max_length(32)
+                                    .required(true)
+                            })
+                        })
+                    })
+            })
+    }).await;
+}
+
+/// Reads a typed move, ignoring the case and the surrounding spaces.
+fn parse_move(text: &str, locales: &[&str]) -> Option<&'static str> {
+    let text = text.trim().to_lowercase();
+
+    MOVES.into_iter().find(|name| {
+        *name == text || locales.iter().any(|locale| tr!(locale, &format!("moves.{}", name)).to_lowercase() == text)
+    })
+}
+
+/// Reads what answering a press takes at once, off the runtime: the settings of the guild of the game, which
+/// are passed down rather than read again along the way, and the locale of the user.
+async fn read_press(press: Press<'_>, session_id: u64) -> (GuildSettings, String) {
+    // The buttons of `/challenges` may be pressed elsewhere than in the guild of the game
+    let guild_id = sessions::update(session_id, |session| session.guild_id).unwrap_or_else(|| press.guild_id());
+    let user_id = press.user().id;
+    let locale = press.locale().to_string();
+
+    off_runtime(move || (theme::settings(guild_id), i18n::user_locale(user_id, &locale))).await
+}
+
+/// Runs reads of the database off the runtime, carrying over a panic as if they had been run in place.
+async fn off_runtime<T: Send + 'static>(read: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(read).await.unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// Answers the interaction with the reason it has been rejected, only shown to the user.
+async fn reject(ctx: &Context, press: Press<'_>, palette: &Palette, interaction_locale: &str, reason: &str) {
+    let _ = press.respond(ctx.http.as_ref(), |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(interaction_locale, "embeds.failure")))
+                            .color(palette.failure)
+                            .description(tr!(interaction_locale, reason))
+                    })
+            })
+    }).await;
+}
+
+/// Applies the action picked from the session (and the locale of the user) to it and updates the game message
+/// accordingly, through the response to the interaction unless it has come from another message than the game one.
+async fn apply<'a>(
+    ctx: &Context,
+    press: Press<'_>,
+    custom_id: CustomId<'_>,
+    action: impl FnOnce(&Session, &str) -> Result<&'a str, &'static str>,
+    from_inbox: bool,
+) {
+    let session_id = custom_id.session_id;
+    let (settings, interaction_locale) = read_press(press, session_id).await;
+    let palette = Palette::from(&settings);
+
+    // The buttons of `/challenges` belong to another message than the game one by design
+    let origin = (!from_inbox).then(|| (press.channel_id(), press.message_id()));
+    let action = |session: &Session| action(session, &interaction_locale);
+
+    // A double click sends the button press twice, and the second one is acknowledged without being handled again
+    // rather than rejected as outdated; the moves of modal mode are told apart by their form instead
+    let pressed_id = match press {
+        Press::Button(interaction) => Some(interaction.data.custom_id.as_str()),
+        Press::Modal(_) => None,
+    };
+
+    let (step, session) = match transition(custom_id, press.user(), origin, press.is_moderator(), action) {
+        Ok(result) => result,
+        Err(_) if pressed_id.is_some_and(|pressed_id| sessions::is_duplicate_press(session_id, press.user().id, pressed_id)) => {
+            info!("Ignoring a duplicate button press");
+
+            let _ = press.respond(ctx.http.as_ref(), |response| {
+                response.kind(InteractionResponseType::DeferredUpdateMessage)
+            }).await;
+
+            return;
+        },
+        Err(reason) => {
+            reject(ctx, press, &palette, &interaction_locale, reason).await;
+
+            return;
+        },
+    };
+
+    if let Some(pressed_id) = pressed_id {
+        sessions::record_press(session_id, press.user().id, pressed_id);
+    }
+
+    let ended = match step {
+        Step::Denied => Some("denied"),
+        Step::Cancelled => Some("cancelled"),
+        Step::Won { .. } => Some("finished"),
+        Step::Terminated => Some("stopped"),
+        Step::Moderated => Some("moderator"),
+        _ => None,
+    };
+
+    if let Some(reason) = ended {
+        sessions::end(session_id);
+
+        metrics::SESSIONS_ENDED.with_label_values(&[RPS, reason]).inc();
+        info!("The game has ended: {}", reason);
+
+        let starter_wins = match step {
+            Step::Won { starter_wins, .. } => Some(starter_wins),
+            _ => None,
+        };
+
+        if let Some(starter_wins) = starter_wins {
+            record_and_crown(ctx, &session, starter_wins).await;
+
+            if session.ladder && starter_wins {
+                ladder::climb(&session);
+            }
+        }
+
+        log_end(ctx, &session, &settings, reason, starter_wins).await;
+
+        if starter_wins.is_some() {
+            offer_rematch(ctx, &session, &settings, press.app_permissions());
+        }
+
+        start_queued(ctx, session.channel_id);
+    } else {
+        if let Step::Accepted = step {
+            reporting::set_session(session_id, "playing");
+        }
+
+        watch_turn(ctx, &session, &settings);
+    }
+
+    let message = render(&step, &session, press.user(), &Presentation::load(&session, Some(&step), &settings).await);
+
+    spectators::mirror(ctx, &session, message.embed.clone());
+
+    if !from_inbox {
+        if matches!(step, Step::Draw | Step::Won { .. }) && stream::enabled(&settings) {
+            reveal(ctx, press, &palette, &step, &session, message).await;
+
+            return;
+        }
+
+        deliver(ctx.http.as_ref(), press.id(), press.token(), &session, message).await;
+
+        return;
+    }
+
+    // The game message is not the one the button belongs to, so it is edited separately
+    edit_game_message(ctx.http.as_ref(), &session, message).await;
+
+    let link = session.message_id
+        .map(|message_id| message_id.link(session.channel_id, session.guild_id))
+        .unwrap_or_default();
+
+    let description = match step {
+        Step::Accepted => tr!(&interaction_locale, "challenges.accepted", user = session.starter.mention(), link = link),
+        _ => tr!(&interaction_locale, "challenges.declined", user = session.starter.mention()),
+    };
+
+    let _ = press.respond(ctx.http.as_ref(), |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|msg| {
+                msg
+                    .components(|comp| comp)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&interaction_locale, "embeds.challenges")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// Applies the action picked from the session to it, pressed by the user on the message of the origin (`None` for
+/// the messages of `/challenges`), and returns the step with the session it has led to, or the reason it is rejected.
+fn transition<'a>(
+    custom_id: CustomId<'_>,
+    user: &User,
+    origin: Option<(ChannelId, Option<MessageId>)>,
+    moderator: bool,
+    action: impl FnOnce(&Session) -> Result<&'a str, &'static str>,
+) -> Result<(Step, Session), &'static str> {
+    let result = sessions::update(custom_id.session_id, |session| {
+        // The buttons of an earlier turn are still valid signatures, e.g. when a move is pressed twice in a row
+        if custom_id.turn != session.turn {
+            return Err("rps.outdated");
+        }
+
+        if let Some((channel_id, message_id)) = origin {
+            check_origin(session, channel_id, message_id)?;
+        }
+
+        let mut action = action(session)?;
+
+        // The exit button of a game is kept to its players, but moderators may end any game, e.g. an abandoned one
+        if action == "stop" && !session.is_participant(user.id) && moderator {
+            action = "moderate";
+        }
+
+        let step = advance(session, user, action)?;
+
+        let turn = match step {
+            Step::Accepted | Step::Denied => Some("invitation"),
+            Step::Moved | Step::Draw | Step::Won { .. } => Some("move"),
+            _ => None,
+        };
+
+        if let Some(turn) = turn {
+            metrics::TURN_LATENCY.with_label_values(&[RPS, turn]).observe(session.turn_started_at.elapsed().as_secs_f64());
+        }
+
+        session.turn += 1;
+        session.turn_started_at = Instant::now();
+
+        Ok((step, session.clone()))
+    });
+
+    result.unwrap_or(Err("rps.inactive"))
+}
+
+/// Plays the recorded rounds of the match again through [`transition`], as its players would press the buttons,
+/// and checks the game ends as recorded: every round but the last one drawn, and the last one won by the recorded
+/// winner, unless the match has been conceded while undecided. Returns what differs otherwise.
+pub fn verify_replay(game: &Match, rounds: &[Round]) -> Result<(), String> {
+    let first_round = match rounds.first() {
+        Some(round) => round.round as usize,
+        None => return Ok(()),
+    };
+
+    // The rounds played before the session was restored are missing, so the game starts at the first recorded one
+    let session = synthetic_session(game.id, game.starter_id, game.opponent_id, game.guild_id, first_round);
+
+    if !sessions::start(session.clone()) {
+        return Err("either player is in a game in progress".to_string());
+    }
+
+    let result = replay_rounds(&session, game, rounds);
+
+    sessions::end(session.id);
+
+    result
+}
+
+fn replay_rounds(session: &Session, game: &Match, rounds: &[Round]) -> Result<(), String> {
+    let mut turn = 0;
+
+    let mut press = |user: &User, action: &str| {
+        let custom_id = CustomId { session_id: session.id, turn, action };
+        let result = transition(custom_id, user, None, false, |_| Ok(action));
+
+        turn += 1;
+
+        result
+            .map(|(step, session)| (step, session.round))
+            .map_err(|reason| format!("{} has been rejected with {}", action, reason))
+    };
+
+    if session.state == State::Invited {
+        press(&session.opponent, "play")?;
+    }
+
+    for (index, round) in rounds.iter().enumerate() {
+        let last = index + 1 == rounds.len();
+
+        // Anything else would be taken for another button
+        for played in [&round.starter_move, &round.opponent_move] {
+            if !MOVES.contains(&played.as_str()) {
+                return Err(format!("round {} has an unknown move, {}", round.round, played));
+            }
+        }
+
+        let (_, current_round) = press(&session.starter, &round.starter_move)?;
+
+        if current_round != round.round as usize {
+            return Err(format!("round {} has been played as round {}", round.round, current_round));
+        }
+
+        match press(&session.opponent, &round.opponent_move)?.0 {
+            Step::Draw => {},
+            Step::Won { .. } if !last => {
+                return Err(format!("round {} has decided the match, yet {} rounds are recorded", round.round, rounds.len()));
+            },
+            Step::Won { starter_wins, .. } => {
+                let winner_id = if starter_wins { game.starter_id } else { game.opponent_id };
+
+                if winner_id != game.winner_id {
+                    return Err(format!("round {} has been won by {} rather than {}", round.round, winner_id, game.winner_id));
+                }
+            },
+            _ => return Err(format!("round {} has not been played out", round.round)),
+        }
+    }
+
+    Ok(())
+}
+
+/// A session between the users of the IDs that no interaction has started, for the games played without Discord;
+/// it is invited if it starts at the first round, and waits for the move of the starter otherwise.
+fn synthetic_session(id: u64, starter_id: u64, opponent_id: u64, guild_id: Option<u64>, first_round: usize) -> Session {
+    let player = |id| {
+        let mut user = User::default();
+
+        user.id = UserId(id);
+        user.bot = false;
+
+        user
+    };
+
+    Session {
+        id,
+        starter: player(starter_id),
+        opponent: player(opponent_id),
+        guild_id: guild_id.map(GuildId),
+        channel_id: ChannelId(0),
+        message_id: None,
+        token: None,
+        started_at: Timestamp::now(),
+        locale: i18n::DEFAULT_LOCALE.to_string(),
+        round: first_round,
+        turn: 0,
+        turn_started_at: Instant::now(),
+        state: if first_round == 1 { State::Invited } else { State::StarterTurn },
+        ladder: false,
+        moves: Vec::new(),
+        rivalry: None,
+    }
+}
+
+/// How long the parts of a synthetic game have taken, as measured by `load-test`.
+pub struct Simulation {
+    /// Registering the session, which looks for the players among every game in progress.
+    pub start: Duration,
+    /// Applying every button press to the session in the registry.
+    pub presses: Vec<Duration>,
+    /// Reading the presentation of the game message and rendering it after every press.
+    pub renders: Vec<Duration>,
+    /// Recording the finished match with its rounds.
+    pub record: Duration,
+}
+
+/// Plays a synthetic game between the players of the IDs through the engine, the way [`apply`] does without
+/// Discord: the opponent accepts, then both players press the moves of every round with `pause` in between,
+/// as if they were thinking, so many games are in progress at once; every step is rendered, and the won game
+/// is ended and recorded. The rounds must be drawn but for the last one.
+pub async fn simulate(
+    id: u64,
+    players: (u64, u64),
+    guild_id: Option<u64>,
+    rounds: &[(&'static str, &'static str)],
+    pause: Duration,
+) -> Result<Simulation, String> {
+    let session = synthetic_session(id, players.0, players.1, guild_id, 1);
+
+    let started = Instant::now();
+
+    if !sessions::start(session.clone()) {
+        return Err("either player is in a game in progress".to_string());
+    }
+
+    let mut simulation = Simulation { start: started.elapsed(), presses: Vec::new(), renders: Vec::new(), record: Duration::ZERO };
+
+    let presses = std::iter::once((&session.opponent, "play"))
+        .chain(rounds.iter().flat_map(|(starter_move, opponent_move)| {
+            [(&session.starter, *starter_move), (&session.opponent, *opponent_move)]
+        }));
+
+    for (turn, (user, action)) in presses.enumerate() {
+        tokio::time::sleep(pause).await;
+
+        let custom_id = CustomId { session_id: id, turn: turn as u32, action };
+
+        let pressed = Instant::now();
+        let result = transition(custom_id, user, None, false, |_| Ok(action));
+
+        simulation.presses.push(pressed.elapsed());
+
+        let (step, session) = match result {
+            Ok(result) => result,
+            Err(reason) => {
+                sessions::end(id);
+
+                return Err(format!("{} has been rejected with {}", action, reason));
+            },
+        };
+
+        let rendered = Instant::now();
+
+        render(&step, &session, user, &Presentation::read(&session, Some(&step), &theme::settings(session.guild_id)));
+
+        simulation.renders.push(rendered.elapsed());
+
+        if let Step::Won { starter_wins, .. } = step {
+            sessions::end(id);
+
+            let recorded = Instant::now();
+
+            record_match(&session, starter_wins);
+
+            simulation.record = recorded.elapsed();
+
+            return Ok(simulation);
+        }
+    }
+
+    sessions::end(id);
+
+    Err("the rounds have not decided the game".to_string())
+}
+
+/// Answers the press with the game message, which is edited instead if the press cannot be answered.
+async fn deliver(transport: &impl Transport, interaction_id: InteractionId, token: &str, session: &Session, message: GameMessage) {
+    let mut response = CreateInteractionResponse::default();
+    let fallback = message.clone();
+
+    response
+        .kind(InteractionResponseType::UpdateMessage)
+        .interaction_response_data(|msg| fill!(msg, message));
+
+    let response = Value::from(json::hashmap_to_json_map(response.0));
+
+    if let Err(err) = transport.respond(interaction_id, token, &response).await {
+        // E.g. the press has taken too long to be answered, which editing the message does not depend on
+        warn!("The button press could not have been answered: {:?}", err);
+
+        edit_game_message(transport, session, fallback).await;
+    }
+}
+
+/// Keeps the moves of the round hidden for a moment in stream mode, then narrates the draw or counts the win.
+async fn reveal(ctx: &Context, press: Press<'_>, palette: &Palette, step: &Step, session: &Session, message: GameMessage) {
+    let locale = session.locale.as_str();
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.stream")))
+        .color(palette.confirmation)
+        .description(tr!(locale, "stream.suspense"));
+
+    let suspense = GameMessage { content: String::new(), embed, row: None };
+
+    if press.respond(ctx.http.as_ref(), |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|msg| fill!(msg, suspense))
+    }).await.is_err() {
+        return;
+    }
+
+    tokio::time::sleep(stream::REVEAL_DELAY).await;
+
+    let _ = ctx.http.as_ref().edit_response(press.token(), &message.payload()).await;
+
+    match step {
+        Step::Draw => stream::narrate(ctx, session).await,
+        Step::Won { starter_wins, .. } => {
+            let winner = if *starter_wins { &session.starter } else { &session.opponent };
+
+            stream::record_win(ctx, session, winner, press.app_permissions()).await;
+        },
+        _ => {},
+    }
+}
+
+/// Stores the finished game for the statistics; a game that cannot be recorded is still shown as won.
+fn record_match(session: &Session, starter_wins: bool) {
+    let winner = if starter_wins { &session.starter } else { &session.opponent };
+    let finished_at = Timestamp::now().unix_timestamp();
+
+    metrics::GAME_DURATION.with_label_values(&[RPS]).observe((finished_at - session.started_at.unix_timestamp()) as f64);
+
+    let game = Match {
+        id: session.id,
+        guild_id: session.guild_id.map(|guild_id| guild_id.0),
+        starter_id: session.starter.id.0,
+        opponent_id: session.opponent.id.0,
+        winner_id: winner.id.0,
+        rounds: session.round as u32,
+        finished_at,
+        shared: false,
+    };
+
+    if let Err(err) = storage::save_match(&game) {
+        error!("The match could not have been recorded: {:?}", err);
+
+        return;
+    }
+
+    let first_round = session.round + 1 - session.moves.len();
+
+    if let Err(err) = storage::save_rounds(session.id, first_round as u32, &session.moves) {
+        error!("The rounds of the match could not have been recorded: {:?}", err);
+    }
+}
+
+/// Records the finished game, then announces the new champion of the guild if the match has made one.
+async fn record_and_crown(ctx: &Context, session: &Session, starter_wins: bool) {
+    let guild_id = session.guild_id.map(|guild_id| guild_id.0);
+
+    if let Some(guild_id) = guild_id {
+        champions::prime(guild_id).await;
+    }
+
+    record_match(session, starter_wins);
+
+    if let Some(guild_id) = guild_id {
+        tokio::spawn(champions::refresh(ctx.clone(), guild_id));
+    }
+}
+
+/// The content of the game message, which can be sent either as the response to a button press
+/// or as an edit of the message through the token of the interaction that has sent it.
+#[derive(Clone)]
+struct GameMessage {
+    content: String,
+    embed: CreateEmbed,
+    row: Option<CreateActionRow>,
+}
+
+impl GameMessage {
+    /// The message as the JSON body of an edit, which is what Discord is sent.
+    fn payload(&self) -> Value {
+        let mut response = EditInteractionResponse::default();
+
+        fill!(response, self.clone());
+
+        Value::from(json::hashmap_to_json_map(response.0))
+    }
+}
+
+/// What the game message shows besides the session: the theme, the titles of the players and the extras
+/// of the guild. It is read from the settings and the database beforehand, so [`render`] depends on nothing else.
+#[derive(Clone, Debug)]
+struct Presentation {
+    theme: Theme,
+    modal_moves: bool,
+    /// The mentions of the players, decorated with their titles.
+    starter: String,
+    opponent: String,
+    /// The standing of the winner of a ladder challenge, once it has ended.
+    standing: Option<String>,
+    /// Whether the result can be shared in the highlights channel of the guild.
+    highlights: bool,
+    replay_link: Option<String>,
+}
+
+impl Presentation {
+    /// Reads the presentation of the game message in the guild with the settings, once the step (if any)
+    /// has been applied to the session.
+    fn read(session: &Session, step: Option<&Step>, settings: &GuildSettings) -> Self {
+        let locale = session.locale.as_str();
+
+        let standing = match step {
+            Some(Step::Won { starter_wins, .. } | Step::Forfeited { starter_wins, .. }) => {
+                let winner = if *starter_wins { &session.starter } else { &session.opponent };
+
+                ladder::standing(session, winner, *starter_wins)
+            },
+            _ => None,
+        };
+
+        Self {
+            theme: Theme::for_game(settings, session.starter.id),
+            modal_moves: modal_moves(settings),
+            starter: titles::decorated(&session.starter, locale),
+            opponent: titles::decorated(&session.opponent, locale),
+            standing,
+            highlights: settings.highlights_channel_id.is_some(),
+            replay_link: replays::link(settings, session.id),
+        }
+    }
+
+    /// [`Presentation::read`] off the runtime, as the titles, the standing and the layout of the starter
+    /// are read from the database.
+    async fn load(session: &Session, step: Option<&Step>, settings: &GuildSettings) -> Self {
+        let (session, step, settings) = (session.clone(), step.copied(), settings.clone());
+
+        off_runtime(move || Self::read(&session, step.as_ref(), &settings)).await
+    }
+
+    fn decorated(&self, session: &Session, user: &User) -> &str {
+        if user.id == session.starter.id {
+            &self.starter
+        } else {
+            &self.opponent
+        }
+    }
+}
+
+/// Renders the game message once the step has been applied, `user` being the one who has pressed the button.
+fn render(step: &Step, session: &Session, user: &User, presentation: &Presentation) -> GameMessage {
+    let Theme { palette, emojis, layout, .. } = &presentation.theme;
+    let locale = session.locale.as_str();
+
+    let mut embed = CreateEmbed::default();
+
+    let (content, row) = match step {
+        Step::Accepted | Step::Draw => {
+            embed = round_embed(session, presentation, &session.starter);
+
+            (String::new(), Some(turn_action_row(session, presentation)))
+        },
+        Step::Moved => {
+            embed = round_embed(session, presentation, &session.opponent);
+
+            (String::new(), Some(turn_action_row(session, presentation)))
+        },
+        Step::Denied => {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.failure")))
+                .color(palette.failure)
+                .description(tr!(locale, "rps.denied", user = session.opponent.mention()));
+
+            (session.starter.mention().to_string(), None)
+        },
+        Step::Cancelled => {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.warning")))
+                .color(palette.warning)
+                .description(tr!(locale, "rps.cancelled", user = session.starter.mention()));
+
+            (String::new(), None)
+        },
+        Step::Won { starter_wins, winner_move, loser_move } => {
+            let (winner, loser) = if *starter_wins {
+                (&session.starter, &session.opponent)
+            } else {
+                (&session.opponent, &session.starter)
+            };
+
+            let emoji = |turn| match turn {
+                "rock" => &emojis.rock,
+                "paper" => &emojis.paper,
+                _ => &emojis.scissors,
+            };
+
+            let standing = presentation.standing.as_ref();
+
+            // A single score line, such as "✋ > ✊ · @winner wins!"
+            if layout.is_compact() {
+                let mut description = tr!(
+                    locale,
+                    "compact.won",
+                    winner_move = emoji(winner_move),
+                    loser_move = emoji(loser_move),
+                    winner = winner.mention(),
+                );
+
+                if let Some(standing) = standing {
+                    description = format!("{}\n{}", description, standing);
+                }
+
+                embed
+                    .color(palette.success)
+                    .description(description);
+            } else {
+                let formatted_turn = |turn| format!("{} {}", emoji(turn), tr!(locale, &format!("moves.{}", turn)));
+
+                embed
+                    .color(palette.success)
+                    .author(|author| {
+                        author
+                            .name(tr!(locale, "embeds.congratulations"))
+                            .icon_url(
+                                winner.avatar_url()
+                                    .unwrap_or_else(|| winner.default_avatar_url())
+                            )
+                    })
+                    .description(tr!(
+                        locale,
+                        "rps.defeats",
+                        winner = presentation.decorated(session, winner),
+                        loser = presentation.decorated(session, loser),
+                    ))
+                    .field(tr!(locale, "rps.winner_turn"), formatted_turn(winner_move), false)
+                    .field(tr!(locale, "rps.loser_turn"), formatted_turn(loser_move), false);
+
+                if let Some(standing) = standing {
+                    embed.field(tr!(locale, "ladder.field"), standing, false);
+                }
+            }
+
+            // The ID the match can be replayed with
+            embed.footer(|footer| footer.text(tr!(locale, "rps.replay_hint", id = session.id)));
+
+            add_replay_link(&mut embed, session, presentation);
+
+            let mut row = CreateActionRow::default();
+
+            if presentation.highlights {
+                highlights::add_button(&mut row, session);
+            }
+
+            insights::add_button(&mut row, session);
+
+            (String::new(), Some(row))
+        },
+        Step::Terminated => {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.warning")))
+                .color(palette.warning)
+                .description(tr!(locale, "rps.terminated", user = user.mention()));
+
+            (String::new(), None)
+        },
+        Step::Moderated => {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.warning")))
+                .color(palette.warning)
+                .description(tr!(locale, "rps.moderator", user = user.mention()));
+
+            (String::new(), None)
+        },
+        Step::Forfeited { starter_wins, reason } => {
+            let winner = if *starter_wins { &session.starter } else { &session.opponent };
+
+            let mut description = tr!(locale, &format!("rps.{}", reason), loser = user.mention(), winner = winner.mention());
+
+            if let Some(standing) = &presentation.standing {
+                description = format!("{}\n{}", description, standing);
+            }
+
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.forfeit")))
+                .color(palette.success)
+                .description(description)
+                .footer(|footer| footer.text(tr!(locale, "rps.replay_hint", id = session.id)));
+
+            add_replay_link(&mut embed, session, presentation);
+
+            let mut row = CreateActionRow::default();
+
+            if presentation.highlights {
+                highlights::add_button(&mut row, session);
+            }
+
+            insights::add_button(&mut row, session);
+
+            (String::new(), Some(row))
+        },
+    };
+
+    GameMessage { content, embed, row }
+}
+
+/// Edits the game message, trying again a few times if it fails; a game still in progress whose message
+/// cannot be edited is posted again, so it can go on rather than be stuck until it expires.
+async fn edit_game_message(transport: &impl Transport, session: &Session, message: GameMessage) {
+    for attempt in 1..=EDIT_ATTEMPTS {
+        match try_edit_game_message(transport, session, &message).await {
+            Ok(_) => return,
+            Err(err) => warn!(session = session.id, attempt, "The game message could not have been edited: {:?}", err),
+        }
+
+        if attempt < EDIT_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+        }
+    }
+
+    if sessions::contains(session.id) {
+        repost(transport, session, message).await;
+    }
+}
+
+/// Edits the game message through the token of the interaction that has sent it or taken it over while it is valid,
+/// or as a regular message otherwise, which also covers the messages posted by the bot itself.
+async fn try_edit_game_message(transport: &impl Transport, session: &Session, message: &GameMessage) -> serenity::Result<()> {
+    if let Some(token) = usable_token(session) {
+        match transport.edit_response(token, &message.payload()).await {
+            Ok(_) => return Ok(()),
+            // E.g. the token has expired sooner than expected, so the message is edited as a regular one instead
+            Err(err) if session.message_id.is_some() => {
+                warn!(session = session.id, "The game message could not have been edited through the interaction: {:?}", err);
+            },
+            Err(err) => return Err(err),
+        }
+    }
+
+    if let Some(message_id) = session.message_id {
+        transport.edit_message(session.channel_id, message_id, &message.payload()).await?;
+    }
+
+    Ok(())
+}
+
+/// The token of the interaction to edit the game message through, unless it may have expired by now, in which case
+/// the message is edited with the token of the bot. The age of the token is counted from the start of the session,
+/// which it can only be younger than, so a long game switches over before its token actually expires.
+fn usable_token(session: &Session) -> Option<&str> {
+    let token = session.token.as_deref()?;
+    let age = Timestamp::now().unix_timestamp() - session.started_at.unix_timestamp();
+
+    // Without the message, the token is the only way to edit it anyway
+    if age < INTERACTION_TOKEN_LIFETIME.as_secs() as i64 || session.message_id.is_none() {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+/// Posts the game as a new message in its channel, e.g. once its message has been deleted without the bot
+/// noticing or the bot can no longer edit it, then moves the session over to it; its buttons keep working,
+/// as they only carry the session.
+async fn repost(transport: &impl Transport, session: &Session, message: GameMessage) {
+    match transport.send_message(session.channel_id, &message.payload()).await {
+        Ok(message_id) => {
+            sessions::update(session.id, |session| {
+                session.message_id = Some(message_id);
+                // The message belongs to the bot now rather than to an interaction
+                session.token = None;
+            });
+
+            info!(session = session.id, "The game has been posted again as {}", message_id);
+        },
+        Err(err) => error!("The game could not have been posted again: {:?}", err),
+    }
+}
+
+fn round_embed(session: &Session, presentation: &Presentation, user: &User) -> CreateEmbed {
+    let Theme { palette, layout, .. } = &presentation.theme;
+    let locale = session.locale.as_str();
+
+    let mut embed = CreateEmbed::default();
+
+    if layout.is_compact() {
+        embed
+            .color(palette.success)
+            .description(tr!(locale, "compact.turn", round = session.round, user = user.mention()))
+            .footer(|footer| footer.text(snapshot(session)));
+
+        return embed;
+    }
+
+    embed
+        .color(palette.success)
+        .author(|author| {
+            author
+                .name(tr!(locale, "rps.round", round = session.round))
+                .icon_url(
+                    user.avatar_url()
+                        .unwrap_or_else(|| user.default_avatar_url())
+                )
+        })
+        .description(tr!(locale, "rps.turn", user = presentation.decorated(session, user)))
+        .footer(|footer| footer.text(snapshot(session)));
+
+    embed
+}
+
+/// Links the result to the replay page of the match, if the guild has made its replays public.
+fn add_replay_link(embed: &mut CreateEmbed, session: &Session, presentation: &Presentation) {
+    if let Some(link) = &presentation.replay_link {
+        let locale = session.locale.as_str();
+
+        embed.field(tr!(locale, "rps.replay"), format!("[{}]({})", tr!(locale, "rps.watch_replay"), link), false);
+    }
+}
+
+fn turn_action_row(session: &Session, presentation: &Presentation) -> CreateActionRow {
+    let Theme { emojis, button_labels, .. } = &presentation.theme;
+    let locale = session.locale.as_str();
+
+    let mut row = CreateActionRow::default();
+
+    if presentation.modal_moves {
+        row.create_button(|button| {
+            button
+                .style(ButtonStyle::Primary)
+                .label(tr!(locale, "rps.make_move"))
+                .custom_id(custom_id(session, "move"))
+        });
+    } else {
+        for (name, emoji) in [("rock", &emojis.rock), ("paper", &emojis.paper), ("scissors", &emojis.scissors)] {
+            row.create_button(|button| {
+                button
+                    .style(ButtonStyle::Secondary)
+                    .custom_id(custom_id(session, name));
+
+                if button_labels.shows_emoji() {
+                    button.emoji(emoji.clone());
+                }
+
+                if button_labels.shows_text() {
+                    button.label(tr!(locale, &format!("moves.{}", name)));
+                }
+
+                button
+            });
+        }
+    }
+
+    row
+        .create_button(|button| {
+            button
+                .style(ButtonStyle::Danger)
+                .label(tr!(locale, "rps.exit"))
+                .custom_id(custom_id(session, "stop"))
+        });
+
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use crate::theme::{ButtonLabels, Layout, MoveEmojis};
+    use crate::transport::{Call, Mock};
+
+    fn user(id: u64, name: &str) -> User {
+        let mut user = User::default();
+
+        user.id = UserId(id);
+        user.name = name.to_string();
+        user.discriminator = 1000 + id as u16;
+        user.avatar = None;
+        user.bot = false;
+
+        user
+    }
+
+    /// Compares the payload with its snapshot in `tests/snapshots`; a change is accepted with `cargo insta review`.
+    fn assert_snapshot(name: &str, payload: &Value) {
+        let mut settings = insta::Settings::clone_current();
+
+        settings.set_snapshot_path(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots"));
+        settings.set_prepend_module_to_snapshot(false);
+        settings.bind(|| insta::assert_json_snapshot!(name, payload));
+    }
+
+    fn session(state: State) -> Session {
+        Session {
+            id: 1_000_000,
+            starter: user(1, "starter"),
+            opponent: user(2, "opponent"),
+            guild_id: None,
+            channel_id: ChannelId(10),
+            message_id: None,
+            token: None,
+            started_at: Timestamp::from_unix_timestamp(0).unwrap(),
+            locale: "en-US".to_string(),
+            round: 2,
+            turn: 3,
+            turn_started_at: Instant::now(),
+            state,
+            ladder: false,
+            moves: vec![("rock", "rock")],
+            rivalry: None,
+        }
+    }
+
+    fn presentation(layout: Layout) -> Presentation {
+        Presentation {
+            theme: Theme {
+                palette: Palette::default(),
+                emojis: MoveEmojis::default(),
+                button_labels: ButtonLabels::default(),
+                layout,
+            },
+            modal_moves: false,
+            starter: "<@1>".to_string(),
+            opponent: "<@2>".to_string(),
+            standing: None,
+            highlights: false,
+            replay_link: None,
+        }
+    }
+
+    fn won() -> Step {
+        Step::Won { starter_wins: true, winner_move: "paper", loser_move: "rock" }
+    }
+
+    /// Renders the step for every layout, pressed by the given player.
+    fn assert_rendered(name: &str, step: Step, session: &Session, user: &User) {
+        for layout in [Layout::Full, Layout::Compact] {
+            let message = render(&step, session, user, &presentation(layout));
+
+            assert_snapshot(&format!("rps_{}_{}", name, layout.as_setting()), &message.payload());
+        }
+    }
+
+    #[test]
+    fn invitation_renders() {
+        let session = session(State::Invited);
+
+        for layout in [Layout::Full, Layout::Compact] {
+            let message = invitation(&session, &presentation(layout), "<@2>".to_string());
+
+            assert_snapshot(&format!("rps_invitation_{}", layout.as_setting()), &message.payload());
+        }
+
+        let session = Session { rivalry: Some((4, 2)), ..session };
+        let message = invitation(&session, &presentation(Layout::Full), "<@2>".to_string());
+
+        assert_snapshot("rps_invitation_rivalry", &message.payload());
+    }
+
+    #[test]
+    fn turns_render() {
+        let session = session(State::StarterTurn);
+
+        assert_rendered("accepted", Step::Accepted, &session, &session.opponent);
+        assert_rendered("draw", Step::Draw, &session, &session.opponent);
+
+        let session = Session { state: State::OpponentTurn { starter_move: "paper" }, ..session };
+
+        assert_rendered("moved", Step::Moved, &session, &session.starter);
+    }
+
+    #[test]
+    fn turn_buttons_follow_the_guild() {
+        let session = session(State::StarterTurn);
+
+        let mut text_labels = presentation(Layout::Full);
+
+        text_labels.theme.button_labels = ButtonLabels::Text;
+
+        let message = render(&Step::Accepted, &session, &session.opponent, &text_labels);
+
+        assert_snapshot("rps_accepted_text_labels", &message.payload());
+
+        let modal_moves = Presentation { modal_moves: true, ..presentation(Layout::Full) };
+        let message = render(&Step::Accepted, &session, &session.opponent, &modal_moves);
+
+        assert_snapshot("rps_accepted_modal_moves", &message.payload());
+    }
+
+    #[test]
+    fn endings_render() {
+        let session = session(State::Invited);
+
+        assert_rendered("denied", Step::Denied, &session, &session.opponent);
+        assert_rendered("cancelled", Step::Cancelled, &session, &session.starter);
+
+        let session = Session { state: State::StarterTurn, ..session };
+
+        assert_rendered("terminated", Step::Terminated, &session, &session.starter);
+        assert_rendered("moderated", Step::Moderated, &session, &user(3, "moderator"));
+    }
+
+    #[test]
+    fn results_render() {
+        let session = session(State::StarterTurn);
+
+        assert_rendered("won", won(), &session, &session.starter);
+        assert_rendered("forfeited", Step::Forfeited { starter_wins: false, reason: "forfeited" }, &session, &session.starter);
+        assert_rendered("afk", Step::Forfeited { starter_wins: true, reason: "afk" }, &session, &session.opponent);
+
+        // Everything a guild can add to a result
+        let extras = Presentation {
+            standing: Some(tr!("en-US", "ladder.climbed", user = "<@1>", position = 1)),
+            highlights: true,
+            replay_link: Some("https://rps.example/replays/1000000".to_string()),
+            ..presentation(Layout::Full)
+        };
+
+        let message = render(&won(), &Session { ladder: true, ..session }, &user(1, "starter"), &extras);
+
+        assert_snapshot("rps_won_extras", &message.payload());
+    }
+
+    /// Registers a game in progress, with players of its own so the tests can run side by side.
+    fn start(id: u64) -> Session {
+        let session = Session {
+            id,
+            starter: user(id * 10 + 1, "starter"),
+            opponent: user(id * 10 + 2, "opponent"),
+            message_id: Some(MessageId(id)),
+            token: Some(format!("token-{}", id)),
+            started_at: Timestamp::now(),
+            round: 1,
+            turn: 0,
+            turn_started_at: Instant::now(),
+            moves: Vec::new(),
+            ..session(State::Invited)
+        };
+
+        assert!(sessions::start(session.clone()));
+
+        session
+    }
+
+    /// Presses the button of the action on the game message as the user and answers the press, as `apply` does.
+    async fn press(transport: &Mock, session: &Session, user: &User, action: &str) -> Result<Step, &'static str> {
+        let turn = sessions::update(session.id, |session| session.turn).unwrap_or_default();
+        let custom_id = CustomId { session_id: session.id, turn, action };
+
+        let origin = Some((session.channel_id, session.message_id));
+        let (step, session) = transition(custom_id, user, origin, false, |_| Ok(action))?;
+
+        let message = render(&step, &session, user, &presentation(Layout::Full));
+
+        deliver(transport, InteractionId(session.id), "press", &session, message).await;
+
+        Ok(step)
+    }
+
+    fn description(message: &Value) -> &str {
+        message["embeds"][0]["description"].as_str().unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn a_game_is_played_through_the_responses() {
+        let transport = Mock::default();
+        let session = start(1);
+        let (starter, opponent) = (&session.starter, &session.opponent);
+
+        assert!(matches!(press(&transport, &session, opponent, "play").await, Ok(Step::Accepted)));
+        assert!(matches!(press(&transport, &session, opponent, "rock").await, Err("rps.not_your_turn")));
+        assert!(matches!(press(&transport, &session, starter, "rock").await, Ok(Step::Moved)));
+        assert!(matches!(press(&transport, &session, opponent, "rock").await, Ok(Step::Draw)));
+        assert!(matches!(press(&transport, &session, starter, "paper").await, Ok(Step::Moved)));
+
+        let won = press(&transport, &session, opponent, "rock").await;
+
+        assert!(matches!(won, Ok(Step::Won { starter_wins: true, winner_move: "paper", loser_move: "rock" })));
+        assert_eq!(transport.kinds(), ["respond"; 5]);
+
+        let calls = transport.calls();
+        let result = calls.last().unwrap().message();
+
+        assert_eq!(description(result), "<@1> defeats <@2>!");
+        assert_eq!(sessions::update(session.id, |session| session.moves.clone()), Some(vec![("rock", "rock"), ("paper", "rock")]));
+
+        sessions::end(session.id);
+    }
+
+    #[tokio::test]
+    async fn an_invitation_is_answered_by_the_opponent_alone() {
+        let transport = Mock::default();
+        let session = start(2);
+
+        assert!(matches!(press(&transport, &session, &session.starter, "deny").await, Err("rps.not_invited")));
+        assert!(matches!(press(&transport, &session, &session.opponent, "cancel").await, Err("rps.not_starter")));
+        assert!(matches!(press(&transport, &session, &session.opponent, "deny").await, Ok(Step::Denied)));
+
+        let calls = transport.calls();
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].message()["content"], session.starter.mention().to_string());
+        assert_eq!(calls[0].message()["components"], Value::Array(Vec::new()));
+
+        sessions::end(session.id);
+    }
+
+    #[tokio::test]
+    async fn stale_and_foreign_presses_are_rejected() {
+        let transport = Mock::default();
+        let session = start(3);
+
+        let outdated = CustomId { session_id: session.id, turn: 5, action: "play" };
+        let origin = Some((session.channel_id, session.message_id));
+
+        assert!(matches!(transition(outdated, &session.opponent, origin, false, |_| Ok("play")), Err("rps.outdated")));
+
+        let foreign = Session { message_id: Some(MessageId(999)), ..session.clone() };
+
+        assert!(matches!(press(&transport, &foreign, &session.opponent, "play").await, Err("rps.foreign_message")));
+
+        // Anyone but the players may only end the game as a moderator
+        let bystander = user(39, "bystander");
+        let stop = CustomId { session_id: session.id, turn: 0, action: "stop" };
+
+        assert!(matches!(transition(stop, &bystander, origin, false, |_| Ok("stop")), Err("rps.not_participant")));
+        assert!(matches!(transition(stop, &bystander, origin, true, |_| Ok("stop")), Ok((Step::Moderated, _))));
+
+        sessions::end(session.id);
+
+        assert!(matches!(press(&transport, &session, &session.opponent, "play").await, Err("rps.inactive")));
+        assert!(transport.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn an_expired_game_is_edited_through_its_token() {
+        let transport = Mock::default();
+        let session = sessions::end(start(4).id).unwrap();
+
+        edit_game_message(&transport, &session, ending(&session, &Palette::default(), "expired")).await;
+
+        let calls = transport.calls();
+
+        assert_eq!(transport.kinds(), ["edit_response"]);
+        assert_eq!(description(calls[0].message()), tr!("en-US", "rps.expired"));
+    }
+
+    #[tokio::test]
+    async fn an_old_game_is_edited_as_a_regular_message() {
+        let transport = Mock::default();
+        let session = sessions::end(start(5).id).unwrap();
+
+        // The token of the interaction may have expired by now
+        let session = Session { started_at: Timestamp::from_unix_timestamp(0).unwrap(), ..session };
+
+        edit_game_message(&transport, &session, ending(&session, &Palette::default(), "expired")).await;
+
+        assert_eq!(transport.calls(), [Call::EditMessage {
+            message_id: MessageId(5),
+            message: ending(&session, &Palette::default(), "expired").payload(),
+        }]);
+    }
+
+    #[tokio::test]
+    async fn an_unanswered_press_edits_the_game_message() {
+        let transport = Mock::default();
+        let session = start(6);
+
+        transport.fail("respond", 1);
+
+        assert!(matches!(press(&transport, &session, &session.opponent, "play").await, Ok(Step::Accepted)));
+        assert_eq!(transport.kinds(), ["respond", "edit_response"]);
+
+        sessions::end(session.id);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_game_whose_message_cannot_be_edited_is_posted_again() {
+        let transport = Mock::default();
+        let session = start(7);
+
+        transport.fail("edit_response", EDIT_ATTEMPTS as usize);
+        transport.fail("edit_message", EDIT_ATTEMPTS as usize);
+
+        edit_game_message(&transport, &session, ending(&session, &Palette::default(), "stale")).await;
+
+        assert_eq!(transport.kinds(), [
+            "edit_response", "edit_message", "edit_response", "edit_message", "edit_response", "edit_message", "send_message",
+        ]);
+
+        // The game goes on in the new message, which the bot has posted itself
+        let moved = sessions::update(session.id, |session| (session.message_id, session.token.clone()));
+
+        assert_eq!(moved, Some((Some(MessageId(1)), None)));
+
+        sessions::end(session.id);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn an_ended_game_is_not_posted_again() {
+        let transport = Mock::default();
+        let session = sessions::end(start(8).id).unwrap();
+
+        transport.fail("edit_response", EDIT_ATTEMPTS as usize);
+        transport.fail("edit_message", EDIT_ATTEMPTS as usize);
+
+        edit_game_message(&transport, &session, ending(&session, &Palette::default(), "expired")).await;
+
+        assert!(!transport.kinds().contains(&"send_message"));
+    }
+
+    fn recorded(id: u64, winner_id: u64, moves: &[(u32, &str, &str)]) -> (Match, Vec<Round>) {
+        let game = Match {
+            id,
+            guild_id: None,
+            starter_id: id * 10 + 1,
+            opponent_id: id * 10 + 2,
+            winner_id,
+            rounds: moves.last().map_or(1, |(round, ..)| *round),
+            finished_at: 0,
+            shared: false,
+        };
+
+        let rounds = moves.iter()
+            .map(|(round, starter_move, opponent_move)| Round {
+                round: *round,
+                starter_move: starter_move.to_string(),
+                opponent_move: opponent_move.to_string(),
+            })
+            .collect();
+
+        (game, rounds)
+    }
+
+    #[test]
+    fn recorded_matches_are_played_again_as_recorded() {
+        let cases = [
+            recorded(101, 1011, &[(1, "rock", "scissors")]),
+            recorded(102, 1022, &[(1, "paper", "paper"), (2, "rock", "rock"), (3, "scissors", "rock")]),
+            // Conceded while the rounds were drawn
+            recorded(103, 1031, &[(1, "scissors", "scissors")]),
+            // Restored during the third round, so the first two are missing
+            recorded(104, 1041, &[(3, "paper", "paper"), (4, "paper", "rock")]),
+            recorded(105, 1052, &[]),
+        ];
+
+        for (game, rounds) in cases {
+            assert_eq!(verify_replay(&game, &rounds), Ok(()), "match {}", game.id);
+            assert!(!sessions::contains(game.id));
+        }
+    }
+
+    #[test]
+    fn altered_matches_are_told_apart() {
+        let (game, rounds) = recorded(111, 1112, &[(1, "rock", "scissors")]);
+
+        assert_eq!(verify_replay(&game, &rounds), Err("round 1 has been won by 1111 rather than 1112".to_string()));
+
+        let (game, rounds) = recorded(112, 1121, &[(1, "rock", "scissors"), (2, "rock", "rock")]);
+
+        assert_eq!(verify_replay(&game, &rounds), Err("round 1 has decided the match, yet 2 rounds are recorded".to_string()));
+
+        let (game, rounds) = recorded(113, 1131, &[(1, "rock", "rock"), (3, "rock", "scissors")]);
+
+        assert_eq!(verify_replay(&game, &rounds), Err("round 3 has been played as round 2".to_string()));
+
+        let (game, rounds) = recorded(114, 1141, &[(1, "stop", "rock")]);
+
+        assert_eq!(verify_replay(&game, &rounds), Err("round 1 has an unknown move, stop".to_string()));
+        assert!(!sessions::contains(game.id));
+    }
+
+    #[test]
+    fn delays_are_parsed() {
+        let minutes = |minutes: u64| Some(Duration::from_secs(minutes * 60));
+
+        assert_eq!(parse_delay("30m"), minutes(30));
+        assert_eq!(parse_delay("2h"), minutes(120));
+        assert_eq!(parse_delay("1h30m"), minutes(90));
+        assert_eq!(parse_delay(" 1D12H "), minutes(36 * 60));
+        assert_eq!(parse_delay("1m"), minutes(1));
+        assert_eq!(parse_delay("7d"), Some(MAX_SCHEDULE_DELAY));
+        assert_eq!(parse_delay("6d23h60m"), Some(MAX_SCHEDULE_DELAY));
+    }
+
+    #[test]
+    fn invalid_delays_are_rejected() {
+        for text in [
+            "", " ", "0m", "0d0h0m", "59s", "30", "m", "1h30", "7d1m", "8d",
+            "soon", "1.5h", "-30m", "30 m", "1h 30m", "½h",
+            // Past the seconds a u64 holds, once multiplied and once added up
+            "99999999999999999999m", "300000000000000d", "200000000000000d200000000000000d",
+        ] {
+            assert_eq!(parse_delay(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn snapshots_round_trip() {
+        let states = [
+            State::Invited,
+            State::StarterTurn,
+            State::OpponentTurn { starter_move: "rock" },
+            State::OpponentTurn { starter_move: "paper" },
+            State::OpponentTurn { starter_move: "scissors" },
+        ];
+
+        for state in states {
+            for ladder in [false, true] {
+                let session = Session { ladder, ..session(state) };
+                let snapshot = parse_snapshot(session.id, &snapshot(&session)).unwrap();
+
+                assert_eq!(snapshot.starter_id, session.starter.id);
+                assert_eq!(snapshot.opponent_id, session.opponent.id);
+                assert_eq!((snapshot.round, snapshot.turn), (session.round, session.turn));
+                assert_eq!(snapshot.locale, session.locale);
+                assert_eq!(snapshot.state, state);
+                assert_eq!(snapshot.ladder, ladder);
+            }
+        }
+    }
+
+    #[test]
+    fn snapshots_mask_the_move_of_the_starter() {
+        for starter_move in MOVES {
+            let mut encoded = HashSet::new();
+
+            for id in 0..30 {
+                let session = Session { id, ..session(State::OpponentTurn { starter_move }) };
+                let text = snapshot(&session);
+
+                assert!(!text.contains(starter_move), "{}", text);
+
+                encoded.insert(text.split(':').nth(6).unwrap().to_string());
+
+                // Only the session the snapshot has been taken of can unmask it
+                assert_eq!(parse_snapshot(id, &text).unwrap().state, State::OpponentTurn { starter_move });
+            }
+
+            // The same move is written differently from a session to the next
+            assert_eq!(encoded.len(), MOVES.len(), "{}", starter_move);
+        }
+    }
+
+    #[test]
+    fn malformed_snapshots_are_rejected() {
+        let session = Session { ladder: true, ..session(State::OpponentTurn { starter_move: "paper" }) };
+        let text = snapshot(&session);
+
+        // Every truncation either fails or loses nothing but the ladder flag, without panicking
+        for end in 0..text.len() {
+            if let Some(snapshot) = parse_snapshot(session.id, &text[..end]) {
+                assert!(!snapshot.ladder);
+                assert_eq!(snapshot.state, session.state);
+            }
+        }
+
+        for text in [
+            "",
+            "rps",
+            "ttt:1:2:2:3:en-US:s",
+            "rps:one:2:2:3:en-US:s",
+            "rps:1:2:two:3:en-US:s",
+            "rps:1:2:2:-3:en-US:s",
+            "rps:1:2:2:3:en-US",
+            "rps:1:2:2:3:en-US:x",
+            "rps:1:2:2:3:en-US:o",
+            "rps:1:2:2:3:en-US:o3",
+            "rps:1:2:2:3:en-US:o255",
+            "rps:1:2:2:3:en-US:o256",
+        ] {
+            assert!(parse_snapshot(session.id, text).is_none(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn restored_turns_keep_their_elapsed_time() {
+        let now = Timestamp::now().unix_timestamp();
+
+        let elapsed = instant_at(now - 90).elapsed();
+
+        assert!(elapsed >= Duration::from_secs(89) && elapsed <= Duration::from_secs(91), "{:?}", elapsed);
+        assert!(instant_at(now + 90).elapsed() < Duration::from_secs(1));
+    }
+}
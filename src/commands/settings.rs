@@ -0,0 +1,767 @@
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOption};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::channel::{ChannelType, ReactionType};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::prelude::Mentionable;
+use serenity::utils::Color;
+
+use tracing::error;
+
+use crate::commands::ladder::DEFAULT_RANGE;
+use crate::commands::layout;
+use crate::commands::rps::{AFK_WARNING, REMATCH_WINDOW};
+use crate::commands::{reply_failure, Localized};
+use crate::config;
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::storage::{self, GuildSettings};
+use crate::stream;
+use crate::theme::{self, ButtonLabels, Layout, MoveEmojis, Palette};
+
+const COLOR_OPTIONS: [&str; 4] = ["success", "failure", "confirmation", "warning"];
+const MOVE_OPTIONS: [&str; 3] = ["rock", "paper", "scissors"];
+
+/// Ten years, which is as good as forever.
+const MAX_RETENTION_DAYS: u64 = 3650;
+
+/// The widest ladder range, past which every challenge might as well be allowed.
+const MAX_LADDER_RANGE: u32 = 50;
+
+/// The longest turn timer, in seconds; a game cannot last longer than its timeout anyway.
+const MAX_TURN_TIMEOUT: u64 = 60 * 10;
+
+/// The longest warning before the end of the turn timer, in seconds.
+const MAX_AFK_WARNING: u64 = 60 * 2;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    // Hidden from the members who could not use it anyway, though `execute` checks the permission again,
+    // as server admins can override the default in the integration settings
+    cmd
+        .localized("commands.settings")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.colors")
+                .kind(CommandOptionType::SubCommand);
+
+            for name in COLOR_OPTIONS {
+                sub.create_sub_option(|option| {
+                    option
+                        .localized(&format!("commands.settings.colors.{}", name))
+                        .kind(CommandOptionType::String)
+                });
+            }
+
+            sub
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.moves")
+                .kind(CommandOptionType::SubCommand);
+
+            for name in MOVE_OPTIONS {
+                sub.create_sub_option(|option| {
+                    option
+                        .localized(&format!("commands.settings.moves.{}", name))
+                        .kind(CommandOptionType::String)
+                });
+            }
+
+            sub
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.buttons")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.buttons.labels")
+                        .kind(CommandOptionType::String);
+
+                    for labels in ButtonLabels::ALL {
+                        option.add_string_choice(
+                            tr!(DEFAULT_LOCALE, &format!("settings.buttons.{}", labels.as_setting())),
+                            labels.as_setting(),
+                        );
+                    }
+
+                    option
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.stats")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.stats.global")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.retention")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.retention.days")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .max_int_value(MAX_RETENTION_DAYS)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.rematch")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.rematch.emoji")
+                        .kind(CommandOptionType::String)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.highlights")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.highlights.channel")
+                        .kind(CommandOptionType::Channel)
+                        .channel_types(&[ChannelType::Text])
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.highlights.disable")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.ladder")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.ladder.range")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(MAX_LADDER_RANGE)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.stream")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.stream.enabled")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.layout")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.layout.layout")
+                        .kind(CommandOptionType::String);
+
+                    for layout in Layout::ALL {
+                        option.add_string_choice(layout::name(layout, DEFAULT_LOCALE), layout.as_setting());
+                    }
+
+                    option
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.modal_moves")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.modal_moves.enabled")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.queue")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.queue.enabled")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.replays")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.replays.public")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.digest")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.digest.channel")
+                        .kind(CommandOptionType::Channel)
+                        .channel_types(&[ChannelType::Text])
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.digest.disable")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.champions")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.champions.channel")
+                        .kind(CommandOptionType::Channel)
+                        .channel_types(&[ChannelType::Text])
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.champions.disable")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
+        .create_option(|sub| {
+            sub
+                .localized("commands.settings.afk")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.afk.timeout")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .max_int_value(MAX_TURN_TIMEOUT)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.settings.afk.warning")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(5)
+                        .max_int_value(MAX_AFK_WARNING)
+                })
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let guild_id = match cmd.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.guild_only")).await;
+
+            return;
+        },
+    };
+
+    let is_admin = cmd.member.as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild());
+
+    if !is_admin {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.admin_only")).await;
+
+        return;
+    }
+
+    let mut settings = match storage::guild_settings(guild_id.0) {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("The guild settings could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let subcommand = &cmd.data.options[0];
+    let options = &subcommand.options;
+
+    let result = match subcommand.name.as_str() {
+        "colors" => colors(&mut settings, &locale, options),
+        "moves" => moves(ctx, guild_id, &mut settings, &locale, options).await,
+        "buttons" => buttons(&mut settings, &locale, options),
+        "stats" => stats(&mut settings, &locale, options),
+        "retention" => retention(&mut settings, &locale, options),
+        "rematch" => rematch(ctx, guild_id, &mut settings, &locale, options).await,
+        "highlights" => highlights(&mut settings, &locale, options),
+        "ladder" => ladder(&mut settings, &locale, options),
+        "stream" => stream(ctx, guild_id, &mut settings, &locale, options).await,
+        "layout" => layout(&mut settings, &locale, options),
+        "modal_moves" => modal_moves(&mut settings, &locale, options),
+        "queue" => queue(&mut settings, &locale, options),
+        "afk" => afk(&mut settings, &locale, options),
+        "replays" => replays(&mut settings, &locale, options),
+        "digest" => digest(&mut settings, &locale, options),
+        "champions" => champions(&mut settings, &locale, options),
+        _ => return,
+    };
+
+    let embed = match result {
+        Ok(embed) => embed,
+        Err(message) => {
+            reply_failure(ctx, cmd, &palette, &message).await;
+
+            return;
+        },
+    };
+
+    if !options.is_empty() {
+        if let Err(err) = storage::save_guild_settings(guild_id.0, &settings) {
+            error!("The guild settings could not have been saved: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.save_failed")).await;
+
+            return;
+        }
+
+        theme::forget(guild_id);
+    }
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| msg.ephemeral(true).set_embed(embed))
+    }).await;
+}
+
+/// Starts the embed that describes the (possibly updated) settings of a subcommand.
+fn settings_embed(settings: &GuildSettings, locale: &str, description: String) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.settings")))
+        .color(Palette::from(settings).success)
+        .description(description);
+
+    embed
+}
+
+fn colors(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    for option in options {
+        let value = match &option.resolved {
+            Some(CommandDataOptionValue::String(value)) => value,
+            _ => continue,
+        };
+
+        let color = if value.eq_ignore_ascii_case("default") {
+            None
+        } else if let Some(color) = theme::parse_color(value) {
+            Some(color)
+        } else {
+            return Err(tr!(locale, "settings.colors.invalid", value = value));
+        };
+
+        match option.name.as_str() {
+            "success" => settings.success_color = color,
+            "failure" => settings.failure_color = color,
+            "confirmation" => settings.confirmation_color = color,
+            _ => settings.warning_color = color,
+        }
+    }
+
+    let palette = Palette::from(&*settings);
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.colors.current")
+    } else {
+        tr!(locale, "settings.colors.updated")
+    };
+
+    let formatted_color = |color: Color| format!("#{}", color.hex());
+
+    let mut embed = settings_embed(settings, locale, description);
+
+    embed
+        .field(tr!(locale, "settings.colors.success"), formatted_color(palette.success), true)
+        .field(tr!(locale, "settings.colors.failure"), formatted_color(palette.failure), true)
+        .field(tr!(locale, "settings.colors.confirmation"), formatted_color(palette.confirmation), true)
+        .field(tr!(locale, "settings.colors.warning"), formatted_color(palette.warning), true);
+
+    Ok(embed)
+}
+
+async fn moves(
+    ctx: &Context,
+    guild_id: GuildId,
+    settings: &mut GuildSettings,
+    locale: &str,
+    options: &[CommandDataOption],
+) -> Result<CreateEmbed, String> {
+    for option in options {
+        let value = match &option.resolved {
+            Some(CommandDataOptionValue::String(value)) => value.trim(),
+            _ => continue,
+        };
+
+        let emoji = if value.eq_ignore_ascii_case("default") {
+            None
+        } else {
+            match usable_emoji(ctx, guild_id, value).await {
+                Ok(emoji) => Some(emoji.to_string()),
+                Err(reason) => {
+                    let reason = tr!(locale, reason);
+
+                    return Err(tr!(locale, "settings.moves.unusable", emoji = value, reason = reason));
+                },
+            }
+        };
+
+        match option.name.as_str() {
+            "rock" => settings.rock_emoji = emoji,
+            "paper" => settings.paper_emoji = emoji,
+            _ => settings.scissors_emoji = emoji,
+        }
+    }
+
+    let emojis = MoveEmojis::from(&*settings);
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.moves.current")
+    } else {
+        tr!(locale, "settings.moves.updated")
+    };
+
+    let mut embed = settings_embed(settings, locale, description);
+
+    embed
+        .field(tr!(locale, "moves.rock"), emojis.rock, true)
+        .field(tr!(locale, "moves.paper"), emojis.paper, true)
+        .field(tr!(locale, "moves.scissors"), emojis.scissors, true);
+
+    Ok(embed)
+}
+
+fn buttons(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::String(value)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.button_labels = ButtonLabels::from_setting(value).map(ButtonLabels::as_setting).map(String::from);
+    }
+
+    let labels = ButtonLabels::from(&*settings);
+    let labels = tr!(locale, &format!("settings.buttons.{}", labels.as_setting()));
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.buttons.current", labels = labels)
+    } else {
+        tr!(locale, "settings.buttons.updated", labels = labels)
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn stats(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Boolean(global)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.global_stats = Some(*global);
+    }
+
+    let scope = if settings.global_stats.unwrap_or(true) {
+        tr!(locale, "settings.stats.global")
+    } else {
+        tr!(locale, "settings.stats.local")
+    };
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.stats.current", scope = scope)
+    } else {
+        tr!(locale, "settings.stats.updated", scope = scope)
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn retention(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Integer(days)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        // Zero follows the retention of the bot again
+        settings.retention_days = Some(*days as u64).filter(|days| *days > 0);
+    }
+
+    let period = match settings.retention_days.or(config::get().retention_days) {
+        Some(days) => tr!(locale, "settings.retention.days", count = days),
+        None => tr!(locale, "settings.retention.forever"),
+    };
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.retention.current", period = period)
+    } else {
+        tr!(locale, "settings.retention.updated", period = period)
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+async fn rematch(
+    ctx: &Context,
+    guild_id: GuildId,
+    settings: &mut GuildSettings,
+    locale: &str,
+    options: &[CommandDataOption],
+) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::String(value)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        let value = value.trim();
+
+        settings.rematch_emoji = if value.eq_ignore_ascii_case("none") {
+            None
+        } else {
+            let emoji = match ReactionType::try_from(value) {
+                // Anything that is not a custom emoji parses as a Unicode one, so words are ruled out here
+                Ok(ReactionType::Unicode(emoji)) if !emoji.chars().any(|c| c.is_alphanumeric()) => {
+                    ReactionType::Unicode(emoji)
+                },
+                Ok(ReactionType::Custom { .. }) => usable_emoji(ctx, guild_id, value).await.map_err(|reason| {
+                    tr!(locale, "settings.moves.unusable", emoji = value, reason = tr!(locale, reason))
+                })?,
+                _ => return Err(tr!(locale, "settings.rematch.invalid", value = value)),
+            };
+
+            Some(emoji.to_string())
+        };
+    }
+
+    let minutes = REMATCH_WINDOW.as_secs() / 60;
+
+    let description = match (&settings.rematch_emoji, options.is_empty()) {
+        (Some(emoji), true) => tr!(locale, "settings.rematch.current", emoji = emoji, minutes = minutes),
+        (Some(emoji), false) => tr!(locale, "settings.rematch.updated", emoji = emoji, minutes = minutes),
+        (None, true) => tr!(locale, "settings.rematch.current_disabled"),
+        (None, false) => tr!(locale, "settings.rematch.disabled"),
+    };
+
+    let mut embed = settings_embed(settings, locale, description);
+
+    if settings.rematch_emoji.is_some() && !config::get().gateway.rematch_reactions {
+        embed.field(tr!(locale, "embeds.warning"), tr!(locale, "settings.rematch.unavailable"), false);
+    }
+
+    Ok(embed)
+}
+
+fn highlights(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    for option in options {
+        match &option.resolved {
+            Some(CommandDataOptionValue::Channel(channel)) => settings.highlights_channel_id = Some(channel.id.0),
+            Some(CommandDataOptionValue::Boolean(true)) => settings.highlights_channel_id = None,
+            _ => {},
+        }
+    }
+
+    let description = match (settings.highlights_channel_id, options.is_empty()) {
+        (Some(id), true) => tr!(locale, "settings.highlights.current", channel = ChannelId(id).mention()),
+        (Some(id), false) => tr!(locale, "settings.highlights.updated", channel = ChannelId(id).mention()),
+        (None, true) => tr!(locale, "settings.highlights.current_disabled"),
+        (None, false) => tr!(locale, "settings.highlights.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn ladder(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Integer(range)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.ladder_range = Some(*range as u32).filter(|range| *range != DEFAULT_RANGE);
+    }
+
+    let range = settings.ladder_range.unwrap_or(DEFAULT_RANGE);
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.ladder.current", range = range)
+    } else {
+        tr!(locale, "settings.ladder.updated", range = range)
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn layout(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::String(value)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.layout = Layout::from_setting(value).map(Layout::as_setting).map(String::from);
+    }
+
+    let layout = layout::name(Layout::from(&*settings), locale);
+
+    let description = if options.is_empty() {
+        tr!(locale, "settings.layout.current", layout = layout)
+    } else {
+        tr!(locale, "settings.layout.updated", layout = layout)
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+async fn stream(
+    ctx: &Context,
+    guild_id: GuildId,
+    settings: &mut GuildSettings,
+    locale: &str,
+    options: &[CommandDataOption],
+) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Boolean(enabled)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.stream_mode = Some(*enabled);
+
+        // The live scores belong to the event that has just ended
+        if !*enabled {
+            stream::stop(ctx, guild_id).await;
+        }
+    }
+
+    let description = match (settings.stream_mode.unwrap_or(false), options.is_empty()) {
+        (true, true) => tr!(locale, "settings.stream.current_enabled"),
+        (true, false) => tr!(locale, "settings.stream.enabled"),
+        (false, true) => tr!(locale, "settings.stream.current_disabled"),
+        (false, false) => tr!(locale, "settings.stream.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn modal_moves(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Boolean(enabled)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.modal_moves = Some(*enabled);
+    }
+
+    let description = match (settings.modal_moves.unwrap_or(false), options.is_empty()) {
+        (true, true) => tr!(locale, "settings.modal_moves.current_enabled"),
+        (true, false) => tr!(locale, "settings.modal_moves.enabled"),
+        (false, true) => tr!(locale, "settings.modal_moves.current_disabled"),
+        (false, false) => tr!(locale, "settings.modal_moves.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn queue(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Boolean(enabled)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.queue_games = Some(*enabled);
+    }
+
+    let description = match (settings.queue_games.unwrap_or(false), options.is_empty()) {
+        (true, true) => tr!(locale, "settings.queue.current_enabled"),
+        (true, false) => tr!(locale, "settings.queue.enabled"),
+        (false, true) => tr!(locale, "settings.queue.current_disabled"),
+        (false, false) => tr!(locale, "settings.queue.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn afk(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    for option in options {
+        let value = match option.resolved {
+            Some(CommandDataOptionValue::Integer(value)) => value as u64,
+            _ => continue,
+        };
+
+        match option.name.as_str() {
+            // 0 turns the timer off
+            "timeout" => settings.turn_timeout = Some(value).filter(|timeout| *timeout != 0),
+            _ => settings.afk_warning = Some(value).filter(|warning| *warning != AFK_WARNING.as_secs()),
+        }
+    }
+
+    let warning = settings.afk_warning.unwrap_or(AFK_WARNING.as_secs());
+
+    let description = match settings.turn_timeout {
+        Some(timeout) if warning >= timeout => return Err(tr!(locale, "settings.afk.warning_too_long", timeout = timeout)),
+        Some(timeout) if options.is_empty() => tr!(locale, "settings.afk.current", timeout = timeout, warning = warning),
+        Some(timeout) => tr!(locale, "settings.afk.updated", timeout = timeout, warning = warning),
+        None if options.is_empty() => tr!(locale, "settings.afk.current_disabled"),
+        None => tr!(locale, "settings.afk.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn replays(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    if let Some(CommandDataOptionValue::Boolean(public)) = options.first().and_then(|option| option.resolved.as_ref()) {
+        settings.public_replays = Some(*public);
+    }
+
+    let description = match (settings.public_replays.unwrap_or(false), options.is_empty()) {
+        (true, true) => tr!(locale, "settings.replays.current_public"),
+        (true, false) => tr!(locale, "settings.replays.public"),
+        (false, true) => tr!(locale, "settings.replays.current_private"),
+        (false, false) => tr!(locale, "settings.replays.private"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn digest(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    for option in options {
+        match &option.resolved {
+            Some(CommandDataOptionValue::Channel(channel)) => settings.digest_channel_id = Some(channel.id.0),
+            Some(CommandDataOptionValue::Boolean(true)) => settings.digest_channel_id = None,
+            _ => {},
+        }
+    }
+
+    let description = match (settings.digest_channel_id, options.is_empty()) {
+        (Some(id), true) => tr!(locale, "settings.digest.current", channel = ChannelId(id).mention()),
+        (Some(id), false) => tr!(locale, "settings.digest.updated", channel = ChannelId(id).mention()),
+        (None, true) => tr!(locale, "settings.digest.current_disabled"),
+        (None, false) => tr!(locale, "settings.digest.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+fn champions(settings: &mut GuildSettings, locale: &str, options: &[CommandDataOption]) -> Result<CreateEmbed, String> {
+    for option in options {
+        match &option.resolved {
+            Some(CommandDataOptionValue::Channel(channel)) => settings.champion_channel_id = Some(channel.id.0),
+            Some(CommandDataOptionValue::Boolean(true)) => settings.champion_channel_id = None,
+            _ => {},
+        }
+    }
+
+    let description = match (settings.champion_channel_id, options.is_empty()) {
+        (Some(id), true) => tr!(locale, "settings.champions.current", channel = ChannelId(id).mention()),
+        (Some(id), false) => tr!(locale, "settings.champions.updated", channel = ChannelId(id).mention()),
+        (None, true) => tr!(locale, "settings.champions.current_disabled"),
+        (None, false) => tr!(locale, "settings.champions.disabled"),
+    };
+
+    Ok(settings_embed(settings, locale, description))
+}
+
+/// Makes sure the string is a custom emoji of the guild that the bot is allowed to use in components,
+/// returning the resource key of the reason otherwise.
+async fn usable_emoji(ctx: &Context, guild_id: GuildId, input: &str) -> Result<ReactionType, &'static str> {
+    let id = match ReactionType::try_from(input) {
+        Ok(ReactionType::Custom { id, .. }) => id,
+        _ => return Err("settings.moves.not_custom"),
+    };
+
+    let guild_emoji = guild_id.emoji(&ctx.http, id).await
+        .map_err(|_| "settings.moves.foreign")?;
+
+    if !guild_emoji.available {
+        return Err("settings.moves.unavailable");
+    }
+
+    if !guild_emoji.roles.is_empty() {
+        return Err("settings.moves.restricted");
+    }
+
+    Ok(guild_emoji.into())
+}
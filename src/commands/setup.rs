@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+use serenity::builder::{CreateApplicationCommand, CreateInteractionResponseData};
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::channel::ChannelType;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info};
+
+use crate::commands::rps::TIMEOUT;
+use crate::commands::{reply_failure, Localized};
+use crate::custom_ids::{self, CustomId};
+use crate::i18n::{self, tr};
+use crate::storage;
+use crate::theme::{self, Palette};
+
+/// The prefix of the custom IDs of the setup components.
+pub const SETUP: &str = "setup";
+
+/// The game timeouts that can be chosen, in minutes.
+const TIMEOUTS: [u64; 4] = [1, 2, 5, 10];
+
+const STEPS: u32 = 4;
+
+/// How long an abandoned setup is kept, which is also how long its message can be edited.
+const LIFETIME: Duration = Duration::from_secs(60 * 15);
+
+/// A select menu holds up to 25 options, one of which clears the channel.
+const MAX_CHANNELS: usize = 24;
+
+lazy_static! {
+    /// The setups in progress, keyed by the ID of the command interaction that has started them.
+    static ref DRAFTS: Mutex<HashMap<u64, Draft>> = Mutex::new(HashMap::new());
+}
+
+/// The answers given so far, which are only saved once the last step has been confirmed.
+#[derive(Clone, Debug)]
+struct Draft {
+    guild_id: GuildId,
+    user_id: UserId,
+    step: u32,
+    channels: Vec<(ChannelId, String)>,
+    games_channel_id: Option<u64>,
+    game_timeout: Option<u64>,
+    log_channel_id: Option<u64>,
+}
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.setup")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let guild_id = match cmd.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.guild_only")).await;
+
+            return;
+        },
+    };
+
+    let is_admin = cmd.member.as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild());
+
+    if !is_admin {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.admin_only")).await;
+
+        return;
+    }
+
+    let settings = match storage::guild_settings(guild_id.0) {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("The guild settings could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "settings.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let mut channels = guild_id.channels(&ctx.http).await
+        .map(|channels| channels.into_values().filter(|channel| channel.kind == ChannelType::Text).collect())
+        .unwrap_or_else(|err| {
+            error!("The channels of the guild could not have been fetched: {:?}", err);
+
+            Vec::new()
+        });
+
+    channels.sort_by_key(|channel| channel.position);
+
+    let draft = Draft {
+        guild_id,
+        user_id: cmd.user.id,
+        step: 0,
+        channels: channels.into_iter()
+            .take(MAX_CHANNELS)
+            .map(|channel| (channel.id, channel.name))
+            .collect(),
+        games_channel_id: settings.games_channel_id,
+        game_timeout: settings.game_timeout,
+        log_channel_id: settings.log_channel_id,
+    };
+
+    DRAFTS.lock().unwrap().insert(cmd.id.0, draft.clone());
+
+    let id = cmd.id.0;
+
+    tokio::spawn(async move {
+        tokio::time::sleep(LIFETIME).await;
+
+        DRAFTS.lock().unwrap().remove(&id);
+    });
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| render(msg.ephemeral(true), id, &draft, &palette, &locale))
+    }).await;
+}
+
+/// What a component press has done to the setup.
+enum Outcome {
+    Advanced(Draft),
+    Saved(Draft),
+    Cancelled,
+    Inactive,
+}
+
+pub async fn handle_component(ctx: &Context, interaction: &MessageComponentInteraction, custom_id: CustomId<'_>) {
+    let palette = Palette::for_guild(interaction.guild_id);
+    let locale = i18n::user_locale(interaction.user.id, &interaction.locale);
+    let id = custom_id.session_id;
+
+    let outcome = {
+        let mut drafts = DRAFTS.lock().unwrap();
+
+        match drafts.get_mut(&id) {
+            Some(draft) if draft.user_id == interaction.user.id && draft.step == custom_id.turn => {
+                match custom_id.action {
+                    "select" => {
+                        let value = interaction.data.values.first().and_then(|value| value.parse().ok());
+
+                        match draft.step {
+                            0 => draft.games_channel_id = value,
+                            1 => draft.game_timeout = value.map(|minutes: u64| minutes * 60),
+                            _ => draft.log_channel_id = value,
+                        }
+
+                        draft.step += 1;
+
+                        Outcome::Advanced(draft.clone())
+                    },
+                    "save" => drafts.remove(&id).map_or(Outcome::Inactive, Outcome::Saved),
+                    _ => {
+                        drafts.remove(&id);
+
+                        Outcome::Cancelled
+                    },
+                }
+            },
+            _ => Outcome::Inactive,
+        }
+    };
+
+    let description = match outcome {
+        Outcome::Advanced(draft) => {
+            let _ = interaction.create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|msg| render(msg, id, &draft, &palette, &locale))
+            }).await;
+
+            return;
+        },
+        Outcome::Saved(draft) => match save(&draft) {
+            Ok(()) => {
+                info!(guild = draft.guild_id.0, "The setup has been saved");
+
+                theme::forget(draft.guild_id);
+
+                tr!(&locale, "setup.saved")
+            },
+            Err(err) => {
+                error!("The guild settings could not have been saved: {:?}", err);
+
+                tr!(&locale, "settings.save_failed")
+            },
+        },
+        Outcome::Cancelled => tr!(&locale, "setup.cancelled"),
+        Outcome::Inactive => tr!(&locale, "setup.inactive"),
+    };
+
+    let _ = interaction.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|msg| {
+                msg
+                    .components(|comp| comp)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.setup")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+            })
+    }).await;
+}
+
+/// Writes the answers over the current settings, so the ones changed with `/settings` in the meantime are kept.
+fn save(draft: &Draft) -> storage::StorageResult<()> {
+    let mut settings = storage::guild_settings(draft.guild_id.0)?;
+
+    settings.games_channel_id = draft.games_channel_id;
+    settings.game_timeout = draft.game_timeout;
+    settings.log_channel_id = draft.log_channel_id;
+
+    storage::save_guild_settings(draft.guild_id.0, &settings)
+}
+
+/// Renders the current step of the setup: a select menu for every question, then a summary to confirm.
+fn render<'a, 'b>(
+    msg: &'b mut CreateInteractionResponseData<'a>,
+    id: u64,
+    draft: &Draft,
+    palette: &Palette,
+    locale: &str,
+) -> &'b mut CreateInteractionResponseData<'a> {
+    let custom_id = |action| custom_ids::encode(SETUP, id, draft.step, action);
+
+    let channel = |channel_id: Option<u64>, none_key| {
+        channel_id.map_or_else(|| tr!(locale, none_key), |id| ChannelId(id).mention().to_string())
+    };
+
+    let timeout = |seconds: Option<u64>| {
+        tr!(locale, "setup.minutes", count = seconds.unwrap_or(TIMEOUT.as_secs()) / 60)
+    };
+
+    msg
+        .embed(|embed| {
+            let question = match draft.step {
+                0 => "setup.games_channel",
+                1 => "setup.timeout",
+                2 => "setup.log_channel",
+                _ => "setup.summary",
+            };
+
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.setup")))
+                .color(palette.confirmation)
+                .description(tr!(locale, question))
+                .footer(|footer| footer.text(tr!(locale, "setup.step", step = draft.step + 1, steps = STEPS)));
+
+            if draft.step >= STEPS - 1 {
+                embed
+                    .field(tr!(locale, "setup.games_channel_field"), channel(draft.games_channel_id, "setup.any_channel"), false)
+                    .field(tr!(locale, "setup.timeout_field"), timeout(draft.game_timeout), false)
+                    .field(tr!(locale, "setup.log_channel_field"), channel(draft.log_channel_id, "setup.no_log_channel"), false);
+            }
+
+            embed
+        })
+        .components(|comp| {
+            if draft.step < STEPS - 1 {
+                comp.create_action_row(|row| {
+                    row.create_select_menu(|menu| {
+                        menu
+                            .custom_id(custom_id("select"))
+                            .options(|options| {
+                                match draft.step {
+                                    1 => {
+                                        for minutes in TIMEOUTS {
+                                            options.create_option(|option| {
+                                                option
+                                                    .label(tr!(locale, "setup.minutes", count = minutes))
+                                                    .value(minutes)
+                                                    .default_selection(draft.game_timeout.unwrap_or(TIMEOUT.as_secs()) == minutes * 60)
+                                            });
+                                        }
+                                    },
+                                    step => {
+                                        let (current, none_key) = if step == 0 {
+                                            (draft.games_channel_id, "setup.any_channel")
+                                        } else {
+                                            (draft.log_channel_id, "setup.no_log_channel")
+                                        };
+
+                                        options.create_option(|option| {
+                                            option
+                                                .label(tr!(locale, none_key))
+                                                .value("none")
+                                                .default_selection(current.is_none())
+                                        });
+
+                                        for (channel_id, name) in &draft.channels {
+                                            options.create_option(|option| {
+                                                option
+                                                    .label(format!("#{}", name))
+                                                    .value(channel_id)
+                                                    .default_selection(current == Some(channel_id.0))
+                                            });
+                                        }
+                                    },
+                                }
+
+                                options
+                            })
+                    })
+                });
+            }
+
+            comp.create_action_row(|row| {
+                if draft.step >= STEPS - 1 {
+                    row.create_button(|button| {
+                        button
+                            .style(ButtonStyle::Success)
+                            .label(tr!(locale, "setup.save"))
+                            .custom_id(custom_id("save"))
+                    });
+                }
+
+                row.create_button(|button| {
+                    button
+                        .style(ButtonStyle::Secondary)
+                        .label(tr!(locale, "setup.cancel"))
+                        .custom_id(custom_id("cancel"))
+                })
+            })
+        })
+}
@@ -0,0 +1,84 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOptionValue};
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::MessageId;
+
+use crate::commands::{reply_failure, rps, Localized};
+use crate::i18n::{self, tr};
+use crate::sessions;
+use crate::spectators;
+use crate::theme::Palette;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.spectate")
+        .create_option(|option| {
+            option
+                .localized("commands.spectate.game")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let link = match cmd.data.options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::String(link)) => link.trim(),
+        _ => "",
+    };
+
+    let session = match parse_link(link).and_then(sessions::by_message) {
+        Some(session) => session,
+        None => {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "spectate.not_found")).await;
+
+            return;
+        },
+    };
+
+    // A game is only spectated by those who could see it anyway
+    if let Some(guild_id) = session.guild_id.filter(|guild_id| Some(*guild_id) != cmd.guild_id) {
+        if guild_id.member(&ctx.http, cmd.user.id).await.is_err() {
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "spectate.not_found")).await;
+
+            return;
+        }
+    }
+
+    if session.channel_id == cmd.channel_id {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, "spectate.same_channel")).await;
+
+        return;
+    }
+
+    if let Err(reason) = spectators::check(session.id, cmd.channel_id) {
+        reply_failure(ctx, cmd, &palette, &tr!(&locale, reason)).await;
+
+        return;
+    }
+
+    let embed = spectators::embed(&session, rps::spectator_view(&session));
+    let content = tr!(&locale, "spectate.mirror", link = link);
+
+    if cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| msg.content(content).set_embed(embed))
+    }).await.is_err() {
+        return;
+    }
+
+    if let Ok(message) = cmd.get_interaction_response(&ctx.http).await {
+        spectators::subscribe(session.id, message.channel_id, message.id);
+    }
+}
+
+/// The message of a link such as `https://discord.com/channels/<guild>/<channel>/<message>`, `@me` standing
+/// for the guild in direct messages; the message ID alone is accepted as well.
+fn parse_link(link: &str) -> Option<MessageId> {
+    link.rsplit('/').next()?.parse().ok().map(MessageId)
+}
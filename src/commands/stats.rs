@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{ApplicationCommandInteraction, CommandDataOption};
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{defer, followup, followup_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::storage::{self, MoveUsage};
+use crate::theme::Theme;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.stats")
+        .create_option(|sub| {
+            sub
+                .localized("commands.stats.moves")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|option| {
+                    option
+                        .localized("commands.stats.moves.user")
+                        .kind(CommandOptionType::User)
+                })
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let subcommand = &cmd.data.options[0];
+
+    if subcommand.name == "moves" {
+        moves(ctx, cmd, &subcommand.options).await;
+    }
+}
+
+/// Shows how often the user throws each move and how well it does, next to the average of the guild,
+/// or of every player sharing their statistics outside of guilds.
+async fn moves(ctx: &Context, cmd: &ApplicationCommandInteraction, options: &[CommandDataOption]) {
+    let Theme { palette, emojis, .. } = Theme::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let user = match options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::User(user, _)) => user,
+        _ => &cmd.user,
+    };
+
+    defer(ctx, cmd).await;
+
+    let guild_id = cmd.guild_id.map(|guild_id| guild_id.0);
+
+    let usages = storage::move_usage(Some(user.id.0), guild_id)
+        .and_then(|own| Ok((own, storage::move_usage(None, guild_id)?)));
+
+    let (own, average) = match usages {
+        Ok(usages) => usages,
+        Err(err) => {
+            error!("The move statistics could not have been read: {:?}", err);
+
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "stats.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let rounds = total(&own);
+
+    if rounds == 0 {
+        followup_failure(ctx, cmd, &palette, &tr!(locale, "stats.no_rounds", user = user.mention())).await;
+
+        return;
+    }
+
+    let description = if guild_id.is_some() {
+        tr!(locale, "stats.moves_guild", user = user.mention())
+    } else {
+        tr!(locale, "stats.moves_global", user = user.mention())
+    };
+
+    let average_rounds = total(&average);
+
+    followup(ctx, cmd, |embed| {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.stats")).icon_url(user.face()))
+            .color(palette.success)
+            .description(description)
+            .footer(|footer| footer.text(tr!(locale, "stats.rounds", count = rounds)));
+
+        for (name, emoji) in [("rock", &emojis.rock), ("paper", &emojis.paper), ("scissors", &emojis.scissors)] {
+            let usage = own.get(name).copied().unwrap_or_default();
+            let average = percentage(average.get(name).map_or(0, |usage| usage.thrown), average_rounds);
+
+            let value = if usage.thrown == 0 {
+                tr!(locale, "stats.move_unused", average = average)
+            } else {
+                tr!(
+                    locale,
+                    "stats.move_usage",
+                    share = percentage(usage.thrown, rounds),
+                    average = average,
+                    rate = percentage(usage.won, usage.thrown),
+                )
+            };
+
+            embed.field(format!("{} {}", emoji, tr!(locale, &format!("moves.{}", name))), value, true);
+        }
+
+        embed
+    }).await;
+}
+
+fn total(usages: &HashMap<String, MoveUsage>) -> u64 {
+    usages.values().map(|usage| usage.thrown).sum()
+}
+
+/// The rounded share of the part in the whole, or zero if the whole is empty.
+fn percentage(part: u64, whole: u64) -> u64 {
+    (part * 100 + whole / 2).checked_div(whole).unwrap_or_default()
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{defer, followup, followup_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::storage::{self, Records, StorageResult};
+use crate::theme::Palette;
+
+/// How long the records are reused before they are read again, as finding the streaks goes through the whole history.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+lazy_static! {
+    /// The records per guild, or `None` for the global ones.
+    static ref CACHE: Mutex<HashMap<Option<GuildId>, CachedRecords>> = Mutex::new(HashMap::new());
+}
+
+struct CachedRecords {
+    read_at: Instant,
+    records: Records,
+}
+
+/// Drops the cached records, so they are read again with the current config.
+pub fn clear_cache() {
+    CACHE.lock().unwrap().clear();
+}
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.localized("commands.top")
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    defer(ctx, cmd).await;
+
+    let Records { longest_streak, most_matches, longest_match } = match records(cmd.guild_id) {
+        Ok(records) => records,
+        Err(err) => {
+            error!("The records could not have been read: {:?}", err);
+
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "top.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let (user_id, count) = match most_matches {
+        Some(most_matches) => most_matches,
+        None => {
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "top.no_matches")).await;
+
+            return;
+        },
+    };
+
+    let description = if cmd.guild_id.is_some() {
+        tr!(locale, "top.server")
+    } else {
+        tr!(locale, "top.global")
+    };
+
+    followup(ctx, cmd, |embed| {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.top")))
+            .color(palette.success)
+            .description(description)
+            .field(
+                tr!(locale, "top.most_matches"),
+                tr!(locale, "top.matches_entry", user = UserId(user_id).mention(), count = count),
+                false,
+            );
+
+        // Both are missing once every match has been pruned, leaving only the totals
+        if let Some((user_id, count)) = longest_streak {
+            embed.field(
+                tr!(locale, "top.longest_streak"),
+                tr!(locale, "top.streak_entry", user = UserId(user_id).mention(), count = count),
+                false,
+            );
+        }
+
+        if let Some(game) = &longest_match {
+            let value = tr!(
+                locale,
+                "top.longest_match_entry",
+                starter = UserId(game.starter_id).mention(),
+                opponent = UserId(game.opponent_id).mention(),
+                winner = UserId(game.winner_id).mention(),
+                rounds = game.rounds,
+                date = format!("<t:{}:D>", game.finished_at),
+            );
+
+            embed.field(tr!(locale, "top.longest_match"), value, false);
+        }
+
+        embed
+    }).await;
+}
+
+/// Reads the records of the guild, or the global ones outside of guilds, reusing them for a while.
+fn records(guild_id: Option<GuildId>) -> StorageResult<Records> {
+    if let Some(cached) = CACHE.lock().unwrap().get(&guild_id) {
+        if cached.read_at.elapsed() < CACHE_TTL {
+            return Ok(cached.records.clone());
+        }
+    }
+
+    let records = storage::records(guild_id.map(|guild_id| guild_id.0))?;
+
+    let mut cache = CACHE.lock().unwrap();
+
+    // The expired records of other guilds are dropped along the way, so the cache does not grow forever
+    cache.retain(|_, cached| cached.read_at.elapsed() < CACHE_TTL);
+    cache.insert(guild_id, CachedRecords { read_at: Instant::now(), records: records.clone() });
+
+    Ok(records)
+}
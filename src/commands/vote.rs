@@ -0,0 +1,74 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::Timestamp;
+
+use tracing::error;
+
+use crate::commands::{reply_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::storage;
+use crate::theme::Palette;
+use crate::votes;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd.localized("commands.vote")
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+
+    let user_votes = match storage::votes(cmd.user.id.0) {
+        Ok(user_votes) => user_votes,
+        Err(err) => {
+            error!("The votes could not have been read: {:?}", err);
+
+            reply_failure(ctx, cmd, &palette, &tr!(&locale, "vote.load_failed")).await;
+
+            return;
+        },
+    };
+
+    let mut description = tr!(&locale, "vote.description");
+
+    if user_votes.count > 0 {
+        description = format!("{}\n\n{}", description, tr!(&locale, "vote.count", count = user_votes.count));
+    }
+
+    // top.gg only counts a vote every so often, which is worth knowing before opening the page for nothing
+    let next_vote = user_votes.latest
+        .map(|latest| latest + votes::COOLDOWN.as_secs() as i64)
+        .filter(|next_vote| *next_vote > Timestamp::now().unix_timestamp());
+
+    if let Some(next_vote) = next_vote {
+        description = format!("{}\n{}", description, tr!(&locale, "vote.cooldown", time = format!("<t:{}:R>", next_vote)));
+    }
+
+    let _ = cmd.create_interaction_response(&ctx.http, |response| {
+        response
+            .kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|msg| {
+                msg
+                    .ephemeral(true)
+                    .embed(|embed| {
+                        embed
+                            .author(|a| a.name(tr!(&locale, "embeds.vote")))
+                            .color(palette.success)
+                            .description(description)
+                    })
+                    .components(|comp| {
+                        comp.create_action_row(|row| {
+                            row.create_button(|button| {
+                                button
+                                    .style(ButtonStyle::Link)
+                                    .label(tr!(&locale, "vote.button"))
+                                    .url(votes::url(cmd.application_id))
+                            })
+                        })
+                    })
+            })
+    }).await;
+}
@@ -0,0 +1,103 @@
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::commands::{defer, followup, followup_failure, reply_failure, Localized};
+use crate::i18n::{self, tr};
+use crate::storage::{self, HeadToHead};
+use crate::theme::Palette;
+
+/// How many of the latest results are shown.
+const RECENT_RESULTS: usize = 5;
+
+pub fn register(cmd: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    cmd
+        .localized("commands.vs")
+        .create_option(|option| {
+            option
+                .localized("commands.vs.user")
+                .kind(CommandOptionType::User)
+                .required(true)
+        })
+}
+
+pub async fn execute(ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    let palette = Palette::for_guild(cmd.guild_id);
+    let locale = i18n::user_locale(cmd.user.id, &cmd.locale);
+    let locale = locale.as_str();
+
+    let opponent = match cmd.data.options.first().and_then(|option| option.resolved.as_ref()) {
+        Some(CommandDataOptionValue::User(user, _)) => user,
+        _ => return,
+    };
+
+    if opponent.id == cmd.user.id {
+        reply_failure(ctx, cmd, &palette, &tr!(locale, "vs.self")).await;
+
+        return;
+    }
+
+    defer(ctx, cmd).await;
+
+    let history = match storage::head_to_head(cmd.user.id.0, opponent.id.0, cmd.guild_id.map(|guild_id| guild_id.0)) {
+        Ok(history) => history,
+        Err(err) => {
+            error!("The head-to-head record could not have been read: {:?}", err);
+
+            followup_failure(ctx, cmd, &palette, &tr!(locale, "profile.load_failed")).await;
+
+            return;
+        },
+    };
+
+    if history.results.is_empty() {
+        followup_failure(ctx, cmd, &palette, &tr!(locale, "vs.no_matches", user = opponent.mention())).await;
+
+        return;
+    }
+
+    let HeadToHead { results, drawn_rounds } = history;
+
+    let wins = results.iter().filter(|won| **won).count();
+    let losses = results.len() - wins;
+
+    let recent = results.iter()
+        .take(RECENT_RESULTS)
+        .map(|won| if *won { "\u{1F7E9}" } else { "\u{1F7E5}" })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let streaks = tr!(
+        locale,
+        "vs.streaks",
+        user = cmd.user.mention(),
+        user_streak = longest_streak(&results, true),
+        opponent = opponent.mention(),
+        opponent_streak = longest_streak(&results, false),
+    );
+
+    followup(ctx, cmd, |embed| {
+        embed
+            .author(|a| a.name(tr!(locale, "embeds.vs")))
+            .color(palette.success)
+            .description(tr!(locale, "vs.description", user = cmd.user.mention(), opponent = opponent.mention()))
+            .field(tr!(locale, "vs.record"), tr!(locale, "vs.wins_losses", wins = wins, losses = losses), true)
+            .field(tr!(locale, "vs.drawn_rounds"), drawn_rounds, true)
+            .field(tr!(locale, "vs.recent"), recent, false)
+            .field(tr!(locale, "vs.longest_streaks"), streaks, false)
+    }).await;
+}
+
+/// The longest run of consecutive matches won by the user (`true`) or by the opponent (`false`).
+fn longest_streak(results: &[bool], side: bool) -> usize {
+    results
+        .split(|won| *won != side)
+        .map(<[bool]>::len)
+        .max()
+        .unwrap_or_default()
+}
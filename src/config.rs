@@ -0,0 +1,482 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+use serde::Deserialize;
+
+use serenity::prelude::GatewayIntents;
+
+use toml::value::{Table, Value};
+
+use tracing::level_filters::LevelFilter;
+use tracing::warn;
+
+lazy_static! {
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+    /// The merged tables the config has last been read from, which the next reload is compared to.
+    static ref TABLE: RwLock<Value> = RwLock::new(Value::Table(Table::new()));
+}
+
+/// The keys only read when the bot starts, so changing them takes a restart rather than a reload.
+const RESTART_KEYS: [&str; 13] = [
+    "database_path",
+    "database_key",
+    "signing_key",
+    "commands_guild",
+    "owner_guild",
+    "discord_token",
+    "sentry_dsn",
+    "gateway",
+    "cache",
+    "http",
+    "ipc",
+    "otlp",
+    "backups.interval_hours",
+];
+
+/// The bot configuration, read from the TOML file at `CONFIG_PATH` (`config.toml` by default).
+/// Every key is optional; see `config.example.toml` for the documented defaults.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The stage the bot runs in, taken from the `ENVIRONMENT` variable rather than from the file.
+    #[serde(skip)]
+    pub environment: Environment,
+    /// Defaults to `rps.sqlite3` in production, and to `rps.<environment>.sqlite3` in the other environments.
+    pub database_path: String,
+    /// Where the key the database is encrypted with is read from; only used with the `sqlcipher` feature,
+    /// without which the bot refuses to start if it is set.
+    pub database_key: Option<SecretSource>,
+    /// Registers the commands in this guild only, where changes show up at once, instead of globally;
+    /// meant for the overlays of the development and staging environments.
+    pub commands_guild: Option<u64>,
+    /// The guild the owner-only commands are registered in, rather than globally; the one of `commands_guild` if unset.
+    pub owner_guild: Option<u64>,
+    /// Where the Discord token is read from.
+    pub discord_token: SecretSource,
+    /// Overrides the `SENTRY_DSN` environment variable; only used with the `sentry` feature.
+    pub sentry_dsn: Option<String>,
+    /// Starts the bot in maintenance mode, which the owners can leave with `/maintenance`.
+    pub maintenance: bool,
+    /// The secret signing the custom IDs of the game buttons; the Discord token is used if unset.
+    pub signing_key: Option<String>,
+    /// How long a game may stay in progress before the sweeper ends it, whatever its timeout; only reached
+    /// by the sessions that have failed to expire.
+    pub max_session_minutes: u64,
+    /// How many days the matches are kept before being folded into the totals of their players; forever if unset.
+    pub retention_days: Option<u64>,
+    /// How many days the owners have to undo a reset of the statistics of a player before it becomes permanent.
+    pub stats_reset_grace_days: u64,
+    pub gateway: GatewayConfig,
+    pub cache: CacheConfig,
+    pub http: HttpConfig,
+    pub ipc: IpcConfig,
+    pub otlp: OtlpConfig,
+    pub topgg: TopggConfig,
+    pub bot_lists: BotListsConfig,
+    pub backups: BackupsConfig,
+    pub dashboard: DashboardConfig,
+    pub activity: ActivityConfig,
+    /// The feature flags and whether they are enabled by default; guilds can override them at runtime.
+    pub flags: HashMap<String, bool>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            environment: Environment::default(),
+            database_path: Environment::default().database_path(),
+            database_key: None,
+            commands_guild: None,
+            owner_guild: None,
+            discord_token: SecretSource::default(),
+            sentry_dsn: None,
+            maintenance: false,
+            signing_key: None,
+            max_session_minutes: 30,
+            retention_days: None,
+            stats_reset_grace_days: 30,
+            gateway: GatewayConfig::default(),
+            cache: CacheConfig::default(),
+            http: HttpConfig::default(),
+            ipc: IpcConfig::default(),
+            otlp: OtlpConfig::default(),
+            topgg: TopggConfig::default(),
+            bot_lists: BotListsConfig::default(),
+            backups: BackupsConfig::default(),
+            dashboard: DashboardConfig::default(),
+            activity: ActivityConfig::default(),
+            flags: HashMap::new(),
+        }
+    }
+}
+
+/// The stage a deployment of the bot belongs to, so the same binary can run in all of them without sharing anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Environment {
+    Development,
+    Staging,
+    #[default]
+    Production,
+}
+
+impl Environment {
+    fn from_env() -> Result<Self, String> {
+        let name = match std::env::var("ENVIRONMENT") {
+            Ok(name) => name,
+            Err(_) => return Ok(Self::default()),
+        };
+
+        match name.to_ascii_lowercase().as_str() {
+            "dev" | "development" => Ok(Self::Development),
+            "staging" => Ok(Self::Staging),
+            "prod" | "production" => Ok(Self::Production),
+            _ => Err(format!("Unknown environment: {}", name)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Development => "dev",
+            Self::Staging => "staging",
+            Self::Production => "prod",
+        }
+    }
+
+    /// Only development logs the details of every request and event.
+    pub fn log_level(self) -> LevelFilter {
+        match self {
+            Self::Development => LevelFilter::DEBUG,
+            Self::Staging | Self::Production => LevelFilter::INFO,
+        }
+    }
+
+    /// The default database, distinct per environment so a test deployment never touches the real matches.
+    fn database_path(self) -> String {
+        match self {
+            Self::Production => "rps.sqlite3".to_string(),
+            _ => format!("rps.{}.sqlite3", self.name()),
+        }
+    }
+}
+
+/// Where a secret is read from, so it does not have to be passed around in the environment of the process.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum SecretSource {
+    /// An environment variable.
+    Env { variable: String },
+    /// A file containing nothing but the secret, e.g. mounted from a Kubernetes secret.
+    File { path: String },
+    /// A Docker secret, i.e. the file of the same name in `/run/secrets`.
+    DockerSecret { name: String },
+    /// A field of a KV secret in HashiCorp Vault, with the token in the `VAULT_TOKEN` environment variable.
+    Vault { address: String, path: String, field: String },
+    /// A secret in AWS Secrets Manager, with the credentials in the usual `AWS_*` environment variables;
+    /// the field picks one value out of a JSON secret.
+    AwsSecretsManager { region: String, secret_id: String, field: Option<String> },
+}
+
+impl Default for SecretSource {
+    fn default() -> Self {
+        Self::Env { variable: "DISCORD_TOKEN".to_string() }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GatewayConfig {
+    /// Ends the sessions whose game message has been deleted, alone or in bulk; needs the message intents.
+    pub message_delete_cleanup: bool,
+    /// Lets the players of a finished game start a rematch by reacting with the emoji their guild has chosen.
+    pub rematch_reactions: bool,
+    /// The IDs of the bots allowed to play through [`crate::protocol`]; the protocol is disabled if empty.
+    pub protocol_bots: Vec<u64>,
+    /// Seeds the generator the moves of the bot are drawn from in the protocol, which makes them reproducible,
+    /// e.g. to replay a sequence of rounds a bot has reported; a random seed is drawn and logged otherwise.
+    pub protocol_seed: Option<u64>,
+    /// Intents requested on top of the ones the enabled features need, e.g. `["GUILD_MEMBERS"]`.
+    pub extra_intents: Vec<String>,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            message_delete_cleanup: true,
+            rematch_reactions: false,
+            protocol_bots: Vec::new(),
+            protocol_seed: None,
+            extra_intents: Vec::new(),
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// Computes the minimal set of intents: interactions need none at all, so only
+    /// the guild events and whatever the enabled optional features rely on are requested.
+    pub fn intents(&self) -> GatewayIntents {
+        let mut intents = GatewayIntents::GUILDS;
+
+        if self.message_delete_cleanup || !self.protocol_bots.is_empty() {
+            intents |= GatewayIntents::GUILD_MESSAGES | GatewayIntents::DIRECT_MESSAGES;
+        }
+
+        if self.rematch_reactions {
+            intents |= GatewayIntents::GUILD_MESSAGE_REACTIONS;
+        }
+
+        for name in &self.extra_intents {
+            match parse_intent(name) {
+                Some(intent) => intents |= intent,
+                None => warn!("Unknown gateway intent in the config: {}", name),
+            }
+        }
+
+        intents
+    }
+}
+
+/// Only used when the bot is built with the `cache` feature.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// The number of messages cached per channel; the game never reads cached messages.
+    pub max_messages: usize,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// The address serving `/metrics`, e.g. `127.0.0.1:9100`; nothing is served if unset.
+    pub listen: Option<SocketAddr>,
+    /// The bearer token of the admin endpoints under `/admin/`, which are not served if unset.
+    pub admin_token: Option<String>,
+    /// The URL the server is reachable at from the outside, e.g. `https://rps.example.com`, which the links
+    /// to the replay pages are built on; the results link to none if unset.
+    pub public_url: Option<String>,
+}
+
+/// Only used on Unix.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    /// The path of the Unix socket accepting the control commands; nothing is listened to if unset.
+    pub socket: Option<String>,
+}
+
+/// Only used when the bot is built with the `otlp` feature.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct OtlpConfig {
+    /// The gRPC endpoint of the collector, e.g. `http://localhost:4317`; nothing is exported if unset.
+    pub endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            service_name: "rps-rs-bot".to_string(),
+        }
+    }
+}
+
+/// Only used when the bot is built with the `topgg` feature.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TopggConfig {
+    /// The authorization top.gg sends its vote webhooks with; votes are neither received nor shown if unset.
+    pub webhook_secret: Option<String>,
+}
+
+/// The API tokens of the bot lists the statistics are posted to; nothing is posted to the lists without one.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BotListsConfig {
+    pub top_gg: Option<String>,
+    pub discord_bots_gg: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BackupsConfig {
+    /// The directory the backups are written to.
+    pub directory: String,
+    /// How many hours pass between two scheduled backups; backups are only made on demand if unset.
+    pub interval_hours: Option<u64>,
+    /// How many of the latest backups are kept in the directory; all of them if unset.
+    pub keep: Option<usize>,
+    /// The bucket every backup is uploaded to as well.
+    pub s3: Option<S3Config>,
+}
+
+impl Default for BackupsConfig {
+    fn default() -> Self {
+        Self {
+            directory: "backups".to_string(),
+            interval_hours: None,
+            keep: None,
+            s3: None,
+        }
+    }
+}
+
+/// An S3-compatible bucket, addressed by path so other providers than AWS work as well;
+/// the credentials are read from the usual `AWS_*` environment variables.
+#[derive(Clone, Debug, Deserialize)]
+pub struct S3Config {
+    /// The endpoint of the service, e.g. `https://s3.eu-central-1.amazonaws.com`.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    /// Prepended to the names of the backups, e.g. `rps/`.
+    #[serde(default)]
+    pub prefix: String,
+}
+
+/// Only used when `http.listen` is set.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DashboardConfig {
+    /// Serves the dashboard under `/dashboard/`.
+    pub enabled: bool,
+    /// The ID of the application the users log in to the dashboard through; anyone reaching the dashboard sees
+    /// everything unless both it and the secret are set, along with `http.public_url`.
+    pub client_id: Option<u64>,
+    /// Where the OAuth2 secret of the application is read from.
+    pub client_secret: Option<SecretSource>,
+}
+
+/// The activity report the owners of the bot see with `/debug activity`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ActivityConfig {
+    /// Posts the report in this channel after every midnight UTC; only shown on demand if unset.
+    pub channel_id: Option<u64>,
+}
+
+fn parse_intent(name: &str) -> Option<GatewayIntents> {
+    let intent = match name.to_ascii_uppercase().as_str() {
+        "GUILDS" => GatewayIntents::GUILDS,
+        "GUILD_MEMBERS" => GatewayIntents::GUILD_MEMBERS,
+        "GUILD_BANS" => GatewayIntents::GUILD_BANS,
+        "GUILD_EMOJIS_AND_STICKERS" => GatewayIntents::GUILD_EMOJIS_AND_STICKERS,
+        "GUILD_INTEGRATIONS" => GatewayIntents::GUILD_INTEGRATIONS,
+        "GUILD_WEBHOOKS" => GatewayIntents::GUILD_WEBHOOKS,
+        "GUILD_INVITES" => GatewayIntents::GUILD_INVITES,
+        "GUILD_VOICE_STATES" => GatewayIntents::GUILD_VOICE_STATES,
+        "GUILD_PRESENCES" => GatewayIntents::GUILD_PRESENCES,
+        "GUILD_MESSAGES" => GatewayIntents::GUILD_MESSAGES,
+        "GUILD_MESSAGE_REACTIONS" => GatewayIntents::GUILD_MESSAGE_REACTIONS,
+        "GUILD_MESSAGE_TYPING" => GatewayIntents::GUILD_MESSAGE_TYPING,
+        "DIRECT_MESSAGES" => GatewayIntents::DIRECT_MESSAGES,
+        "DIRECT_MESSAGE_REACTIONS" => GatewayIntents::DIRECT_MESSAGE_REACTIONS,
+        "DIRECT_MESSAGE_TYPING" => GatewayIntents::DIRECT_MESSAGE_TYPING,
+        "MESSAGE_CONTENT" => GatewayIntents::MESSAGE_CONTENT,
+        "GUILD_SCHEDULED_EVENTS" => GatewayIntents::GUILD_SCHEDULED_EVENTS,
+        _ => return None,
+    };
+
+    Some(intent)
+}
+
+/// Reads the config file, then the overlay of the environment next to it (e.g. `config.staging.toml`),
+/// whose keys replace the ones of the file; the defaults are kept for the keys set in neither.
+pub fn load() -> Result<(), Box<dyn Error + Send + Sync>> {
+    reload().map(|_| ())
+}
+
+/// Reads the config again like `load`, returning the dotted keys whose values have changed, in order.
+pub fn reload() -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let environment = Environment::from_env()?;
+
+    let path = std::env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+    let overlay_path = Path::new(&path).with_extension(format!("{}.toml", environment.name()));
+
+    let mut table = Value::Table(Table::new());
+
+    for path in [Path::new(&path), &overlay_path] {
+        if path.exists() {
+            merge(&mut table, toml::from_str(&std::fs::read_to_string(path)?)?);
+        }
+    }
+
+    if let Value::Table(table) = &mut table {
+        table.entry("database_path").or_insert_with(|| Value::String(environment.database_path()));
+    }
+
+    let mut config = table.clone().try_into::<Config>()?;
+
+    config.environment = environment;
+
+    let mut changes = Vec::new();
+
+    diff("", &TABLE.read().unwrap(), &table, &mut changes);
+
+    *CONFIG.write().unwrap() = config;
+    *TABLE.write().unwrap() = table;
+
+    Ok(changes)
+}
+
+/// Whether a change to the key only applies once the bot is restarted.
+pub fn needs_restart(key: &str) -> bool {
+    RESTART_KEYS.iter().any(|prefix| key == *prefix || key.starts_with(&format!("{}.", prefix)))
+}
+
+/// Collects the keys whose values differ between the tables, descending into the tables present in both.
+fn diff(prefix: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    let (old, new) = match (old, new) {
+        (Value::Table(old), Value::Table(new)) => (old, new),
+        _ => {
+            if old != new {
+                changes.push(prefix.to_string());
+            }
+
+            return;
+        },
+    };
+
+    let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+
+    for key in keys {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) => diff(&path, old, new, changes),
+            _ => changes.push(path),
+        }
+    }
+}
+
+/// Merges the tables recursively, any other value of the overlay replacing the one of the base.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, overlay) => *base = overlay,
+    }
+}
+
+pub fn get() -> Config {
+    CONFIG.read().unwrap().clone()
+}
+
+/// Points `database_path` at another file, which only applies before the database is first opened;
+/// `load-test` uses it to keep its synthetic games out of the real database.
+pub fn set_database_path(path: &str) {
+    CONFIG.write().unwrap().database_path = path.to_string();
+}
@@ -0,0 +1,181 @@
+//! The custom IDs of every component the bot sends (the game buttons, but also those of `/setup`, `/debug`, etc.,
+//! and the form of the moves typed in modal mode), structured as `<game>:<session>:<turn>:<action>:<signature>`.
+//! The signature is a truncated HMAC of everything before it, keyed with a secret that never leaves the server,
+//! so a crafted interaction can neither make up a button of another session nor replay one from an earlier turn,
+//! and is answered with an error rather than handled. The move already chosen by the starter is never encoded here,
+//! as the opponent could read it from their client.
+
+use std::sync::RwLock;
+
+use hmac::{Hmac, Mac};
+
+use lazy_static::lazy_static;
+
+use sha2::Sha256;
+
+use tracing::warn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The number of HMAC bytes kept in a custom ID, which must not exceed 100 characters in total.
+const SIGNATURE_LENGTH: usize = 8;
+
+lazy_static! {
+    static ref KEY: RwLock<Vec<u8>> = RwLock::new(Vec::new());
+}
+
+/// A verified custom ID of a game button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustomId<'a> {
+    pub session_id: u64,
+    /// The number of presses the session had seen when the button was sent.
+    pub turn: u32,
+    pub action: &'a str,
+}
+
+/// Sets the secret the custom IDs are signed with; the buttons sent before a change of the key stop working.
+pub fn set_key(key: &[u8]) {
+    *KEY.write().unwrap() = key.to_vec();
+}
+
+fn mac(payload: &str) -> HmacSha256 {
+    keyed_mac(&KEY.read().unwrap(), payload)
+}
+
+fn keyed_mac(key: &[u8], payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+
+    mac.update(payload.as_bytes());
+
+    mac
+}
+
+/// Derives a secret number below the modulo from the payload, used to hide values shown in public messages.
+pub fn mask(payload: &str, modulo: u8) -> u8 {
+    mac(payload).finalize().into_bytes()[0] % modulo
+}
+
+/// Derives a token nobody can guess without the secret from the payload, which must never repeat.
+pub fn token(payload: &str) -> String {
+    keyed_token(&KEY.read().unwrap(), payload)
+}
+
+fn keyed_token(key: &[u8], payload: &str) -> String {
+    keyed_mac(key, payload).finalize().into_bytes().iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn encode(game: &str, session_id: u64, turn: u32, action: &str) -> String {
+    let payload = format!("{}:{}:{}:{}", game, session_id, turn, action);
+
+    let signature = mac(&payload).finalize().into_bytes();
+    let signature = signature[..SIGNATURE_LENGTH].iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    format!("{}:{}", payload, signature)
+}
+
+/// Parses a custom ID built by [`encode`] for the game, returning `None` if it belongs to something else
+/// or if its signature does not match.
+pub fn decode<'a>(game: &str, custom_id: &'a str) -> Option<CustomId<'a>> {
+    let (payload, signature) = custom_id.rsplit_once(':')?;
+
+    let mut parts = payload.splitn(4, ':');
+
+    if parts.next() != Some(game) {
+        return None;
+    }
+
+    let session_id = parts.next()?.parse().ok()?;
+    let turn = parts.next()?.parse().ok()?;
+    let action = parts.next()?;
+
+    let signature = (0..signature.len())
+        .step_by(2)
+        .map(|i| signature.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect::<Option<Vec<u8>>>()?;
+
+    if signature.len() != SIGNATURE_LENGTH || mac(payload).verify_truncated_left(&signature).is_err() {
+        warn!(custom_id, "A button with an invalid signature has been pressed");
+
+        return None;
+    }
+
+    Some(CustomId { session_id, turn, action })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replaces the field of the custom ID at the index, keeping the rest and the signature.
+    fn with_field(custom_id: &str, index: usize, value: &str) -> String {
+        let mut fields = custom_id.split(':').collect::<Vec<_>>();
+
+        fields[index] = value;
+
+        fields.join(":")
+    }
+
+    #[test]
+    fn custom_ids_round_trip() {
+        let custom_id = encode("rps", 42, 3, "rock");
+
+        assert!(custom_id.len() <= 100);
+        assert_eq!(decode("rps", &custom_id), Some(CustomId { session_id: 42, turn: 3, action: "rock" }));
+
+        // The actions are not known here, but the handlers only act upon those they know
+        let custom_id = encode("rps", 42, 3, "unknown");
+
+        assert_eq!(decode("rps", &custom_id).map(|custom_id| custom_id.action), Some("unknown"));
+    }
+
+    #[test]
+    fn altered_custom_ids_are_rejected() {
+        let custom_id = encode("rps", 42, 3, "rock");
+        let signature = custom_id.rsplit(':').next().unwrap();
+
+        let flipped = format!("{}{}", &signature[..signature.len() - 1], if signature.ends_with('0') { '1' } else { '0' });
+
+        assert_eq!(decode("rps", &with_field(&custom_id, 4, &flipped)), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 4, "zz")), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 4, "é")), None);
+
+        assert_eq!(decode("setup", &custom_id), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 0, "setup")), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 1, "43")), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 2, "4")), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 3, "paper")), None);
+        assert_eq!(decode("rps", &with_field(&custom_id, 3, "unknown")), None);
+    }
+
+    #[test]
+    fn truncated_and_overlong_custom_ids_are_rejected() {
+        let custom_id = encode("rps", 42, 3, "rock");
+
+        assert_eq!(decode("rps", &custom_id[..custom_id.len() - 2]), None);
+        assert_eq!(decode("rps", &custom_id[..custom_id.len() - 1]), None);
+        assert_eq!(decode("rps", &format!("{}00", custom_id)), None);
+        assert_eq!(decode("rps", custom_id.rsplit_once(':').unwrap().0), None);
+        assert_eq!(decode("rps", "rps:42:3"), None);
+        assert_eq!(decode("rps", ""), None);
+    }
+
+    #[test]
+    fn tokens_depend_on_the_key_and_the_purpose() {
+        let token = keyed_token(b"key", "dashboard:session:1");
+
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(keyed_token(b"key", "dashboard:session:1"), token);
+
+        assert_ne!(keyed_token(b"key", "dashboard:state:1"), token);
+        assert_ne!(keyed_token(b"key", "replays:session:1"), token);
+        assert_ne!(keyed_token(b"other key", "dashboard:session:1"), token);
+
+        assert_eq!(super::token("dashboard:session:1"), keyed_token(&KEY.read().unwrap(), "dashboard:session:1"));
+    }
+}
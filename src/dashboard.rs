@@ -0,0 +1,558 @@
+//! The dashboard, served by the HTTP server while `dashboard.enabled` is set. The page is static and follows
+//! `/dashboard/events`, a stream of server-sent events carrying the state every few seconds, which `/dashboard/state`
+//! answers once as JSON as well. The database is read once per refresh for all the open streams, off the runtime,
+//! and the snapshot is broadcast to them, each only showing its viewer what they may see.
+//!
+//! Once the OAuth2 credentials of the application are configured, the users log in with Discord, and what they
+//! see depends on who they are: everyone sees their own statistics, the guilds they hold Manage Server in get
+//! their games in progress, latest results, leaderboard and activity, and the owners of the bot see all of it for
+//! every guild. Without the credentials, the dashboard has no authentication and shows everything, so the HTTP
+//! server must then only be reachable by the operators.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use hyper::{Body, Method, Request, Response, StatusCode};
+
+use lazy_static::lazy_static;
+
+use reqwest::Url;
+
+use serde_json::{json, Value};
+
+use serenity::model::id::UserId;
+use serenity::model::Timestamp;
+
+use tokio::sync::watch;
+
+use tracing::{error, info, warn};
+
+use crate::admin;
+use crate::commands;
+use crate::config;
+use crate::custom_ids;
+use crate::guilds;
+use crate::oauth;
+use crate::sessions::{self, Session, State};
+use crate::storage::{self, Match, Record, Standing};
+
+/// The prefix of the paths of the dashboard.
+pub const PREFIX: &str = "/dashboard";
+
+const PAGE: &str = include_str!("../assets/dashboard.html");
+
+/// The cookie carrying the login of a user.
+const COOKIE: &str = "rps_dashboard";
+/// The cookie carrying the state of the authorization the browser has started, so that nobody can finish
+/// an authorization of their own in the browser of somebody else and log them in as themselves.
+const STATE_COOKIE: &str = "rps_dashboard_state";
+
+/// How long a login lasts; the guilds and permissions of the user are only read when they log in.
+const LOGIN_DURATION: Duration = Duration::from_secs(12 * 60 * 60);
+/// How long a user has to authorize the dashboard on Discord.
+const AUTHORIZATION_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// How often the open pages are sent the state again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// How many event streams may be open at once; the pages opened beyond are refused until others are closed.
+const MAX_STREAMS: usize = 100;
+
+const RECENT_MATCHES: usize = 20;
+const LEADERBOARD_SIZE: usize = 10;
+/// How many days the activity of the guilds is shown over, and of how many of the most active guilds.
+const ACTIVITY_DAYS: i64 = 14;
+const ACTIVITY_GUILDS: usize = 10;
+
+/// Who is looking at the dashboard, which decides what it shows them.
+#[derive(Clone, Debug)]
+struct Viewer {
+    /// `None` when the dashboard has no login.
+    user_id: Option<UserId>,
+    tag: String,
+    /// Whether they see everything, as the owners of the bot do.
+    owner: bool,
+    /// The guilds of the bot they hold Manage Server in, with their names.
+    guilds: Vec<(u64, String)>,
+}
+
+lazy_static! {
+    /// The users logged in, keyed by the token in their cookie, with when their login expires.
+    static ref LOGINS: Mutex<HashMap<String, (Viewer, Instant)>> = Mutex::new(HashMap::new());
+    /// The states of the authorizations in progress, which Discord sends back so they cannot be forged.
+    static ref AUTHORIZATIONS: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+    /// The viewers of the open event streams, keyed by the numbers of the streams.
+    static ref STREAMS: Mutex<HashMap<u64, Viewer>> = Mutex::new(HashMap::new());
+    /// The latest snapshot read for the open streams, with a receiver the streams subscribe through.
+    static ref SNAPSHOTS: (watch::Sender<Arc<Snapshot>>, watch::Receiver<Arc<Snapshot>>) =
+        watch::channel(Arc::new(Snapshot::default()));
+}
+
+static TOKENS: AtomicU64 = AtomicU64::new(0);
+static STREAM_IDS: AtomicU64 = AtomicU64::new(0);
+
+/// Only one task reads the snapshots, started along with the first stream.
+static BROADCASTING: AtomicBool = AtomicBool::new(false);
+
+/// What has been read from the database for the viewers at once.
+#[derive(Debug, Default)]
+struct Snapshot {
+    now: i64,
+    sessions: Vec<Session>,
+    /// The daily number of matches of every guild, `None` for direct messages.
+    activity: HashMap<Option<u64>, Vec<u64>>,
+    /// The recent matches and the leaderboard of the guilds the viewers may see, `None` for all of them at once.
+    boards: HashMap<Option<u64>, (Vec<Match>, Vec<Standing>)>,
+    /// The records and the recent matches of the viewers themselves.
+    users: HashMap<u64, (Record, Vec<Match>)>,
+}
+
+impl Snapshot {
+    /// Reads everything the viewers may see, each part only once however many of them see it.
+    fn read(viewers: &[Viewer]) -> Self {
+        let now = Timestamp::now().unix_timestamp();
+
+        let mut snapshot = Self {
+            now,
+            sessions: sessions::all(),
+            activity: activity(first_day(now)),
+            ..Self::default()
+        };
+
+        for viewer in viewers {
+            if let Some(user_id) = viewer.user_id {
+                snapshot.users.entry(user_id.0).or_insert_with(|| (
+                    read("record", storage::record(user_id.0, None)),
+                    read("recent matches", storage::recent_matches(None, Some(user_id.0), RECENT_MATCHES)),
+                ));
+            }
+
+            for scope in scopes(viewer) {
+                snapshot.boards.entry(scope).or_insert_with(|| (
+                    read("recent matches", storage::recent_matches(scope, None, RECENT_MATCHES)),
+                    read("leaderboard", storage::leaderboard(scope, None, LEADERBOARD_SIZE)),
+                ));
+            }
+        }
+
+        snapshot
+    }
+
+    /// Whether everything the viewer may see has been read, which a stream opened since may miss.
+    fn covers(&self, viewer: &Viewer) -> bool {
+        viewer.user_id.iter().all(|user_id| self.users.contains_key(&user_id.0))
+            && scopes(viewer).all(|scope| self.boards.contains_key(&scope))
+    }
+}
+
+/// The guilds whose matches the viewer may see, `None` standing for all of them at once.
+fn scopes(viewer: &Viewer) -> impl Iterator<Item = Option<u64>> + '_ {
+    viewer.owner.then_some(None).into_iter().chain(viewer.guilds.iter().map(|(guild_id, _)| Some(*guild_id)))
+}
+
+/// Reads the snapshot off the runtime, as every part of it aggregates matches; `None` if the read has panicked.
+async fn read_snapshot(viewers: Vec<Viewer>) -> Option<Arc<Snapshot>> {
+    match tokio::task::spawn_blocking(move || Snapshot::read(&viewers)).await {
+        Ok(snapshot) => Some(Arc::new(snapshot)),
+        Err(err) => {
+            error!("The state of the dashboard could not have been read: {:?}", err);
+
+            None
+        },
+    }
+}
+
+pub fn enabled() -> bool {
+    config::get().dashboard.enabled
+}
+
+pub async fn handle(request: Request<Body>) -> hyper::http::Result<Response<Body>> {
+    let path = request.uri().path().trim_start_matches(PREFIX);
+
+    if request.method() != Method::GET {
+        return status(StatusCode::NOT_FOUND);
+    }
+
+    match path {
+        "/login" if oauth::enabled() => return login(),
+        "/callback" if oauth::enabled() => return callback(&request).await,
+        "/logout" => return logout(&request),
+        _ => {},
+    }
+
+    let viewer = match viewer(&request) {
+        Some(viewer) => viewer,
+        // The page sends the users to Discord, whereas its data is simply refused
+        None if matches!(path, "" | "/") => return redirect(&format!("{}/login", PREFIX), Vec::new()),
+        None => return status(StatusCode::UNAUTHORIZED),
+    };
+
+    match path {
+        "" | "/" => Response::builder()
+            .header("Content-Type", "text/html; charset=utf-8")
+            .body(Body::from(PAGE)),
+        "/state" => match read_snapshot(vec![viewer.clone()]).await {
+            Some(snapshot) => Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(state(&viewer, &snapshot).to_string())),
+            None => status(StatusCode::INTERNAL_SERVER_ERROR),
+        },
+        "/events" => events(viewer),
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+/// The user the request has been made by, or everyone's operator when there is no login.
+fn viewer(request: &Request<Body>) -> Option<Viewer> {
+    if !oauth::enabled() {
+        return Some(Viewer { user_id: None, tag: String::new(), owner: true, guilds: Vec::new() });
+    }
+
+    let token = cookie(request, COOKIE)?;
+
+    let mut logins = LOGINS.lock().unwrap();
+    let now = Instant::now();
+
+    logins.retain(|_, (_, expires_at)| *expires_at > now);
+    logins.get(token).map(|(viewer, _)| viewer.clone())
+}
+
+/// The value of the cookie the request carries, if any.
+fn cookie<'a>(request: &'a Request<Body>, name: &str) -> Option<&'a str> {
+    request.headers()
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix(name)?.strip_prefix('='))
+}
+
+/// Builds a cookie of the dashboard, only sent back by the browsers over HTTPS if the server is public over it.
+fn set_cookie(name: &str, value: &str, max_age: Duration) -> String {
+    let secure = if config::get().http.public_url.is_some_and(|url| url.starts_with("https://")) { "; Secure" } else { "" };
+
+    format!("{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Lax{}", name, value, PREFIX, max_age.as_secs(), secure)
+}
+
+/// Sends the user to Discord to authorize the dashboard.
+fn login() -> hyper::http::Result<Response<Body>> {
+    let state = new_token("state");
+
+    let url = match oauth::authorize_url(&state) {
+        Ok(url) => url,
+        Err(err) => {
+            error!("The authorization URL of the dashboard could not have been built: {:?}", err);
+
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        },
+    };
+
+    let mut authorizations = AUTHORIZATIONS.lock().unwrap();
+    let now = Instant::now();
+
+    authorizations.retain(|_, expires_at| *expires_at > now);
+    authorizations.insert(state.clone(), now + AUTHORIZATION_DURATION);
+
+    // Lax, as the browser comes back from Discord through a top-level navigation
+    redirect(&url, vec![set_cookie(STATE_COOKIE, &state, AUTHORIZATION_DURATION)])
+}
+
+/// Logs the user in once Discord has sent them back, mapping who they are to what they may see.
+async fn callback(request: &Request<Body>) -> hyper::http::Result<Response<Body>> {
+    let query = Url::parse(&format!("http://localhost{}", request.uri()))
+        .map(|url| url.query_pairs().into_owned().collect::<HashMap<_, _>>())
+        .unwrap_or_default();
+
+    // The state must be the one of the authorization this very browser has started
+    let state_is_known = query.get("state")
+        .filter(|state| cookie(request, STATE_COOKIE) == Some(state.as_str()))
+        .and_then(|state| AUTHORIZATIONS.lock().unwrap().remove(state))
+        .is_some_and(|expires_at| expires_at > Instant::now());
+
+    // E.g. the user has declined, or the link has been forged to log someone in as somebody else
+    let code = match query.get("code") {
+        Some(code) if state_is_known => code,
+        _ => return status(StatusCode::BAD_REQUEST),
+    };
+
+    let (user, user_guilds) = match oauth::identify(code).await {
+        Ok(identity) => identity,
+        Err(err) => {
+            warn!("A user could not have been logged in to the dashboard: {:?}", err);
+
+            return status(StatusCode::BAD_GATEWAY);
+        },
+    };
+
+    let user_id = match user.id.parse() {
+        Ok(user_id) => UserId(user_id),
+        Err(_) => return status(StatusCode::BAD_GATEWAY),
+    };
+
+    let owner = match admin::context() {
+        Some(ctx) => commands::is_owner(&ctx, user_id).await,
+        None => false,
+    };
+
+    let guilds = user_guilds.iter()
+        .filter(|guild| guild.is_manageable())
+        .filter_map(|guild| Some((guild.id.parse().ok()?, guild.name.clone())))
+        .filter(|(guild_id, _)| guilds::contains(*guild_id))
+        .collect();
+
+    let viewer = Viewer { user_id: Some(user_id), tag: user.tag(), owner, guilds };
+
+    info!(user = %user_id, owner, guilds = viewer.guilds.len(), "A user has logged in to the dashboard");
+
+    let token = new_token("login");
+
+    LOGINS.lock().unwrap().insert(token.clone(), (viewer, Instant::now() + LOGIN_DURATION));
+
+    redirect(PREFIX, vec![set_cookie(COOKIE, &token, LOGIN_DURATION), set_cookie(STATE_COOKIE, "", Duration::ZERO)])
+}
+
+fn logout(request: &Request<Body>) -> hyper::http::Result<Response<Body>> {
+    if let Some(token) = cookie(request, COOKIE) {
+        LOGINS.lock().unwrap().remove(token);
+    }
+
+    redirect(PREFIX, vec![set_cookie(COOKIE, "", Duration::ZERO)])
+}
+
+/// A token unique to the process, which cannot be guessed without the signing key.
+fn new_token(kind: &str) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    custom_ids::token(&format!("dashboard:{}:{}:{}", kind, nanos, TOKENS.fetch_add(1, Ordering::Relaxed)))
+}
+
+/// Removes the stream from the open ones once its task ends, however it does.
+struct OpenStream(u64);
+
+impl Drop for OpenStream {
+    fn drop(&mut self) {
+        STREAMS.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Streams the state until the page is closed, which is only noticed when the next event cannot be sent.
+fn events(viewer: Viewer) -> hyper::http::Result<Response<Body>> {
+    let stream = {
+        let mut streams = STREAMS.lock().unwrap();
+
+        if streams.len() >= MAX_STREAMS {
+            warn!("A dashboard event stream has been refused, as {} are open already", streams.len());
+
+            return status(StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        let stream_id = STREAM_IDS.fetch_add(1, Ordering::Relaxed);
+
+        streams.insert(stream_id, viewer.clone());
+
+        OpenStream(stream_id)
+    };
+
+    if !BROADCASTING.swap(true, Ordering::SeqCst) {
+        tokio::spawn(broadcast());
+    }
+
+    let (mut sender, body) = Body::channel();
+    let mut snapshots = SNAPSHOTS.1.clone();
+
+    tokio::spawn(async move {
+        let _stream = stream;
+
+        snapshots.borrow_and_update();
+
+        // The page is sent its state straight away rather than on the next refresh
+        let mut snapshot = read_snapshot(vec![viewer.clone()]).await;
+
+        loop {
+            if let Some(snapshot) = snapshot.as_ref().filter(|snapshot| snapshot.covers(&viewer)) {
+                if sender.send_data(format!("data: {}\n\n", state(&viewer, snapshot)).into()).await.is_err() {
+                    return;
+                }
+            }
+
+            if snapshots.changed().await.is_err() {
+                return;
+            }
+
+            snapshot = Some(Arc::clone(&snapshots.borrow()));
+        }
+    });
+
+    Response::builder()
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(body)
+}
+
+/// Reads a snapshot for the open streams on every refresh, as long as there are any.
+async fn broadcast() {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let viewers = STREAMS.lock().unwrap().values().cloned().collect::<Vec<_>>();
+
+        if viewers.is_empty() {
+            continue;
+        }
+
+        if let Some(snapshot) = read_snapshot(viewers).await {
+            SNAPSHOTS.0.send_replace(snapshot);
+        }
+    }
+}
+
+/// The first day the activity is shown from, counted from the UNIX epoch.
+fn first_day(now: i64) -> i64 {
+    now / 86400 - ACTIVITY_DAYS + 1
+}
+
+/// Gathers what the viewer may see out of the snapshot; a part that could not be read from the database is empty.
+/// The IDs are strings, as the snowflakes do not fit in the numbers of JavaScript.
+fn state(viewer: &Viewer, snapshot: &Snapshot) -> Value {
+    let Snapshot { now, sessions, activity, boards, users } = snapshot;
+    let first_day = first_day(*now);
+
+    let board = |scope: Option<u64>| match boards.get(&scope) {
+        Some((recent, leaderboard)) => (&recent[..], &leaderboard[..]),
+        None => (&[][..], &[][..]),
+    };
+
+    let me = viewer.user_id.and_then(|user_id| users.get(&user_id.0)).map(|(record, recent)| {
+        json!({
+            "wins": record.wins,
+            "losses": record.losses,
+            "streak": record.streak,
+            "recent": recent.iter().map(match_json).collect::<Vec<_>>(),
+        })
+    });
+
+    let everything = viewer.owner.then(|| {
+        let mut guilds = activity.iter().collect::<Vec<_>>();
+
+        guilds.sort_by_key(|(_, days)| std::cmp::Reverse(days.iter().sum::<u64>()));
+        guilds.truncate(ACTIVITY_GUILDS);
+
+        let (recent, leaderboard) = board(None);
+
+        json!({
+            "sessions": sessions.iter().map(session_json).collect::<Vec<_>>(),
+            "recent": recent.iter().map(match_json).collect::<Vec<_>>(),
+            "leaderboard": leaderboard_json(leaderboard),
+            "activity": guilds.into_iter()
+                .map(|(guild_id, days)| json!({ "guild": guild_id.map(|guild_id| guild_id.to_string()), "days": days }))
+                .collect::<Vec<_>>(),
+        })
+    });
+
+    let guilds = viewer.guilds.iter()
+        .map(|(guild_id, name)| {
+            let (recent, leaderboard) = board(Some(*guild_id));
+
+            json!({
+                "id": guild_id.to_string(),
+                "name": name,
+                "sessions": sessions.iter()
+                    .filter(|session| session.guild_id.map(|id| id.0) == Some(*guild_id))
+                    .map(session_json)
+                    .collect::<Vec<_>>(),
+                "recent": recent.iter().map(match_json).collect::<Vec<_>>(),
+                "leaderboard": leaderboard_json(leaderboard),
+                "days": activity.get(&Some(*guild_id)).cloned().unwrap_or_else(|| vec![0; ACTIVITY_DAYS as usize]),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "generated_at": now,
+        "first_day": first_day * 86400,
+        "viewer": { "tag": viewer.tag, "login": viewer.user_id.is_some() },
+        "me": me,
+        "everything": everything,
+        "guilds": guilds,
+    })
+}
+
+fn session_json(session: &Session) -> Value {
+    let state = match session.state {
+        State::Invited => "invited",
+        State::StarterTurn => "starter_turn",
+        State::OpponentTurn { .. } => "opponent_turn",
+    };
+
+    json!({
+        "id": session.id.to_string(),
+        "guild": session.guild_id.map(|guild_id| guild_id.to_string()),
+        "starter": session.starter.tag(),
+        "opponent": session.opponent.tag(),
+        "round": session.round,
+        "state": state,
+        "started_at": session.started_at.unix_timestamp(),
+    })
+}
+
+fn match_json(game: &Match) -> Value {
+    json!({
+        "id": game.id.to_string(),
+        "guild": game.guild_id.map(|guild_id| guild_id.to_string()),
+        "starter": game.starter_id.to_string(),
+        "opponent": game.opponent_id.to_string(),
+        "winner": game.winner_id.to_string(),
+        "rounds": game.rounds,
+        "finished_at": game.finished_at,
+    })
+}
+
+fn leaderboard_json(leaderboard: &[Standing]) -> Vec<Value> {
+    leaderboard
+        .iter()
+        .map(|standing| json!({ "user": standing.user_id.to_string(), "wins": standing.wins, "losses": standing.losses }))
+        .collect()
+}
+
+/// The daily number of matches of every guild since the first day, from the oldest; `None` for direct messages.
+fn activity(first_day: i64) -> HashMap<Option<u64>, Vec<u64>> {
+    let mut activity = HashMap::<Option<u64>, Vec<u64>>::new();
+
+    for (guild_id, day, count) in read("activity", storage::daily_matches(first_day * 86400)) {
+        if let Some(index) = usize::try_from(day - first_day).ok().filter(|index| *index < ACTIVITY_DAYS as usize) {
+            activity.entry(guild_id).or_insert_with(|| vec![0; ACTIVITY_DAYS as usize])[index] += count;
+        }
+    }
+
+    activity
+}
+
+fn read<T: Default>(what: &str, result: storage::StorageResult<T>) -> T {
+    result.unwrap_or_else(|err| {
+        error!("The {} of the dashboard could not have been read: {:?}", what, err);
+
+        T::default()
+    })
+}
+
+fn redirect(location: &str, cookies: Vec<String>) -> hyper::http::Result<Response<Body>> {
+    let mut response = Response::builder()
+        .status(StatusCode::FOUND)
+        .header("Location", location);
+
+    for cookie in cookies {
+        response = response.header("Set-Cookie", cookie);
+    }
+
+    response.body(Body::empty())
+}
+
+fn status(status: StatusCode) -> hyper::http::Result<Response<Body>> {
+    Response::builder().status(status).body(Body::empty())
+}
@@ -0,0 +1,153 @@
+//! The weekly digests of the guilds that have chosen a channel with `/settings digest`: the games played, the most
+//! active players, the longest winning streak and the unlikeliest win of the week, from Monday to Sunday in UTC.
+//! The scheduler posts them once the week is over. The last week sent to every guild is stored, so a digest missed
+//! while the bot was down is posted late rather than never, and none is posted twice.
+
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::Timestamp;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, info, warn};
+
+use crate::guilds;
+use crate::i18n::{tr, DEFAULT_LOCALE};
+use crate::storage::{self, Digest};
+use crate::theme::Palette;
+
+/// How many of the most active players are listed.
+const MOST_ACTIVE: usize = 3;
+
+/// The shortest winning streak worth mentioning.
+const MIN_STREAK: u64 = 2;
+
+/// Posts the digest of the week that has just ended in the channels of the guilds that have not been sent it yet.
+pub async fn post_due(ctx: &Context) {
+    let week = week(Timestamp::now().unix_timestamp() / 86400) - 1;
+
+    let due = match storage::due_digests(week) {
+        Ok(due) => due,
+        Err(err) => {
+            error!("The due digests could not have been read: {:?}", err);
+
+            return;
+        },
+    };
+
+    for (guild_id, channel_id) in due {
+        // Marked first, so a digest that fails to be posted is not retried on every tick
+        if let Err(err) = storage::set_digest_week(guild_id, week) {
+            error!("The digest could not have been marked as posted: {:?}", err);
+
+            continue;
+        }
+
+        let (since, until) = (first_day(week) * 86400, first_day(week + 1) * 86400);
+
+        let digest = match storage::digest(guild_id, since, until, MOST_ACTIVE) {
+            Ok(digest) if digest.matches > 0 => digest,
+            // A quiet week is not worth a message
+            Ok(_) => continue,
+            Err(err) => {
+                error!(guild = guild_id, "The digest could not have been read: {:?}", err);
+
+                continue;
+            },
+        };
+
+        let locale = guilds::locale(guild_id).unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+
+        let embed = embed(&digest, week, &Palette::for_guild(Some(GuildId(guild_id))), &locale);
+
+        match ChannelId(channel_id).send_message(&ctx.http, |msg| msg.set_embed(embed)).await {
+            Ok(_) => info!(guild = guild_id, "The weekly digest has been posted"),
+            // Most likely the channel has been deleted or the bot may no longer post there
+            Err(err) => warn!(guild = guild_id, "The weekly digest could not have been posted: {:?}", err),
+        }
+    }
+}
+
+/// The week of the day, both counted from the UNIX epoch; the weeks start on Mondays, the epoch being a Thursday.
+fn week(day: i64) -> i64 {
+    (day + 3).div_euclid(7)
+}
+
+/// The Monday the week starts on, counted from the UNIX epoch.
+fn first_day(week: i64) -> i64 {
+    week * 7 - 3
+}
+
+fn embed(digest: &Digest, week: i64, palette: &Palette, locale: &str) -> CreateEmbed {
+    let date = |day: i64| format!("<t:{}:d>", day * 86400);
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| a.name(tr!(locale, "embeds.digest")))
+        .color(palette.success)
+        .description(tr!(
+            locale,
+            "digest.summary",
+            from = date(first_day(week)),
+            to = date(first_day(week + 1) - 1),
+            games = digest.matches,
+        ));
+
+    let most_active = digest.most_active.iter()
+        .enumerate()
+        .map(|(index, (user_id, games))| {
+            tr!(locale, "digest.active_player", position = index + 1, user = UserId(*user_id).mention(), games = games)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    embed.field(tr!(locale, "digest.most_active"), most_active, false);
+
+    if let Some((user_id, wins)) = digest.longest_streak.filter(|(_, wins)| *wins >= MIN_STREAK) {
+        let streak = tr!(locale, "digest.streak", user = UserId(user_id).mention(), wins = wins);
+
+        embed.field(tr!(locale, "digest.longest_streak"), streak, false);
+    }
+
+    if let Some((game, winner_wins, loser_wins)) = &digest.unlikeliest_win {
+        let loser_id = if game.winner_id == game.starter_id { game.opponent_id } else { game.starter_id };
+
+        embed.field(
+            tr!(locale, "digest.unlikeliest_win"),
+            tr!(
+                locale,
+                "digest.unlikeliest_win_details",
+                winner = UserId(game.winner_id).mention(),
+                loser = UserId(loser_id).mention(),
+                deficit = loser_wins - winner_wins,
+                winner_wins = winner_wins,
+                loser_wins = loser_wins,
+                id = game.id,
+            ),
+            false,
+        );
+    }
+
+    embed.footer(|f| f.text(tr!(locale, "digest.footer")));
+
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weeks_start_on_mondays() {
+        // 1970-01-05 was the first Monday after the epoch, and 2024-01-01 a Monday as well
+        for monday in [4, 19723] {
+            assert_eq!(first_day(week(monday)), monday);
+            assert_eq!(week(monday + 6), week(monday));
+            assert_eq!(week(monday - 1), week(monday) - 1);
+        }
+
+        assert_eq!(week(0), 0);
+        assert_eq!(first_day(0), -3);
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serenity::model::id::GuildId;
+
+use tracing::error;
+
+use crate::config;
+use crate::storage;
+
+/// Blocks new games while games in progress are allowed to finish; starts from `maintenance` in the config.
+static MAINTENANCE: AtomicBool = AtomicBool::new(false);
+
+pub fn maintenance() -> bool {
+    MAINTENANCE.load(Ordering::Relaxed)
+}
+
+pub fn set_maintenance(enabled: bool) {
+    MAINTENANCE.store(enabled, Ordering::Relaxed);
+}
+
+/// The state of a single flag in a guild.
+#[derive(Clone, Copy, Debug)]
+pub struct FlagState {
+    pub enabled: bool,
+    /// Whether the guild overrides the default from the config.
+    pub overridden: bool,
+}
+
+/// The flags declared in the `[flags]` section of the config, mapped to their default state.
+/// Only declared flags can be overridden, so a typo cannot silently create a new one.
+pub fn declared() -> BTreeMap<String, bool> {
+    config::get().flags.into_iter().collect()
+}
+
+/// Whether the feature behind the flag is enabled in the guild: the override of the guild if there is one,
+/// the default from the config otherwise. Undeclared flags are disabled, and DMs always use the defaults.
+#[allow(dead_code)] // Nothing is behind a flag yet
+pub fn enabled(guild_id: Option<GuildId>, name: &str) -> bool {
+    let default = config::get().flags.get(name).copied().unwrap_or(false);
+
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return default,
+    };
+
+    match storage::guild_flags(guild_id.0) {
+        Ok(overrides) => overrides.get(name).copied().unwrap_or(default),
+        Err(err) => {
+            error!("The feature flags of the guild could not have been read: {:?}", err);
+
+            default
+        },
+    }
+}
+
+/// Resolves every declared flag for the guild.
+pub fn guild_states(guild_id: GuildId) -> storage::StorageResult<BTreeMap<String, FlagState>> {
+    let overrides = storage::guild_flags(guild_id.0)?;
+
+    let states = declared().into_iter()
+        .map(|(name, default)| {
+            let state = match overrides.get(&name) {
+                Some(&enabled) => FlagState { enabled, overridden: true },
+                None => FlagState { enabled: default, overridden: false },
+            };
+
+            (name, state)
+        })
+        .collect();
+
+    Ok(states)
+}
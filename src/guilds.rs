@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use serenity::client::Context;
+use serenity::model::gateway::Ready;
+use serenity::model::guild::{Guild, UnavailableGuild};
+use serenity::model::id::UserId;
+
+use tracing::{error, info};
+
+use crate::commands::{PERMISSIONS, REMATCH_PERMISSIONS};
+use crate::i18n::tr;
+use crate::theme::Palette;
+
+/// The guilds the bot is in, which tells the guilds it joins apart from the ones
+/// that become available after (re)connecting, regardless of whether the cache is enabled.
+#[derive(Default)]
+struct Guilds {
+    bot_id: Option<UserId>,
+    known: HashSet<u64>,
+    /// The preferred locales of the guilds, for the messages addressed to a whole guild.
+    locales: HashMap<u64, String>,
+    /// The number of shards the bot is split into, as reported when connecting.
+    shard_count: u64,
+}
+
+lazy_static! {
+    static ref GUILDS: Mutex<Guilds> = Mutex::new(Guilds::default());
+}
+
+pub fn ready(ready: &Ready) {
+    let mut guilds = GUILDS.lock().unwrap();
+
+    guilds.bot_id = Some(ready.user.id);
+    guilds.known = ready.guilds.iter().map(|guild| guild.id.0).collect();
+    guilds.shard_count = ready.shard.map_or(1, |[_, total]| total);
+
+    info!("Serving {} guilds!", guilds.known.len());
+}
+
+/// The user of the bot itself, known once it has connected.
+pub fn bot_id() -> Option<UserId> {
+    GUILDS.lock().unwrap().bot_id
+}
+
+/// The number of guilds the bot is in.
+pub fn count() -> usize {
+    GUILDS.lock().unwrap().known.len()
+}
+
+pub fn contains(guild_id: u64) -> bool {
+    GUILDS.lock().unwrap().known.contains(&guild_id)
+}
+
+pub fn shard_count() -> u64 {
+    GUILDS.lock().unwrap().shard_count
+}
+
+/// The preferred locale of the guild, known once it has become available.
+pub fn locale(guild_id: u64) -> Option<String> {
+    GUILDS.lock().unwrap().locales.get(&guild_id).cloned()
+}
+
+pub async fn create(ctx: &Context, guild: &Guild) {
+    let (is_new, count, bot_id) = {
+        let mut guilds = GUILDS.lock().unwrap();
+
+        guilds.locales.insert(guild.id.0, guild.preferred_locale.clone());
+
+        (guilds.known.insert(guild.id.0), guilds.known.len(), guilds.bot_id)
+    };
+
+    if !is_new {
+        return;
+    }
+
+    info!("Joined the {} guild ({}), now serving {} guilds!", guild.name, guild.id, count);
+
+    if let Some(bot_id) = bot_id {
+        welcome(ctx, guild, bot_id).await;
+    }
+}
+
+pub fn delete(incomplete: &UnavailableGuild) {
+    // An unavailable guild is an outage rather than the bot having been removed
+    if incomplete.unavailable {
+        return;
+    }
+
+    let mut guilds = GUILDS.lock().unwrap();
+
+    guilds.locales.remove(&incomplete.id.0);
+
+    if guilds.known.remove(&incomplete.id.0) {
+        info!("Left the {} guild, now serving {} guilds!", incomplete.id, guilds.known.len());
+    }
+}
+
+/// Introduces the bot in the system channel of a guild it has just joined, if it is allowed to.
+async fn welcome(ctx: &Context, guild: &Guild, bot_id: UserId) {
+    let channel = match guild.system_channel_id
+        .and_then(|id| guild.channels.get(&id))
+        .and_then(|channel| channel.clone().guild())
+    {
+        Some(channel) => channel,
+        None => return,
+    };
+
+    let can_send = guild.members.get(&bot_id)
+        .and_then(|member| guild.user_permissions_in(&channel, member).ok())
+        // The welcome message is not reacted to
+        .is_some_and(|permissions| permissions.contains(PERMISSIONS - REMATCH_PERMISSIONS));
+
+    if !can_send {
+        info!("The welcome message could not have been sent to the {} guild due to missing permissions", guild.id);
+
+        return;
+    }
+
+    let locale = guild.preferred_locale.as_str();
+    let palette = Palette::default();
+
+    if let Err(err) = channel.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.welcome")))
+                .color(palette.success)
+                .description(tr!(locale, "welcome.description"))
+                .field(tr!(locale, "welcome.play"), tr!(locale, "welcome.play_description"), false)
+                .field(tr!(locale, "welcome.configure"), tr!(locale, "welcome.configure_description"), false)
+                .field(tr!(locale, "welcome.language"), tr!(locale, "welcome.language_description"), false)
+        })
+    }).await {
+        error!("The welcome message could not have been sent: {:?}", err);
+    }
+}
@@ -0,0 +1,93 @@
+//! Lets a new process take over the games in progress when the bot is redeployed. The process being replaced
+//! disconnects first when asked to stop (SIGTERM or Ctrl+C), so no press changes a game after it has been stored,
+//! then stores its games; the new process picks them up as soon as it is connected, whether it has started before
+//! or after. A press in between is answered by restoring the game from its message, as after any restart.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::client::Context;
+
+use tokio::sync::Mutex;
+
+use tracing::{error, info, warn};
+
+use crate::commands::rps;
+use crate::systemd;
+
+/// How often the games handed off by another process are looked for, which is how long they may go unnoticed.
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// `ready` is dispatched again on every reconnect, but only one poller must run.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the process has been asked to stop, so it does not take its own games back.
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+pub fn start(ctx: &Context) {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run(ctx.clone()));
+    }
+}
+
+async fn run(ctx: Context) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if STOPPING.load(Ordering::SeqCst) {
+            return;
+        }
+
+        rps::take_over(&ctx).await;
+    }
+}
+
+/// Disconnects every shard once the process has been asked to stop, which makes the client return.
+pub async fn stop_on_signal(shard_manager: Arc<Mutex<ShardManager>>) {
+    wait_for_signal().await;
+
+    info!("Shutting down...");
+
+    STOPPING.store(true, Ordering::SeqCst);
+    systemd::stopping();
+    shard_manager.lock().await.shutdown_all().await;
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = terminate.recv() => {},
+                _ = tokio::signal::ctrl_c() => {},
+            }
+        },
+        Err(err) => {
+            warn!("The SIGTERM handler could not have been installed: {:?}", err);
+
+            let _ = tokio::signal::ctrl_c().await;
+        },
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Stores the games in progress for the next process, once the client has returned.
+pub fn hand_off() {
+    STOPPING.store(true, Ordering::SeqCst);
+
+    match rps::hand_off() {
+        Ok(0) => {},
+        Ok(count) => info!("{} sessions have been handed off", count),
+        Err(err) => error!("The sessions could not have been handed off: {:?}", err),
+    }
+}
@@ -0,0 +1,84 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+
+use tokio::net::TcpListener;
+
+use tracing::{error, info};
+
+use crate::admin;
+use crate::dashboard;
+use crate::metrics;
+use crate::readiness;
+use crate::replays;
+#[cfg(feature = "topgg")]
+use crate::votes;
+
+/// Serves the operator endpoints; only started when `http.listen` is set in the config.
+pub async fn serve(addr: SocketAddr) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("The HTTP server could not have been bound to {}: {:?}", addr, err);
+
+            return;
+        },
+    };
+
+    info!("Serving the HTTP endpoints on {}!", addr);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                error!("An HTTP connection could not have been accepted: {:?}", err);
+
+                continue;
+            },
+        };
+
+        tokio::spawn(async move {
+            if let Err(err) = Http::new().http1_only(true).serve_connection(stream, service_fn(handle)).await {
+                error!("An error occurred while serving an HTTP connection: {:?}", err);
+            }
+        });
+    }
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(metrics::gather())),
+        (&Method::GET, "/health") => Response::builder()
+            .body(Body::from("ok")),
+        (&Method::GET, "/ready") => ready(),
+        #[cfg(feature = "topgg")]
+        (&Method::POST, votes::PATH) if votes::enabled() => votes::handle_webhook(request).await,
+        (&Method::GET, path) if path.starts_with(replays::PREFIX) => replays::handle(request).await,
+        (_, path) if path.starts_with(admin::PREFIX) && admin::enabled() => admin::handle(request).await,
+        (_, path) if path.starts_with(dashboard::PREFIX) && dashboard::enabled() => dashboard::handle(request).await,
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+
+    Ok(response.unwrap_or_default())
+}
+
+/// Answers 200 once the bot is connected, its commands are registered and the database is reachable,
+/// and 503 listing the failing checks until then.
+fn ready() -> hyper::http::Result<Response<Body>> {
+    let failing = readiness::failing_checks();
+
+    if failing.is_empty() {
+        return Response::builder().body(Body::from("ready"));
+    }
+
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .body(Body::from(format!("not ready: {}", failing.join(", "))))
+}
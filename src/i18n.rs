@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use lazy_static::lazy_static;
+
+use serenity::model::id::UserId;
+
+use tracing::error;
+
+use crate::storage;
+
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// The resource files of every supported locale, keyed by Discord's locale codes.
+const RESOURCES: [(&str, &str); 3] = [
+    ("en-US", include_str!("../locales/en-US.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("ru", include_str!("../locales/ru.json")),
+];
+
+lazy_static! {
+    static ref BUNDLES: HashMap<&'static str, HashMap<String, String>> = RESOURCES.iter()
+        .map(|(locale, source)| {
+            let bundle = serde_json::from_str(source)
+                .unwrap_or_else(|err| panic!("The {} resource file is malformed: {}", locale, err));
+
+            (*locale, bundle)
+        })
+        .collect();
+}
+
+/// Formats a message from the resource files, e.g. `tr!(locale, "rps.turn", user = user.mention())`.
+macro_rules! tr {
+    ($locale:expr, $key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::translate($locale, $key, &[$((stringify!($name), &$value as &(dyn std::fmt::Display + Sync))),*])
+    };
+}
+
+pub(crate) use tr;
+
+/// Picks the bundle of the locale, matching on the language alone (e.g. `en-GB` to `en-US`) if needed.
+fn bundle(locale: &str) -> &'static HashMap<String, String> {
+    let language = locale.split('-').next().unwrap_or(locale);
+
+    BUNDLES.get(locale)
+        .or_else(|| BUNDLES.iter().find(|(code, _)| code.split('-').next() == Some(language)).map(|(_, b)| b))
+        .unwrap_or(&BUNDLES[DEFAULT_LOCALE])
+}
+
+/// Looks up the message in the locale, falling back to English and then to the key itself,
+/// and substitutes every `{name}` placeholder with its argument.
+pub fn translate(locale: &str, key: &str, args: &[(&str, &(dyn Display + Sync))]) -> String {
+    let template = bundle(locale).get(key)
+        .or_else(|| BUNDLES[DEFAULT_LOCALE].get(key))
+        .map_or(key, String::as_str);
+
+    args.iter().fold(template.to_string(), |message, (name, value)| {
+        message.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// Lists the translations of the key in every non-default locale that defines it.
+pub fn localizations(key: &str) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+    BUNDLES.iter()
+        .filter(|(locale, _)| **locale != DEFAULT_LOCALE)
+        .filter_map(move |(locale, bundle)| bundle.get(key).map(|message| (*locale, message.as_str())))
+}
+
+/// Lists the codes of every supported locale.
+pub fn locales() -> impl Iterator<Item = &'static str> {
+    RESOURCES.iter().map(|(locale, _)| *locale)
+}
+
+/// Resolves the locale of a response directed at the user: their `/language` preference if they have one,
+/// the locale of their client otherwise.
+pub fn user_locale(user_id: UserId, interaction_locale: &str) -> String {
+    match storage::user_preferences(user_id.0) {
+        Ok(preferences) => preferences.locale.unwrap_or_else(|| interaction_locale.to_string()),
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            interaction_locale.to_string()
+        },
+    }
+}
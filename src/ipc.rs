@@ -0,0 +1,170 @@
+//! A control channel on a Unix socket, letting the operators on the host manage the bot without going through
+//! Discord or the network, e.g. with `socat - UNIX-CONNECT:rps.sock`. Every line sent is a command, answered
+//! with one or more lines: `status`, `sessions`, `end <session>` and `reload-config`.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use tracing::{error, info, warn};
+
+use crate::admin;
+use crate::commands::rps;
+use crate::config;
+use crate::flags;
+use crate::guilds;
+use crate::readiness;
+use crate::sessions::{self, State};
+
+/// Only started when `ipc.socket` is set in the config.
+pub async fn serve(path: String) {
+    // A socket left behind by a previous process would make binding fail
+    if Path::new(&path).exists() {
+        if let Err(err) = std::fs::remove_file(&path) {
+            error!("The stale control socket {} could not have been removed: {:?}", path, err);
+
+            return;
+        }
+    }
+
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("The control socket could not have been bound to {}: {:?}", path, err);
+
+            return;
+        },
+    };
+
+    // Only the user running the bot may control it
+    if let Err(err) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+        warn!("The permissions of the control socket could not have been restricted: {:?}", err);
+    }
+
+    info!("Listening for control commands on {}!", path);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(stream));
+            },
+            Err(err) => error!("A control connection could not have been accepted: {:?}", err),
+        }
+    }
+}
+
+async fn handle_connection(stream: UnixStream) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        info!(command = line, "Handling a control command");
+
+        let mut reply = execute(line).await;
+
+        reply.push('\n');
+
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn execute(line: &str) -> String {
+    let mut words = line.split_whitespace();
+
+    match (words.next(), words.next()) {
+        (Some("status"), None) => status(),
+        (Some("sessions"), None) => list_sessions(),
+        (Some("end"), Some(session_id)) => match session_id.parse() {
+            Ok(session_id) => end(session_id).await,
+            Err(_) => format!("invalid session: {}", session_id),
+        },
+        (Some("reload-config"), None) => match admin::reload_config() {
+            Ok(changes) if changes.is_empty() => "reloaded; nothing has changed".to_string(),
+            Ok(changes) => {
+                let changes = changes.iter()
+                    .map(|key| if config::needs_restart(key) { format!("{} (after a restart)", key) } else { key.clone() })
+                    .collect::<Vec<_>>();
+
+                format!("reloaded; changed: {}", changes.join(", "))
+            },
+            Err(err) => format!("the config could not have been reloaded: {}", err),
+        },
+        _ => "unknown command; expected status, sessions, end <session> or reload-config".to_string(),
+    }
+}
+
+fn status() -> String {
+    let (sessions, players) = sessions::count();
+    let failing = readiness::failing_checks();
+
+    let ready = if failing.is_empty() {
+        "yes".to_string()
+    } else {
+        format!("no ({})", failing.join(", "))
+    };
+
+    format!(
+        "ready: {}\nguilds: {}\nsessions: {}\nplayers: {}\nmaintenance: {}",
+        ready,
+        guilds::count(),
+        sessions,
+        players,
+        if flags::maintenance() { "on" } else { "off" },
+    )
+}
+
+fn list_sessions() -> String {
+    let sessions = sessions::all();
+
+    if sessions.is_empty() {
+        return "no sessions".to_string();
+    }
+
+    sessions.iter()
+        .map(|session| {
+            let state = match session.state {
+                State::Invited => "invited",
+                State::StarterTurn => "starter_turn",
+                State::OpponentTurn { .. } => "opponent_turn",
+            };
+
+            format!(
+                "{} guild={} channel={} starter={} opponent={} round={} state={} started_at={}",
+                session.id,
+                session.guild_id.map_or("dm".to_string(), |guild_id| guild_id.to_string()),
+                session.channel_id,
+                session.starter.id,
+                session.opponent.id,
+                session.round,
+                state,
+                session.started_at.unix_timestamp(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn end(session_id: u64) -> String {
+    let ctx = match admin::context() {
+        Some(ctx) => ctx,
+        None => return "not connected to Discord yet".to_string(),
+    };
+
+    if rps::force_end(&ctx, session_id).await {
+        info!(session = session_id, "The game has been ended through the control socket");
+
+        "ended".to_string()
+    } else {
+        format!("no session {}", session_id)
+    }
+}
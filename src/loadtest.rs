@@ -0,0 +1,208 @@
+//! The `load-test` subcommand: plays thousands of synthetic games at once through the session registry and the game
+//! engine, recording them into a scratch database, then reports how long every step has taken, how long the registry
+//! has been waited for and how much memory the process has used. The figures are meant to be compared between runs,
+//! e.g. before and after moving the registry from its mutex to a concurrent map.
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use tokio::sync::Semaphore;
+
+use tracing::{error, info};
+
+use crate::commands::rps;
+use crate::rules::{self, MOVES};
+use crate::sessions;
+use crate::storage;
+
+/// How often the registry is probed while the games are played.
+const PROBE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The number of synthetic guilds the games are spread over, so the settings of several guilds are read.
+const GUILDS: u64 = 100;
+
+pub struct Options {
+    /// How many games are played in total.
+    pub games: usize,
+    /// How many games are in progress at most at once.
+    pub concurrency: usize,
+    /// How long the players think before every press.
+    pub pause: Duration,
+    /// The seed the moves are drawn from, so that the runs play the same games.
+    pub seed: u64,
+}
+
+pub async fn run(options: Options) -> Result<(), Box<dyn Error + Send + Sync>> {
+    storage::migrate()?;
+
+    info!(
+        "Playing {} synthetic games, {} at once, with {:?} between the presses",
+        options.games, options.concurrency, options.pause,
+    );
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let stop = Arc::new(AtomicBool::new(false));
+    let probe = tokio::spawn(probe(Arc::clone(&stop)));
+
+    let started = Instant::now();
+    let mut games = Vec::with_capacity(options.games);
+
+    for index in 0..options.games as u64 {
+        let rounds = draw_rounds(&mut rng);
+        let permit = Arc::clone(&permits).acquire_owned().await?;
+        let pause = options.pause;
+
+        games.push(tokio::spawn(async move {
+            let _permit = permit;
+
+            // Every game has players of its own, who would be turned away as busy otherwise
+            rps::simulate(index + 1, (index * 2 + 1, index * 2 + 2), Some(index % GUILDS + 1), &rounds, pause).await
+        }));
+    }
+
+    let mut starts = Vec::new();
+    let mut presses = Vec::new();
+    let mut renders = Vec::new();
+    let mut records = Vec::new();
+    let mut failures = 0;
+
+    for game in games {
+        match game.await? {
+            Ok(simulation) => {
+                starts.push(simulation.start);
+                presses.extend(simulation.presses);
+                renders.extend(simulation.renders);
+                records.push(simulation.record);
+            },
+            Err(err) => {
+                error!("The synthetic game has failed: {}", err);
+
+                failures += 1;
+            },
+        }
+    }
+
+    let elapsed = started.elapsed();
+
+    stop.store(true, Ordering::SeqCst);
+
+    let (waits, peak) = probe.await?;
+
+    info!(
+        "{} games have been played in {:.1?}: {:.0} games and {:.0} presses per second",
+        records.len(),
+        elapsed,
+        records.len() as f64 / elapsed.as_secs_f64(),
+        presses.len() as f64 / elapsed.as_secs_f64(),
+    );
+    info!("Starting a session: {}", percentiles(starts));
+    info!("Applying a press: {}", percentiles(presses));
+    info!("Rendering the game message: {}", percentiles(renders));
+    info!("Recording a match: {}", percentiles(records));
+    info!("Waiting for the registry: {}, with up to {} games in progress", percentiles(waits), peak);
+
+    let pool = storage::pool_state();
+
+    info!("Database pool: {} of {} connections open, {} idle", pool.connections, storage::pool_max_size(), pool.idle_connections);
+
+    match memory() {
+        Some((resident, peak)) => info!("Memory: {} kB resident, {} kB at the peak", resident, peak),
+        None => info!("Memory: not measured on this platform"),
+    }
+
+    if failures > 0 {
+        return Err(format!("{} of {} synthetic games have failed", failures, options.games).into());
+    }
+
+    Ok(())
+}
+
+/// Draws the rounds of a game: drawn ones until a round decides it, as the engine expects.
+fn draw_rounds(rng: &mut impl Rng) -> Vec<(&'static str, &'static str)> {
+    let mut rounds = Vec::new();
+
+    loop {
+        let round = (MOVES[rng.gen_range(0..MOVES.len())], MOVES[rng.gen_range(0..MOVES.len())]);
+
+        rounds.push(round);
+
+        if rules::wins(round.0, round.1).is_some() {
+            return rounds;
+        }
+    }
+}
+
+/// Times how long the registry takes to be read until stopped, returning the waits
+/// with the largest number of games seen in progress at once.
+async fn probe(stop: Arc<AtomicBool>) -> (Vec<Duration>, usize) {
+    let mut waits = Vec::new();
+    let mut peak = 0;
+
+    while !stop.load(Ordering::SeqCst) {
+        let started = Instant::now();
+        let (count, _) = sessions::count();
+
+        waits.push(started.elapsed());
+        peak = peak.max(count);
+
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+
+    (waits, peak)
+}
+
+fn percentiles(mut samples: Vec<Duration>) -> String {
+    if samples.is_empty() {
+        return "no samples".to_string();
+    }
+
+    samples.sort_unstable();
+
+    let at = |percentile: usize| samples[(samples.len() - 1) * percentile / 100];
+
+    format!("p50 {:?}, p95 {:?}, p99 {:?}, max {:?}", at(50), at(95), at(99), at(100))
+}
+
+/// The resident memory of the process and its peak, in kilobytes, as Linux reports them.
+fn memory() -> Option<(u64, u64)> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    let field = |name: &str| {
+        status.lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+    };
+
+    Some((field("VmRSS:")?, field("VmHWM:")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_last_round_decides_the_game() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..100 {
+            let rounds = draw_rounds(&mut rng);
+            let (last, drawn) = rounds.split_last().unwrap();
+
+            assert!(rules::wins(last.0, last.1).is_some());
+            assert!(drawn.iter().all(|(starter_move, opponent_move)| starter_move == opponent_move));
+        }
+    }
+
+    #[test]
+    fn percentiles_are_picked_from_the_sorted_samples() {
+        let samples = (1..=100).rev().map(Duration::from_millis).collect();
+
+        assert_eq!(percentiles(samples), "p50 50ms, p95 95ms, p99 99ms, max 100ms");
+    }
+}
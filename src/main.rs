@@ -1,484 +1,433 @@
-use std::collections::HashSet;
+mod activity;
+mod admin;
+mod aws;
+mod backups;
+mod botlists;
+mod champions;
+mod cli;
+mod commands;
+mod config;
+mod custom_ids;
+mod dashboard;
+mod digest;
+mod flags;
+mod guilds;
+mod handoff;
+mod http;
+mod i18n;
+#[cfg(unix)]
+mod ipc;
+mod loadtest;
+mod metrics;
+mod oauth;
+mod protocol;
+mod readiness;
+mod replays;
+mod reporting;
+mod retention;
+mod rules;
+mod scheduler;
+mod secrets;
+mod sessions;
+mod spectators;
+mod storage;
+mod stream;
+mod sweeper;
+mod systemd;
+#[cfg(feature = "otlp")]
+mod telemetry;
+mod theme;
+mod titles;
+mod transport;
+#[cfg(feature = "topgg")]
+mod votes;
+
 use std::error::Error;
-use std::sync::Mutex;
+use std::io;
 use std::time::Duration;
 
-use lazy_static::lazy_static;
+use clap::Parser;
 
 use serenity::async_trait;
-use serenity::builder::{CreateActionRow, CreateEmbed};
 use serenity::Client;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
 use serenity::client::{Context, EventHandler};
-use serenity::futures::StreamExt;
-use serenity::model::application::command::{Command, CommandOptionType};
-use serenity::model::application::component::ButtonStyle;
-use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::gateway::ConnectionStage;
+use serenity::model::application::interaction::Interaction;
+use serenity::model::channel::{Message, Reaction};
 use serenity::model::gateway::Ready;
+use serenity::model::guild::{Guild, UnavailableGuild};
 use serenity::model::id::{ChannelId, GuildId, MessageId};
-use serenity::model::prelude::component::ComponentType;
-use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
-use serenity::model::user::User;
-use serenity::prelude::{GatewayIntents, Mentionable};
-use serenity::utils::Color;
-
-use tracing::{error, info};
 
-const ROCK: char = '\u{270A}';
-const PAPER: char = '\u{270B}';
-const SCISSORS: char = '\u{270C}';
+use tracing::{error, field, info, info_span, Instrument};
 
-const SUCCESS_COLOR: Color = Color::from_rgb(140, 190, 218);
-const FAILURE_COLOR: Color = Color::from_rgb(239, 67, 63);
-const CONFIRMATION_COLOR: Color = Color::from_rgb(118, 255, 3);
-const WARNING_COLOR: Color = Color::from_rgb(255, 242, 54);
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::prelude::*;
 
-lazy_static! {
-    pub static ref SESSIONS: Mutex<HashSet<(u64, u64)>> = Mutex::new(HashSet::new());
-}
+use crate::cli::Task;
+use crate::config::Config;
 
 struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
+    async fn message(&self, ctx: Context, msg: Message) {
+        protocol::handle_message(&ctx, &msg).await;
+    }
+
     async fn message_delete(
         &self,
-        _ctx: Context,
-        _channel_id: ChannelId,
+        ctx: Context,
+        channel_id: ChannelId,
         id: MessageId,
         _guild_id: Option<GuildId>,
     ) {
-        let mut sessions = SESSIONS.lock().unwrap();
+        if !config::get().gateway.message_delete_cleanup {
+            return;
+        }
 
-        for session in sessions.clone().iter().filter(|(_, m)| m == id.as_u64()) {
-            sessions.remove(session);
+        for _ in 0..sessions::end_by_message(id) {
+            metrics::SESSIONS_ENDED.with_label_values(&[metrics::RPS, "deleted"]).inc();
         }
+
+        commands::rps::start_queued(&ctx, channel_id);
     }
 
-    async fn ready(&self, ctx: Context, _ready: Ready) {
-        if !ctx.http.get_global_application_commands().await.unwrap().iter().any(|cmd| cmd.name == "rps") {
-            Command::create_global_application_command(&ctx.http, |cmd| {
-                cmd
-                    .name("rps")
-                    .description("Starts the rock-paper-scissors game against the specified user")
-                    .create_option(|option| {
-                        option
-                            .name("opponent")
-                            .description("The user to play rock-paper-scissors against")
-                            .kind(CommandOptionType::User)
-                            .required(true)
-                    })
-            }).await.expect("The rock-paper-scissors command could not have been registered!");
-
-            info!("The rock-paper-scissors command has been registered!");
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        ids: Vec<MessageId>,
+        _guild_id: Option<GuildId>,
+    ) {
+        if !config::get().gateway.message_delete_cleanup {
+            return;
+        }
+
+        for _ in 0..sessions::end_by_messages(&ids) {
+            metrics::SESSIONS_ENDED.with_label_values(&[metrics::RPS, "purged"]).inc();
         }
 
+        commands::rps::start_queued(&ctx, channel_id);
+    }
+
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        commands::rps::handle_reaction(&ctx, &reaction).await;
+    }
+
+    #[cfg(feature = "cache")]
+    async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: bool) {
+        guilds::create(&ctx, &guild).await;
+    }
+
+    #[cfg(not(feature = "cache"))]
+    async fn guild_create(&self, ctx: Context, guild: Guild) {
+        guilds::create(&ctx, &guild).await;
+    }
+
+    #[cfg(feature = "cache")]
+    async fn guild_delete(&self, _ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        guilds::delete(&incomplete);
+    }
+
+    #[cfg(not(feature = "cache"))]
+    async fn guild_delete(&self, _ctx: Context, incomplete: UnavailableGuild) {
+        guilds::delete(&incomplete);
+    }
+
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        guilds::ready(&ready);
+        readiness::set_connected(true);
+        scheduler::start(&ctx);
+        sweeper::start(&ctx);
+        activity::start(&ctx);
+        handoff::start(&ctx);
+        admin::ready(&ctx);
+        botlists::start(ready.user.id);
+
+        match commands::sync(&ctx.http).await {
+            Ok(_) => {
+                readiness::set_commands_synced();
+
+                info!("The application commands have been registered!");
+            },
+            // The commands registered before are kept, and they are registered again on the next `ready`
+            Err(err) => error!("The application commands could not have been registered: {:?}", err),
+        }
+
+        systemd::ready();
+
         info!("Connected to Discord!");
     }
 
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        readiness::set_connected(event.new == ConnectionStage::Connected);
+    }
+
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
         if let Interaction::ApplicationCommand(cmd) = interaction {
-            if cmd.data.name == "rps" {
-                let option = &cmd.data.options[0];
-
-                if let Some(CommandDataOptionValue::User(opponent, _)) = &option.resolved {
-                    let starter = &cmd.user;
-
-                    if opponent.bot || opponent.id == starter.id {
-                        if let Err(_) = cmd.create_interaction_response(&ctx.http, |response| {
-                            response
-                                .kind(InteractionResponseType::ChannelMessageWithSource)
-                                .interaction_response_data(|msg| {
-                                    msg
-                                        .ephemeral(true)
-                                        .embed(|embed| {
-                                            embed
-                                                .author(|a| a.name("Failure!"))
-                                                .color(FAILURE_COLOR)
-                                                .description("You cannot play against the specified user!")
-                                        })
-                                })
-                        }).await {}
-
-                        return;
-                    }
-
-                    if SESSIONS.lock().unwrap().iter().any(|(u, _)| u == starter.id.as_u64() || u == opponent.id.as_u64()) {
-                        if let Err(_) = cmd.create_interaction_response(&ctx.http, |response| {
-                            response
-                                .kind(InteractionResponseType::ChannelMessageWithSource)
-                                .interaction_response_data(|msg| {
-                                    msg
-                                        .ephemeral(true)
-                                        .embed(|embed| {
-                                            embed
-                                                .author(|a| a.name("Failure!"))
-                                                .color(FAILURE_COLOR)
-                                                .description("Either user is already playing rock-paper-scissors!")
-                                        })
-                                })
-                        }).await {}
-
-                        return;
-                    }
-
-                    if let Err(_) = cmd.create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|msg| {
-                                msg
-                                    .content(opponent.mention())
-                                    .embed(|embed| {
-                                        embed
-                                            .author(|a| a.name("Confirmation!"))
-                                            .color(CONFIRMATION_COLOR)
-                                            .description(
-                                                format!("Do you want to play rock-paper-scissors against {}?", starter.mention())
-                                            )
-                                    })
-                                    .components(|comp| {
-                                        comp.create_action_row(|row| {
-                                            row
-                                                .create_button(|button| {
-                                                    button
-                                                        .label("Yes")
-                                                        .custom_id("play")
-                                                        .style(ButtonStyle::Secondary)
-                                                })
-                                                .create_button(|button| {
-                                                    button
-                                                        .label("No")
-                                                        .custom_id("deny")
-                                                        .style(ButtonStyle::Danger)
-                                                })
-                                        })
-                                    })
-                            })
-                    }).await { return; }
-
-                    if let Ok(response) = cmd.get_interaction_response(&ctx.http).await {
-                        SESSIONS.lock().unwrap().extend([
-                            (*starter.id.as_u64(), *response.id.as_u64()),
-                            (*opponent.id.as_u64(), *response.id.as_u64()),
-                        ]);
-
-                        let mut round_counter = 1usize;
-
-                        let round_embed = |user: &User, round_counter: usize| {
-                            let mut embed = CreateEmbed::default();
-
-                            embed
-                                .color(SUCCESS_COLOR)
-                                .author(|author| {
-                                    author
-                                        .name(format!("Round #{}", round_counter))
-                                        .icon_url(
-                                            user.avatar_url()
-                                                .unwrap_or_else(|| user.default_avatar_url())
-                                        )
-                                })
-                                .description(format!("It is {}'s turn!", user.mention()));
-
-                            embed
-                        };
-
-                        let turn_action_row = |users: Vec<String>| {
-                            let ids_joined = users.join("-");
-
-                            let mut row = CreateActionRow::default();
-
-                            row
-                                .create_button(|button| {
-                                    button
-                                        .style(ButtonStyle::Secondary)
-                                        .emoji(ROCK)
-                                        .custom_id(format!("{}-rock", ids_joined))
-                                })
-                                .create_button(|button| {
-                                    button
-                                        .style(ButtonStyle::Secondary)
-                                        .emoji(PAPER)
-                                        .custom_id(format!("{}-paper", ids_joined))
-                                })
-                                .create_button(|button| {
-                                    button
-                                        .style(ButtonStyle::Secondary)
-                                        .emoji(SCISSORS)
-                                        .custom_id(format!("{}-scissors", ids_joined))
-                                })
-                                .create_button(|button| {
-                                    button
-                                        .style(ButtonStyle::Danger)
-                                        .label("Exit")
-                                        .custom_id("stop")
-                                });
-
-                            row
-                        };
-
-                        let mut interaction_stream = response.await_component_interactions(&ctx)
-                            .filter(|i| i.data.component_type == ComponentType::Button)
-                            .timeout(Duration::from_secs(60 * 5))
-                            .build();
-
-                        while let Some(interaction) = interaction_stream.next().await {
-                            let id: Vec<_> = interaction.data.custom_id.split('-').collect();
-                            let suffix = *id.last().unwrap();
-
-                            match suffix {
-                                "play" | "deny" => {
-                                    if interaction.user.id == opponent.id {
-                                        if suffix == "play" {
-                                            if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                                response
-                                                    .kind(InteractionResponseType::UpdateMessage)
-                                                    .interaction_response_data(|msg| {
-                                                        msg
-                                                            .components(|comp| comp.set_action_row(turn_action_row(vec![starter.id.to_string()])))
-                                                            .content("")
-                                                            .set_embed(round_embed(starter, round_counter))
-                                                    })
-                                            }).await {}
-                                        } else {
-                                            if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                                response
-                                                    .kind(InteractionResponseType::UpdateMessage)
-                                                    .interaction_response_data(|msg| {
-                                                        msg
-                                                            .components(|comp| comp)
-                                                            .content(starter.mention())
-                                                            .embed(|embed| {
-                                                                embed
-                                                                    .author(|a| a.name("Failure!"))
-                                                                    .color(FAILURE_COLOR)
-                                                                    .description(format!("{} has denied your invitation!", opponent.mention()))
-                                                            })
-                                                    })
-                                            }).await {}
-
-                                            let mut sessions = SESSIONS.lock().unwrap();
-
-                                            sessions.remove(&(*opponent.id.as_u64(), *response.id.as_u64()));
-                                            sessions.remove(&(*starter.id.as_u64(), *response.id.as_u64()));
-
-                                            break;
-                                        }
-                                    } else {
-                                        if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                            response
-                                                .kind(InteractionResponseType::ChannelMessageWithSource)
-                                                .interaction_response_data(|msg| {
-                                                    msg
-                                                        .ephemeral(true)
-                                                        .embed(|embed| {
-                                                            embed
-                                                                .author(|a| a.name("Failure!"))
-                                                                .color(FAILURE_COLOR)
-                                                                .description("You are not the user who has to reply to the command!")
-                                                        })
-                                                })
-                                        }).await {}
-                                    }
-                                },
-                                "rock" | "paper" | "scissors" => {
-                                    if interaction.user.id.to_string().as_str() == id[0] {
-                                        if interaction.user.id == starter.id {
-                                            if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                                response
-                                                    .kind(InteractionResponseType::UpdateMessage)
-                                                    .interaction_response_data(|msg| {
-                                                        msg
-                                                            .components(|comp| comp.set_action_row(turn_action_row(vec![opponent.id.to_string(), suffix.to_string()])))
-                                                            .set_embed(round_embed(opponent, round_counter))
-                                                    })
-                                            }).await {}
-                                        } else {
-                                            let starter_turn = id[1];
-                                            let opponent_turn = suffix;
-
-                                            let winner = match starter_turn {
-                                                "rock" => match opponent_turn {
-                                                    "rock" => None,
-                                                    "paper" => Some(opponent),
-                                                    _ => Some(starter),
-                                                },
-                                                "paper" => match opponent_turn {
-                                                    "rock" => Some(starter),
-                                                    "paper" => None,
-                                                    _ => Some(opponent),
-                                                },
-                                                _ => match opponent_turn {
-                                                    "rock" => Some(opponent),
-                                                    "paper" => Some(starter),
-                                                    _ => None,
-                                                },
-                                            };
-
-                                            if let Some(winner) = winner {
-                                                if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                                    response
-                                                        .kind(InteractionResponseType::UpdateMessage)
-                                                        .interaction_response_data(|msg| {
-                                                            let winner_turn = if winner.id == starter.id {
-                                                                starter_turn
-                                                            } else {
-                                                                opponent_turn
-                                                            };
-
-                                                            let (loser, loser_turn) = if winner.id == starter.id {
-                                                                (opponent, opponent_turn)
-                                                            } else {
-                                                                (starter, starter_turn)
-                                                            };
-
-                                                            let formatted_turn = |turn| match turn {
-                                                                "rock" => format!("{} Rock", ROCK),
-                                                                "paper" => format!("{} Paper", PAPER),
-                                                                _ => format!("{} Scissors", SCISSORS),
-                                                            };
-
-                                                            msg
-                                                                .components(|comp| comp)
-                                                                .embed(|embed| {
-                                                                    embed
-                                                                        .color(SUCCESS_COLOR)
-                                                                        .author(|author| {
-                                                                            author
-                                                                                .name("Congratulations!")
-                                                                                .icon_url(
-                                                                                    winner.avatar_url()
-                                                                                        .unwrap_or_else(|| winner.default_avatar_url())
-                                                                                )
-                                                                        })
-                                                                        .description(format!("{} defeats {}!", winner.mention(), loser.mention()))
-                                                                        .field("Winner's Turn", formatted_turn(winner_turn), false)
-                                                                        .field("Loser's Turn", formatted_turn(loser_turn), false)
-                                                                })
-                                                        })
-                                                }).await {}
-
-                                                let mut sessions = SESSIONS.lock().unwrap();
-
-                                                sessions.remove(&(*opponent.id.as_u64(), *response.id.as_u64()));
-                                                sessions.remove(&(*starter.id.as_u64(), *response.id.as_u64()));
-
-                                                break;
-                                            } else {
-                                                round_counter += 1;
-
-                                                if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                                    response
-                                                        .kind(InteractionResponseType::UpdateMessage)
-                                                        .interaction_response_data(|msg| {
-                                                            msg
-                                                                .components(|comp| {
-                                                                    comp.create_action_row(|row| {
-                                                                        row
-                                                                            .create_button(|button| {
-                                                                                button
-                                                                                    .style(ButtonStyle::Secondary)
-                                                                                    .emoji(ROCK)
-                                                                                    .custom_id(format!("{}-rock", starter.id))
-                                                                            })
-                                                                            .create_button(|button| {
-                                                                                button
-                                                                                    .style(ButtonStyle::Secondary)
-                                                                                    .emoji(PAPER)
-                                                                                    .custom_id(format!("{}-paper", starter.id))
-                                                                            })
-                                                                            .create_button(|button| {
-                                                                                button
-                                                                                    .style(ButtonStyle::Secondary)
-                                                                                    .emoji(SCISSORS)
-                                                                                    .custom_id(format!("{}-scissors", starter.id))
-                                                                            })
-                                                                            .create_button(|button| {
-                                                                                button
-                                                                                    .style(ButtonStyle::Danger)
-                                                                                    .label("Exit")
-                                                                                    .custom_id("stop")
-                                                                            })
-                                                                    })
-                                                                })
-                                                                .set_embed(round_embed(starter, round_counter))
-                                                        })
-                                                }).await {}
-                                            }
-                                        }
-                                    } else {
-                                        if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                            response
-                                                .kind(InteractionResponseType::ChannelMessageWithSource)
-                                                .interaction_response_data(|msg| {
-                                                    msg
-                                                        .ephemeral(true)
-                                                        .embed(|embed| {
-                                                            embed
-                                                                .author(|a| a.name("Failure!"))
-                                                                .color(FAILURE_COLOR)
-                                                                .description(if id[0] != starter.id.to_string().as_str()
-                                                                    && id[0] != opponent.id.to_string().as_str()
-                                                                {
-                                                                    "You did not invoke the initial command!"
-                                                                } else {
-                                                                    "It is not your turn at the moment!"
-                                                                })
-                                                        })
-                                                })
-                                        }).await {}
-                                    }
-                                },
-                                _ => {
-                                    if let Err(_) = interaction.create_interaction_response(&ctx.http, |response| {
-                                        response
-                                            .kind(InteractionResponseType::UpdateMessage)
-                                            .interaction_response_data(|msg| {
-                                                msg
-                                                    .components(|comp| comp)
-                                                    .embed(|embed| {
-                                                        embed
-                                                            .author(|a| a.name("Warning!"))
-                                                            .color(WARNING_COLOR)
-                                                            .description(format!("{} has terminated the session!", interaction.user.mention()))
-                                                    })
-                                            })
-                                    }).await {}
-
-                                    let mut sessions = SESSIONS.lock().unwrap();
-
-                                    sessions.remove(&(*opponent.id.as_u64(), *response.id.as_u64()));
-                                    sessions.remove(&(*starter.id.as_u64(), *response.id.as_u64()));
-
-                                    break;
-                                }
-                            }
-                        }
-                    }
+            // Every event logged while handling the command (including the whole game it starts)
+            // carries these fields, so a game can be followed by filtering on its session id
+            let span = info_span!(
+                "interaction",
+                id = %cmd.id,
+                command = %cmd.data.name,
+                guild = ?cmd.guild_id.map(|id| id.0),
+                channel = %cmd.channel_id,
+                user = %cmd.user.id,
+                session = field::Empty,
+            );
+
+            let future = async {
+                info!("Handling the command");
+
+                match cmd.data.name.as_str() {
+                    "admin" => commands::admin::execute(&ctx, &cmd).await,
+                    "backup" => commands::backup::execute(&ctx, &cmd).await,
+                    "block" | "unblock" => commands::blocks::execute(&ctx, &cmd).await,
+                    "challenges" => commands::challenges::execute(&ctx, &cmd).await,
+                    "debug" => commands::debug::execute(&ctx, &cmd).await,
+                    "flags" => commands::flags::execute(&ctx, &cmd).await,
+                    "forfeit" => commands::forfeit::execute(&ctx, &cmd).await,
+                    "insights" => commands::insights::execute(&ctx, &cmd).await,
+                    "invite" => commands::invite::execute(&ctx, &cmd).await,
+                    "ladder" => commands::ladder::execute(&ctx, &cmd).await,
+                    "language" => commands::language::execute(&ctx, &cmd).await,
+                    "layout" => commands::layout::execute(&ctx, &cmd).await,
+                    "leaderboard" => commands::leaderboard::execute(&ctx, &cmd).await,
+                    "maintenance" => commands::maintenance::execute(&ctx, &cmd).await,
+                    "notifications" => commands::notifications::execute(&ctx, &cmd).await,
+                    "profile" => commands::profile::execute(&ctx, &cmd).await,
+                    "prune" => commands::prune::execute(&ctx, &cmd).await,
+                    "replay" => commands::replay::execute(&ctx, &cmd).await,
+                    "reset-stats" | "restore-stats" => commands::resets::execute(&ctx, &cmd).await,
+                    "rps" => commands::rps::execute(&ctx, &cmd).await,
+                    "settings" => commands::settings::execute(&ctx, &cmd).await,
+                    "setup" => commands::setup::execute(&ctx, &cmd).await,
+                    "spectate" => commands::spectate::execute(&ctx, &cmd).await,
+                    "stats" => commands::stats::execute(&ctx, &cmd).await,
+                    "top" => commands::top::execute(&ctx, &cmd).await,
+                    "vs" => commands::vs::execute(&ctx, &cmd).await,
+                    #[cfg(feature = "topgg")]
+                    "vote" => commands::vote::execute(&ctx, &cmd).await,
+                    _ => {}
                 }
+            };
+
+            reporting::with_command_context(&cmd, future.instrument(span)).await;
+
+            commands::rps::notify_purged(&ctx, &cmd.user, &cmd.locale, cmd.guild_id, &cmd.token).await;
+        } else if let Interaction::MessageComponent(component) = interaction {
+            // The buttons of a game carry its session, so any press can be handled without a collector
+            if let Some(custom_id) = custom_ids::decode(metrics::RPS, &component.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %component.id,
+                    action = %custom_id.action,
+                    guild = ?component.guild_id.map(|id| id.0),
+                    channel = %component.channel_id,
+                    user = %component.user.id,
+                    session = custom_id.session_id,
+                );
+
+                let future = async {
+                    info!("Handling the button press");
+
+                    commands::rps::handle_component(&ctx, &component, custom_id).await
+                };
+
+                reporting::with_component_context(&component, custom_id.session_id, future.instrument(span)).await
+            } else if let Some(custom_id) = custom_ids::decode(commands::setup::SETUP, &component.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %component.id,
+                    action = %custom_id.action,
+                    guild = ?component.guild_id.map(|id| id.0),
+                    channel = %component.channel_id,
+                    user = %component.user.id,
+                );
+
+                commands::setup::handle_component(&ctx, &component, custom_id).instrument(span).await
+            } else if let Some(custom_id) = custom_ids::decode(commands::highlights::HIGHLIGHTS, &component.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %component.id,
+                    action = %custom_id.action,
+                    guild = ?component.guild_id.map(|id| id.0),
+                    channel = %component.channel_id,
+                    user = %component.user.id,
+                    session = custom_id.session_id,
+                );
+
+                commands::highlights::handle_component(&ctx, &component, custom_id).instrument(span).await
+            } else if let Some(custom_id) = custom_ids::decode(commands::insights::INSIGHTS, &component.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %component.id,
+                    action = %custom_id.action,
+                    guild = ?component.guild_id.map(|id| id.0),
+                    channel = %component.channel_id,
+                    user = %component.user.id,
+                    session = custom_id.session_id,
+                );
+
+                commands::insights::handle_component(&ctx, &component, custom_id).instrument(span).await
+            } else if let Some(custom_id) = custom_ids::decode(commands::replay::REPLAY, &component.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %component.id,
+                    action = %custom_id.action,
+                    guild = ?component.guild_id.map(|id| id.0),
+                    channel = %component.channel_id,
+                    user = %component.user.id,
+                    session = custom_id.session_id,
+                );
+
+                commands::replay::handle_component(&ctx, &component, custom_id).instrument(span).await
+            } else if let Some(custom_id) = custom_ids::decode(commands::debug::DEBUG, &component.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %component.id,
+                    action = %custom_id.action,
+                    guild = ?component.guild_id.map(|id| id.0),
+                    channel = %component.channel_id,
+                    user = %component.user.id,
+                );
+
+                commands::debug::handle_component(&ctx, &component, custom_id).instrument(span).await
+            } else {
+                commands::reject_component(&ctx, &component).await
+            }
+
+            commands::rps::notify_purged(&ctx, &component.user, &component.locale, component.guild_id, &component.token).await;
+        } else if let Interaction::ModalSubmit(modal) = interaction {
+            // The moves typed in modal mode, whose form is signed just as the buttons are
+            if let Some(custom_id) = custom_ids::decode(metrics::RPS, &modal.data.custom_id) {
+                let span = info_span!(
+                    "interaction",
+                    id = %modal.id,
+                    action = %custom_id.action,
+                    guild = ?modal.guild_id.map(|id| id.0),
+                    channel = %modal.channel_id,
+                    user = %modal.user.id,
+                    session = custom_id.session_id,
+                );
+
+                let future = async {
+                    info!("Handling the typed move");
+
+                    commands::rps::handle_modal(&ctx, &modal, custom_id).await
+                };
+
+                future.instrument(span).await
             }
         }
     }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let task = cli::Cli::parse().task.unwrap_or(Task::Run);
+
+    config::load()?;
+
+    let config = config::get();
+
     {
         std::env::set_var("RUST_LOG", "DEBUG");
 
-        tracing_subscriber::fmt::init();
+        // The exported statistics are printed to the standard output, so the logs must not end up among them
+        let writer = match task {
+            Task::ExportStats { .. } => BoxMakeWriter::new(io::stderr),
+            _ => BoxMakeWriter::new(io::stdout),
+        };
+
+        let subscriber = tracing_subscriber::registry()
+            .with(config.environment.log_level())
+            .with(tracing_subscriber::fmt::layer().with_writer(writer));
+
+        // Logged errors become Sentry events, while the other logs are attached to them as breadcrumbs
+        #[cfg(feature = "sentry")]
+        let subscriber = subscriber.with(sentry_tracing::layer());
+
+        #[cfg(feature = "otlp")]
+        let subscriber = subscriber.with(telemetry::layer(&config.otlp));
+
+        subscriber.init();
+
+        info!("Starting in the {} environment!", config.environment.name());
+    }
+
+    if let Some(source) = &config.database_key {
+        storage::set_key(secrets::read(source).await?)?;
+    }
+
+    let result = match task {
+        Task::Run => run(&config).await,
+        Task::RegisterCommands => cli::register_commands(&secrets::read(&config.discord_token).await?).await,
+        Task::ClearCommands => cli::clear_commands(&secrets::read(&config.discord_token).await?).await,
+        Task::Migrate => cli::migrate(),
+        #[cfg(feature = "sqlcipher")]
+        Task::EncryptDatabase { output } => cli::encrypt_database(&output),
+        Task::Backup => cli::backup().await,
+        Task::Restore { path } => cli::restore(&path),
+        Task::ExportStats { guild, since } => cli::export_stats(guild, since),
+        Task::VerifyReplays { limit } => cli::verify_replays(limit),
+        Task::LoadTest { database, games, concurrency, pause, seed } => {
+            cli::load_test(&database, loadtest::Options { games, concurrency, pause: Duration::from_millis(pause), seed }).await
+        },
+    };
+
+    #[cfg(feature = "otlp")]
+    telemetry::shutdown();
+
+    result
+}
+
+async fn run(config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
+    storage::migrate()?;
+    retention::start();
+    backups::start();
+    flags::set_maintenance(config.maintenance);
 
-        info!("Starting!");
+    let _reporting = reporting::init(config.sentry_dsn.as_deref());
+
+    if let Some(addr) = config.http.listen {
+        tokio::spawn(http::serve(addr));
     }
 
-    let token = std::env::var("DISCORD_TOKEN")?;
-    let intents = GatewayIntents::all();
+    #[cfg(unix)]
+    if let Some(path) = config.ipc.socket.clone() {
+        tokio::spawn(ipc::serve(path));
+    }
+
+    let token = secrets::read(&config.discord_token).await?;
+
+    custom_ids::set_key(config.signing_key.as_deref().unwrap_or(&token).as_bytes());
+
+    let intents = config.gateway.intents();
 
-    let mut client = Client::builder(&token, intents)
-        .event_handler(Handler)
-        .await?;
+    info!("Requesting the gateway intents: {:?}", intents);
+
+    let builder = Client::builder(&token, intents).event_handler(Handler);
+
+    #[cfg(feature = "cache")]
+    let builder = builder.cache_settings(|settings| settings.max_messages(config.cache.max_messages));
+
+    #[cfg(not(feature = "cache"))]
+    info!("Running without the cache!");
+
+    let mut client = builder.await?;
+
+    systemd::start_watchdog();
+
+    tokio::spawn(handoff::stop_on_signal(client.shard_manager.clone()));
 
     if let Err(err) = client.start().await {
         error!("An error occurred while running the client: {:?}", err);
     }
 
+    handoff::hand_off();
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,173 @@
+use lazy_static::lazy_static;
+
+use prometheus::core::Metric;
+use prometheus::{Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+
+use tracing::error;
+
+use crate::sessions;
+use crate::storage;
+
+/// The `game` label of the session metrics; only rock-paper-scissors exists so far.
+pub const RPS: &str = "rps";
+
+lazy_static! {
+    static ref REGISTRY: Registry = {
+        let registry = Registry::new_custom(Some("rps".to_string()), None)
+            .expect("The metrics registry could not have been created!");
+
+        #[cfg(target_os = "linux")]
+        registry.register(Box::new(prometheus::process_collector::ProcessCollector::for_self()))
+            .expect("The process metrics could not have been registered!");
+
+        registry
+    };
+
+    static ref ACTIVE_SESSIONS: IntGaugeVec = register(IntGaugeVec::new(
+        Opts::new("sessions_active", "Games currently holding their players"),
+        &["game"],
+    ));
+
+    static ref ACTIVE_PLAYERS: IntGauge = register(IntGauge::new(
+        "players_active",
+        "Users currently taking part in a game",
+    ));
+
+    /// Every started session should eventually be counted as ended, so a growing
+    /// difference from the active gauge points at sessions that are never cleaned up.
+    pub static ref SESSIONS_STARTED: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("sessions_started_total", "Games whose invitation has been sent"),
+        &["game"],
+    ));
+
+    pub static ref SESSIONS_ENDED: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("sessions_ended_total", "Games that have been cleaned up, by the reason they ended"),
+        &["game", "reason"],
+    ));
+
+    pub static ref PROTOCOL_ROUNDS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("protocol_rounds_total", "Rounds played by other bots through the protocol, by their outcome"),
+        &["outcome"],
+    ));
+
+    /// The timeouts are minutes long, so the buckets reach well beyond them to tell how far off they are.
+    pub static ref GAME_DURATION: HistogramVec = register(HistogramVec::new(
+        HistogramOpts::new("game_duration_seconds", "How long the games that have been won have lasted since their invitation")
+            .buckets(vec![10.0, 20.0, 30.0, 45.0, 60.0, 90.0, 120.0, 180.0, 300.0, 600.0, 900.0, 1800.0]),
+        &["game"],
+    ));
+
+    pub static ref TURN_LATENCY: HistogramVec = register(HistogramVec::new(
+        HistogramOpts::new("turn_latency_seconds", "How long the players have taken to answer an invitation or to move")
+            .buckets(vec![1.0, 2.0, 3.0, 5.0, 10.0, 15.0, 20.0, 30.0, 45.0, 60.0, 90.0, 120.0, 180.0, 300.0]),
+        &["game", "turn"],
+    ));
+
+    static ref DB_POOL_CONNECTIONS: IntGauge = register(IntGauge::new(
+        "db_pool_connections",
+        "Database connections currently opened by the pool",
+    ));
+
+    static ref DB_POOL_IDLE_CONNECTIONS: IntGauge = register(IntGauge::new(
+        "db_pool_idle_connections",
+        "Opened database connections that are not in use",
+    ));
+
+    static ref DB_POOL_MAX_CONNECTIONS: IntGauge = register(IntGauge::new(
+        "db_pool_max_connections",
+        "The maximum number of connections the pool may open",
+    ));
+}
+
+fn register<M: prometheus::core::Collector + Clone + 'static>(metric: prometheus::Result<M>) -> M {
+    let metric = metric.expect("A metric could not have been created!");
+
+    REGISTRY.register(Box::new(metric.clone()))
+        .expect("A metric could not have been registered!");
+
+    metric
+}
+
+/// Samples the gauges that mirror some other state, then renders every metric
+/// in the Prometheus text format.
+pub fn gather() -> String {
+    let (sessions, players) = sessions::count();
+
+    ACTIVE_SESSIONS.with_label_values(&[RPS]).set(sessions as i64);
+    ACTIVE_PLAYERS.set(players as i64);
+
+    let pool = storage::pool_state();
+
+    DB_POOL_CONNECTIONS.set(pool.connections as i64);
+    DB_POOL_IDLE_CONNECTIONS.set(pool.idle_connections as i64);
+    DB_POOL_MAX_CONNECTIONS.set(storage::pool_max_size() as i64);
+
+    // Forces the counters to be registered even before any game has been played
+    lazy_static::initialize(&SESSIONS_STARTED);
+    lazy_static::initialize(&SESSIONS_ENDED);
+    lazy_static::initialize(&PROTOCOL_ROUNDS);
+    lazy_static::initialize(&GAME_DURATION);
+    lazy_static::initialize(&TURN_LATENCY);
+
+    let mut buffer = Vec::new();
+
+    if let Err(err) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        error!("The metrics could not have been encoded: {:?}", err);
+    }
+
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Estimates the quantile (between 0 and 1) of what the histogram has observed, interpolating within the bucket
+/// it falls in as `histogram_quantile` does in Prometheus; `None` until anything has been observed.
+pub fn quantile(histogram: &Histogram, quantile: f64) -> Option<f64> {
+    let metric = histogram.metric();
+    let histogram = metric.get_histogram();
+
+    let rank = quantile * histogram.get_sample_count() as f64;
+
+    if rank == 0.0 {
+        return None;
+    }
+
+    let (mut lower_bound, mut lower_count) = (0.0, 0);
+
+    for bucket in histogram.get_bucket() {
+        let count = bucket.get_cumulative_count();
+
+        if count as f64 >= rank {
+            let share = (rank - lower_count as f64) / (count - lower_count) as f64;
+
+            return Some(lower_bound + (bucket.get_upper_bound() - lower_bound) * share);
+        }
+
+        (lower_bound, lower_count) = (bucket.get_upper_bound(), count);
+    }
+
+    // Only the lower bound of the values beyond the last bucket is known
+    Some(lower_bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_are_interpolated_within_their_bucket() {
+        let histogram = Histogram::with_opts(HistogramOpts::new("test", "test").buckets(vec![10.0, 20.0, 40.0])).unwrap();
+
+        assert_eq!(quantile(&histogram, 0.5), None);
+
+        for value in [5.0, 15.0, 15.0, 30.0] {
+            histogram.observe(value);
+        }
+
+        assert_eq!(quantile(&histogram, 0.25), Some(10.0));
+        assert_eq!(quantile(&histogram, 0.5), Some(15.0));
+        assert_eq!(quantile(&histogram, 0.875), Some(30.0));
+
+        histogram.observe(100.0);
+
+        assert_eq!(quantile(&histogram, 0.99), Some(40.0));
+    }
+}
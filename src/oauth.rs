@@ -0,0 +1,146 @@
+//! The Discord OAuth2 authorization code flow the dashboard logs its users in with, asking for the `identify`
+//! and `guilds` scopes: who they are, and which guilds they are in with which permissions.
+
+use std::error::Error;
+
+use reqwest::Url;
+
+use serde::Deserialize;
+
+use serenity::model::permissions::Permissions;
+
+use crate::config;
+use crate::secrets;
+
+type OAuthResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const API_URL: &str = "https://discord.com/api/v10";
+
+/// The user who has logged in, as Discord describes them.
+#[derive(Clone, Debug, Deserialize)]
+pub struct User {
+    /// The ID, as a string.
+    pub id: String,
+    pub username: String,
+    pub discriminator: String,
+}
+
+impl User {
+    pub fn tag(&self) -> String {
+        // The users who have picked a unique username have no discriminator anymore
+        if self.discriminator == "0" {
+            self.username.clone()
+        } else {
+            format!("{}#{}", self.username, self.discriminator)
+        }
+    }
+}
+
+/// A guild of the user who has logged in.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Guild {
+    /// The ID, as a string.
+    pub id: String,
+    pub name: String,
+    /// The permissions of the user in the guild, as a string.
+    pub permissions: String,
+    /// Whether the user owns the guild.
+    #[serde(default)]
+    pub owner: bool,
+}
+
+impl Guild {
+    pub fn permissions(&self) -> Permissions {
+        Permissions::from_bits_truncate(self.permissions.parse().unwrap_or_default())
+    }
+
+    /// Whether the user may manage the guild, as its owner, an administrator or through the Manage Server permission.
+    pub fn is_manageable(&self) -> bool {
+        self.owner || self.permissions().intersects(Permissions::ADMINISTRATOR | Permissions::MANAGE_GUILD)
+    }
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+/// Whether the dashboard asks its users to log in, which needs the credentials of the application
+/// and the public URL Discord redirects them back to.
+pub fn enabled() -> bool {
+    let config = config::get();
+
+    config.dashboard.client_id.is_some() && config.dashboard.client_secret.is_some() && config.http.public_url.is_some()
+}
+
+/// The page of Discord asking the user to authorize the dashboard, which then sends them back with the state.
+pub fn authorize_url(state: &str) -> OAuthResult<String> {
+    let config = config::get();
+    let client_id = config.dashboard.client_id.ok_or("dashboard.client_id is not set")?;
+
+    let url = Url::parse_with_params(AUTHORIZE_URL, &[
+        ("client_id", client_id.to_string()),
+        ("redirect_uri", redirect_uri()?),
+        ("response_type", "code".to_string()),
+        ("scope", "identify guilds".to_string()),
+        ("state", state.to_string()),
+        ("prompt", "none".to_string()),
+    ])?;
+
+    Ok(url.to_string())
+}
+
+/// Exchanges the code Discord has sent the user back with for who they are and the guilds they are in;
+/// the access token is only used for that and then forgotten.
+pub async fn identify(code: &str) -> OAuthResult<(User, Vec<Guild>)> {
+    let config = config::get();
+    let client_id = config.dashboard.client_id.ok_or("dashboard.client_id is not set")?;
+    let client_secret = secrets::read(config.dashboard.client_secret.as_ref().ok_or("dashboard.client_secret is not set")?).await?;
+
+    let client = reqwest::Client::new();
+
+    let body = client
+        .post(format!("{}/oauth2/token", API_URL))
+        .form(&[
+            ("client_id", client_id.to_string()),
+            ("client_secret", client_secret),
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code.to_string()),
+            ("redirect_uri", redirect_uri()?),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let token = serde_json::from_slice::<Token>(&body)?.access_token;
+
+    let user = client
+        .get(format!("{}/users/@me", API_URL))
+        .bearer_auth(&token)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let guilds = client
+        .get(format!("{}/users/@me/guilds", API_URL))
+        .bearer_auth(&token)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    Ok((serde_json::from_slice(&user)?, serde_json::from_slice(&guilds)?))
+}
+
+/// Where Discord sends the users back to, which must be registered as a redirect of the application.
+fn redirect_uri() -> OAuthResult<String> {
+    let public_url = config::get().http.public_url.ok_or("http.public_url is not set")?;
+
+    Ok(format!("{}/dashboard/callback", public_url.trim_end_matches('/')))
+}
@@ -0,0 +1,197 @@
+//! A plain-text protocol letting other bots (or a companion CLI running as a bot account) play against this bot,
+//! as bots can neither run slash commands nor press buttons. It is disabled unless `gateway.protocol_bots` lists
+//! the bots allowed to use it, and only ever answers them.
+//!
+//! A request is a message that starts with a mention of this bot, followed by the protocol version and a command:
+//!
+//! ```text
+//! @RPS rps/1 play <rock|paper|scissors> [nonce]
+//! ```
+//!
+//! Mentioning the bot also makes Discord deliver the content without the privileged message content intent.
+//! Every round is a match of its own: the bot draws its move at random, independently of the request,
+//! and replies to the message with the outcome from the point of view of the requesting bot:
+//!
+//! ```text
+//! rps/1 result <rock|paper|scissors> <win|loss|draw> [nonce]
+//! ```
+//!
+//! A request that cannot be played is answered with `rps/1 error <code> [nonce]`, where the code is one of
+//! `unsupported_version`, `unknown_command`, `invalid_move`, `maintenance`, or `rate_limited`.
+//! The nonce is optional and echoed as is, so requests sent in quick succession can be told apart.
+//! Versions are only bumped for incompatible changes; new commands and error codes may be added to `rps/1`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+
+use tracing::info;
+
+use crate::config;
+use crate::flags;
+use crate::guilds;
+use crate::metrics;
+use crate::rules::{self, MOVES};
+
+/// The version every request and reply starts with.
+pub const VERSION: &str = "rps/1";
+
+/// How long a bot has to wait between two requests, so two misbehaving bots cannot flood a channel.
+const COOLDOWN: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    /// When each bot has last sent a request that has been answered.
+    static ref LAST_REQUESTS: Mutex<HashMap<UserId, Instant>> = Mutex::new(HashMap::new());
+    /// The generator the moves of the bot are drawn from, seeded once.
+    static ref RNG: Mutex<StdRng> = Mutex::new(StdRng::seed_from_u64(seed()));
+}
+
+pub async fn handle_message(ctx: &Context, msg: &Message) {
+    if !msg.author.bot || !config::get().gateway.protocol_bots.contains(&msg.author.id.0) {
+        return;
+    }
+
+    let request = match guilds::bot_id().and_then(|bot_id| strip_mention(&msg.content, bot_id)) {
+        Some(request) => request,
+        None => return,
+    };
+
+    let mut words = request.split_whitespace();
+
+    if words.next() != Some(VERSION) {
+        reply(ctx, msg, "error unsupported_version", None).await;
+
+        return;
+    }
+
+    let command = words.next();
+    let argument = words.next();
+    let nonce = words.next();
+
+    let answer = if !allow(msg.author.id) {
+        "error rate_limited".to_string()
+    } else if command != Some("play") {
+        "error unknown_command".to_string()
+    } else if flags::maintenance() {
+        "error maintenance".to_string()
+    } else {
+        match argument.and_then(|argument| MOVES.into_iter().find(|name| *name == argument)) {
+            Some(played) => {
+                let (own_move, outcome) = play(played, &mut *RNG.lock().unwrap());
+
+                metrics::PROTOCOL_ROUNDS.with_label_values(&[outcome]).inc();
+                info!(bot = %msg.author.id, outcome, "A round has been played through the protocol");
+
+                format!("result {} {}", own_move, outcome)
+            },
+            None => "error invalid_move".to_string(),
+        }
+    };
+
+    reply(ctx, msg, &answer, nonce).await;
+}
+
+/// The rest of the message if it starts with a mention of the bot, in either of its forms.
+fn strip_mention(content: &str, bot_id: UserId) -> Option<&str> {
+    let content = content.trim_start();
+
+    content.strip_prefix(&format!("<@{}>", bot_id))
+        .or_else(|| content.strip_prefix(&format!("<@!{}>", bot_id)))
+}
+
+/// Whether the bot is past its cooldown, which restarts if so.
+fn allow(bot_id: UserId) -> bool {
+    let mut last_requests = LAST_REQUESTS.lock().unwrap();
+    let now = Instant::now();
+
+    if last_requests.get(&bot_id).is_some_and(|last| now.duration_since(*last) < COOLDOWN) {
+        return false;
+    }
+
+    last_requests.insert(bot_id, now);
+
+    true
+}
+
+/// Plays a round against the move of the requesting bot, drawing the move of this one from the generator,
+/// and returns it with the outcome from the point of view of the requesting bot.
+fn play(played: &str, rng: &mut impl Rng) -> (&'static str, &'static str) {
+    let own_move = MOVES[rng.gen_range(0..MOVES.len())];
+
+    let outcome = match rules::wins(played, own_move) {
+        Some(true) => "win",
+        Some(false) => "loss",
+        None => "draw",
+    };
+
+    (own_move, outcome)
+}
+
+fn seed() -> u64 {
+    config::get().gateway.protocol_seed.unwrap_or_else(|| {
+        let seed = rand::random();
+
+        info!(seed, "The moves of the protocol are drawn with a random seed, which `gateway.protocol_seed` reproduces");
+
+        seed
+    })
+}
+
+async fn reply(ctx: &Context, msg: &Message, answer: &str, nonce: Option<&str>) {
+    let content = match nonce {
+        Some(nonce) => format!("{} {} {}", VERSION, answer, nonce),
+        None => format!("{} {}", VERSION, answer),
+    };
+
+    let _ = msg.reply(ctx, content).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounds(seed: u64) -> Vec<(&'static str, &'static str)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        MOVES.into_iter().cycle().take(30).map(|played| play(played, &mut rng)).collect()
+    }
+
+    #[test]
+    fn a_seed_reproduces_the_rounds() {
+        assert_eq!(rounds(42), rounds(42));
+        assert_ne!(rounds(42), rounds(43));
+    }
+
+    #[test]
+    fn the_outcome_follows_the_rules() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for played in MOVES.into_iter().cycle().take(30) {
+            let (own_move, outcome) = play(played, &mut rng);
+
+            let expected = match rules::wins(played, own_move) {
+                Some(true) => "win",
+                Some(false) => "loss",
+                None => "draw",
+            };
+
+            assert_eq!(outcome, expected, "{} against {}", played, own_move);
+        }
+    }
+
+    #[test]
+    fn every_move_is_drawn() {
+        let drawn = rounds(0).into_iter().map(|(own_move, _)| own_move).collect::<Vec<_>>();
+
+        assert!(MOVES.iter().all(|name| drawn.contains(name)));
+    }
+}
@@ -0,0 +1,43 @@
+//! Tracks whether the bot can actually serve its users, which `/ready` of the HTTP server reports,
+//! so an orchestrator only routes to (or keeps waiting for) a process that is connected and set up.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::warn;
+
+use crate::storage;
+
+/// Whether the shard is connected to the gateway; cleared while it reconnects.
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the application commands have been registered since the process started.
+static COMMANDS_SYNCED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_connected(connected: bool) {
+    CONNECTED.store(connected, Ordering::SeqCst);
+}
+
+pub fn set_commands_synced() {
+    COMMANDS_SYNCED.store(true, Ordering::SeqCst);
+}
+
+/// The checks the bot currently fails, none of them once it is ready.
+pub fn failing_checks() -> Vec<&'static str> {
+    let mut failing = Vec::new();
+
+    if !CONNECTED.load(Ordering::SeqCst) {
+        failing.push("gateway");
+    }
+
+    if !COMMANDS_SYNCED.load(Ordering::SeqCst) {
+        failing.push("commands");
+    }
+
+    if let Err(err) = storage::ping() {
+        warn!("The database could not have been reached: {:?}", err);
+
+        failing.push("database");
+    }
+
+    failing
+}
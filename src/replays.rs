@@ -0,0 +1,175 @@
+//! The replay pages of the HTTP server: `GET /replays/<match>` answers a minimal HTML page listing the rounds
+//! of the match, without any authentication, but only for the matches played in the guilds that have made
+//! their replays public with `/settings replays`; the others are answered 404, as if they did not exist.
+
+use hyper::{Body, Request, Response, StatusCode};
+
+use serenity::model::id::GuildId;
+use serenity::model::Timestamp;
+
+use tracing::error;
+
+#[cfg(feature = "cache")]
+use crate::admin;
+use crate::config;
+use crate::i18n::{self, tr, DEFAULT_LOCALE};
+use crate::rules;
+use crate::storage::{self, GuildSettings, Match};
+use crate::theme::{self, MoveEmojis};
+
+/// The prefix of the paths of the pages, followed by the ID of the match.
+pub const PREFIX: &str = "/replays/";
+
+/// Whether the replays of the guild with the settings are public; those of the games played in direct messages
+/// never are, as they have the default settings.
+pub fn public(settings: &GuildSettings) -> bool {
+    settings.public_replays.unwrap_or(false)
+}
+
+/// The link to the replay page of the match played in the guild with the settings, if it is public and the server
+/// has a public URL to build it on.
+pub fn link(settings: &GuildSettings, match_id: u64) -> Option<String> {
+    let config = config::get().http;
+    let public_url = config.public_url.filter(|_| config.listen.is_some())?;
+
+    public(settings).then(|| format!("{}{}{}", public_url.trim_end_matches('/'), PREFIX, match_id))
+}
+
+pub async fn handle(request: Request<Body>) -> hyper::http::Result<Response<Body>> {
+    let game = match request.uri().path().trim_start_matches(PREFIX).parse() {
+        Ok(match_id) => storage::find_match(match_id),
+        Err(_) => return status(StatusCode::NOT_FOUND),
+    };
+
+    let game = match game {
+        Ok(Some(game)) if public(&theme::settings(game.guild_id.map(GuildId))) => game,
+        Ok(_) => return status(StatusCode::NOT_FOUND),
+        Err(err) => {
+            error!("The match could not have been read: {:?}", err);
+
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        },
+    };
+
+    let rounds = match storage::match_rounds(game.id) {
+        Ok(rounds) => rounds,
+        Err(err) => {
+            error!("The rounds of the match could not have been read: {:?}", err);
+
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        },
+    };
+
+    // The first language the browser asks for, e.g. `de` out of `de-DE,de;q=0.9,en;q=0.8`
+    let locale = request.headers()
+        .get("Accept-Language")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split([',', ';']).next())
+        .map_or(DEFAULT_LOCALE.to_string(), |locale| locale.trim().to_string());
+
+    let locale = i18n::locales()
+        .find(|supported| supported.split('-').next() == locale.split('-').next())
+        .unwrap_or(DEFAULT_LOCALE);
+
+    let starter = name(game.starter_id);
+    let opponent = name(game.opponent_id);
+    let winner = if game.winner_id == game.starter_id { &starter } else { &opponent };
+
+    let emojis = MoveEmojis::default();
+
+    let formatted_move = |name: &str| {
+        let emoji = match name {
+            "rock" => &emojis.rock,
+            "paper" => &emojis.paper,
+            _ => &emojis.scissors,
+        };
+
+        format!("{} {}", emoji, tr!(locale, &format!("moves.{}", name)))
+    };
+
+    let rows = rounds.iter()
+        .map(|round| {
+            let outcome = match rules::wins(&round.starter_move, &round.opponent_move) {
+                Some(true) => tr!(locale, "replay.round_won", user = starter),
+                Some(false) => tr!(locale, "replay.round_won", user = opponent),
+                None => tr!(locale, "replay.round_drawn"),
+            };
+
+            format!(
+                "<tr><th>{}</th><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&tr!(locale, "rps.round", round = round.round)),
+                escape(&formatted_move(&round.starter_move)),
+                escape(&formatted_move(&round.opponent_move)),
+                escape(&outcome),
+            )
+        })
+        .collect::<String>();
+
+    let body = if rows.is_empty() {
+        format!(
+            "<p>{}</p>",
+            escape(&tr!(locale, "replay.no_rounds", starter = starter, opponent = opponent, winner = winner)),
+        )
+    } else {
+        format!(
+            "<table><tr><th></th><th>{}</th><th>{}</th><th>{}</th></tr>{}</table><p><strong>{}</strong></p>",
+            escape(&format!("{} ({})", tr!(locale, "replay.starter_move"), starter)),
+            escape(&format!("{} ({})", tr!(locale, "replay.opponent_move"), opponent)),
+            escape(&tr!(locale, "replay.outcome")),
+            rows,
+            escape(&tr!(locale, "replay.match_won", user = winner)),
+        )
+    };
+
+    let page = format!(
+        "<!DOCTYPE html>\n<html lang=\"{lang}\"><head><meta charset=\"utf-8\">\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>{title}</title>\
+        <style>body{{font-family:sans-serif;max-width:40em;margin:2em auto;padding:0 1em}}\
+        table{{border-collapse:collapse;width:100%}}th,td{{padding:.4em;border-bottom:1px solid #ddd;text-align:left}}</style>\
+        </head><body><h1>{title}</h1><p>{matchup}</p>{body}</body></html>\n",
+        lang = locale,
+        title = escape(&tr!(locale, "embeds.replay")),
+        matchup = escape(&matchup(&game, locale, &starter, &opponent)),
+        body = body,
+    );
+
+    Response::builder()
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(Body::from(page))
+}
+
+fn matchup(game: &Match, locale: &str, starter: &str, opponent: &str) -> String {
+    // The relative timestamps of Discord are not rendered outside of it
+    let finished = Timestamp::from_unix_timestamp(game.finished_at)
+        .map_or_else(|_| String::new(), |finished| finished.to_string());
+
+    tr!(locale, "replay.matchup", starter = starter, opponent = opponent, finished = finished)
+}
+
+/// The tag of the player as cached, or their ID otherwise. The users are never fetched, as anyone may load
+/// the pages, which must not spend the rate limits of the bot.
+#[cfg(feature = "cache")]
+fn name(user_id: u64) -> String {
+    admin::context()
+        .and_then(|ctx| ctx.cache.user(user_id))
+        .map_or_else(|| user_id.to_string(), |user| user.tag())
+}
+
+/// The ID of the player, as the users are never fetched for the pages anyone may load.
+#[cfg(not(feature = "cache"))]
+fn name(user_id: u64) -> String {
+    user_id.to_string()
+}
+
+/// Escapes the text for HTML, as the names of the players are chosen by them.
+fn escape(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn status(status: StatusCode) -> hyper::http::Result<Response<Body>> {
+    Response::builder().status(status).body(Body::empty())
+}
@@ -0,0 +1,153 @@
+//! Error reporting to Sentry, compiled in with the `sentry` feature and enabled by a DSN.
+//! Without either, every function here is a no-op so the call sites need no feature gates.
+
+use std::future::Future;
+
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+
+#[cfg(feature = "sentry")]
+use sentry::SentryFutureExt;
+
+use tracing::info;
+
+/// Keeps the Sentry client alive until the end of `main`, flushing the queued events when dropped.
+pub struct Guard {
+    #[cfg(feature = "sentry")]
+    _client: Option<sentry::ClientInitGuard>,
+}
+
+/// Starts reporting panics and logged errors if a DSN is configured
+/// (either via `sentry_dsn` in the config or the `SENTRY_DSN` environment variable).
+#[cfg(feature = "sentry")]
+pub fn init(dsn: Option<&str>) -> Guard {
+    let client = sentry::init(sentry::ClientOptions {
+        dsn: dsn.and_then(|dsn| dsn.parse().ok()),
+        release: sentry::release_name!(),
+        ..Default::default()
+    });
+
+    if !client.is_enabled() {
+        info!("No Sentry DSN is configured, the errors will not be reported!");
+
+        return Guard { _client: None };
+    }
+
+    info!("Reporting the errors to Sentry!");
+
+    Guard { _client: Some(client) }
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn init(dsn: Option<&str>) -> Guard {
+    if dsn.is_some() {
+        info!("A Sentry DSN is configured, but the bot has been built without the sentry feature!");
+    }
+
+    Guard {}
+}
+
+/// Runs the handling of a command in its own scope, so every event reported from it
+/// is tagged with the user, the guild, the channel, and the command data.
+#[cfg(feature = "sentry")]
+pub async fn with_command_context<F: Future>(cmd: &ApplicationCommandInteraction, future: F) -> F::Output {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use sentry::protocol::{Context, User, Value};
+    use sentry::Hub;
+
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+
+    hub.configure_scope(|scope| {
+        scope.set_user(Some(User {
+            id: Some(cmd.user.id.to_string()),
+            username: Some(cmd.user.tag()),
+            ..Default::default()
+        }));
+
+        scope.set_tag("command", &cmd.data.name);
+        scope.set_tag("channel", cmd.channel_id);
+
+        if let Some(guild_id) = cmd.guild_id {
+            scope.set_tag("guild", guild_id);
+        }
+
+        let mut data = BTreeMap::new();
+
+        data.insert("interaction".to_string(), Value::from(cmd.id.to_string()));
+        data.insert("locale".to_string(), Value::from(cmd.locale.as_str()));
+        data.insert("options".to_string(), serde_json::to_value(&cmd.data.options).unwrap_or_default());
+
+        scope.set_context("command", Context::Other(data));
+    });
+
+    future.bind_hub(hub).await
+}
+
+#[cfg(not(feature = "sentry"))]
+pub async fn with_command_context<F: Future>(_cmd: &ApplicationCommandInteraction, future: F) -> F::Output {
+    future.await
+}
+
+/// Runs the handling of a button press in its own scope, tagged like the command that has started the game.
+#[cfg(feature = "sentry")]
+pub async fn with_component_context<F: Future>(
+    interaction: &MessageComponentInteraction,
+    session_id: u64,
+    future: F,
+) -> F::Output {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use sentry::protocol::{Context, User, Value};
+    use sentry::Hub;
+
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+
+    hub.configure_scope(|scope| {
+        scope.set_user(Some(User {
+            id: Some(interaction.user.id.to_string()),
+            username: Some(interaction.user.tag()),
+            ..Default::default()
+        }));
+
+        scope.set_tag("session", session_id);
+        scope.set_tag("channel", interaction.channel_id);
+
+        if let Some(guild_id) = interaction.guild_id {
+            scope.set_tag("guild", guild_id);
+        }
+
+        let mut data = BTreeMap::new();
+
+        data.insert("interaction".to_string(), Value::from(interaction.id.to_string()));
+        data.insert("locale".to_string(), Value::from(interaction.locale.as_str()));
+        data.insert("custom_id".to_string(), Value::from(interaction.data.custom_id.as_str()));
+
+        scope.set_context("component", Context::Other(data));
+    });
+
+    future.bind_hub(hub).await
+}
+
+#[cfg(not(feature = "sentry"))]
+pub async fn with_component_context<F: Future>(
+    _interaction: &MessageComponentInteraction,
+    _session_id: u64,
+    future: F,
+) -> F::Output {
+    future.await
+}
+
+/// Tags the events of the current interaction with the game it belongs to and its state.
+#[cfg(feature = "sentry")]
+pub fn set_session(session_id: u64, state: &str) {
+    sentry::configure_scope(|scope| {
+        scope.set_tag("session", session_id);
+        scope.set_tag("session.state", state);
+    });
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn set_session(_session_id: u64, _state: &str) {}
@@ -0,0 +1,52 @@
+//! Prunes the matches that have outlived the retention of their guild, keeping the totals of their players.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serenity::model::Timestamp;
+
+use tracing::{error, info};
+
+use crate::config;
+use crate::storage::{self, StorageResult};
+
+/// How often the matches are pruned; retention is counted in days, so there is no need to be precise.
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+pub fn start() {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run());
+    }
+}
+
+async fn run() {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match prune(false) {
+            Ok(0) => {},
+            Ok(count) => info!("{} expired matches have been pruned", count),
+            Err(err) => error!("The expired matches could not have been pruned: {:?}", err),
+        }
+
+        match storage::purge_stats_resets(Timestamp::now().unix_timestamp() - grace_seconds()) {
+            Ok(0) => {},
+            Ok(count) => info!("{} totals set aside by statistics resets have been purged", count),
+            Err(err) => error!("The statistics resets could not have been purged: {:?}", err),
+        }
+    }
+}
+
+/// How long a reset of the statistics of a player can be undone.
+pub fn grace_seconds() -> i64 {
+    config::get().stats_reset_grace_days as i64 * 86400
+}
+
+/// Prunes the expired matches now, or only counts them on a dry run.
+pub fn prune(dry_run: bool) -> StorageResult<usize> {
+    storage::prune_matches(Timestamp::now().unix_timestamp(), config::get().retention_days, dry_run)
+}
@@ -0,0 +1,67 @@
+//! The rules of the game, shared by the games played with buttons, their replays and the text protocol of the bots.
+
+/// The moves, each of which beats the one right before it, cycling around.
+pub const MOVES: [&str; 3] = ["rock", "paper", "scissors"];
+
+/// Whether the move wins against the other one, or `None` on a draw.
+pub fn wins(played: &str, against: &str) -> Option<bool> {
+    match played {
+        "rock" => match against {
+            "rock" => None,
+            "paper" => Some(false),
+            _ => Some(true),
+        },
+        "paper" => match against {
+            "rock" => Some(true),
+            "paper" => None,
+            _ => Some(false),
+        },
+        _ => match against {
+            "rock" => Some(false),
+            "paper" => Some(true),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every ordered pair of moves, which is few enough to check every property for all of them.
+    fn pairs() -> impl Iterator<Item = (&'static str, &'static str)> {
+        MOVES.into_iter().flat_map(|played| MOVES.into_iter().map(move |against| (played, against)))
+    }
+
+    #[test]
+    fn equal_moves_draw() {
+        for played in MOVES {
+            assert_eq!(wins(played, played), None, "{} against itself", played);
+        }
+    }
+
+    #[test]
+    fn different_moves_never_draw() {
+        for (played, against) in pairs().filter(|(played, against)| played != against) {
+            assert!(wins(played, against).is_some(), "{} against {}", played, against);
+        }
+    }
+
+    #[test]
+    fn the_rules_are_antisymmetric() {
+        for (played, against) in pairs() {
+            assert_eq!(wins(played, against), wins(against, played).map(|won| !won), "{} against {}", played, against);
+        }
+    }
+
+    #[test]
+    fn every_move_beats_the_one_before_it() {
+        for (index, played) in MOVES.into_iter().enumerate() {
+            let previous = MOVES[(index + MOVES.len() - 1) % MOVES.len()];
+            let next = MOVES[(index + 1) % MOVES.len()];
+
+            assert_eq!(wins(played, previous), Some(true), "{} against {}", played, previous);
+            assert_eq!(wins(played, next), Some(false), "{} against {}", played, next);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Starts the games booked with `/rps schedule` once they are due, and posts the weekly digests of the guilds once
+//! their week is over. Both are stored in the database, so the games booked before a restart still start, late
+//! if the bot was down at the time, and the digests are still posted.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use serenity::client::Context;
+use serenity::model::Timestamp;
+
+use tracing::error;
+
+use crate::commands;
+use crate::digest;
+use crate::storage;
+
+/// How often the database is checked for due games, which is also how late a game may start.
+const INTERVAL: Duration = Duration::from_secs(15);
+
+/// `ready` is dispatched again on every reconnect, but only one scheduler must run.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+pub fn start(ctx: &Context) {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run(ctx.clone()));
+    }
+}
+
+async fn run(ctx: Context) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let games = match storage::due_scheduled_games(Timestamp::now().unix_timestamp()) {
+            Ok(games) => games,
+            Err(err) => {
+                error!("The scheduled games could not have been read: {:?}", err);
+
+                continue;
+            },
+        };
+
+        for game in games {
+            // Deleted first, so a game that fails to start is not retried on every tick
+            if let Err(err) = storage::delete_scheduled_game(game.id) {
+                error!("The scheduled game could not have been deleted: {:?}", err);
+
+                continue;
+            }
+
+            commands::rps::start_scheduled(&ctx, game).await;
+        }
+
+        digest::post_due(&ctx).await;
+    }
+}
@@ -0,0 +1,94 @@
+//! Reads the secrets from where the config says they are kept: the environment, a file, a Docker secret,
+//! HashiCorp Vault or AWS Secrets Manager.
+
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::aws;
+use crate::config::SecretSource;
+
+type SecretResult = Result<String, Box<dyn Error + Send + Sync>>;
+
+pub async fn read(source: &SecretSource) -> SecretResult {
+    let secret = match source {
+        SecretSource::Env { variable } => env::var(variable).map_err(|_| format!("{} is not set", variable))?,
+        SecretSource::File { path } => read_file(Path::new(path))?,
+        SecretSource::DockerSecret { name } => read_file(&Path::new("/run/secrets").join(name))?,
+        SecretSource::Vault { address, path, field } => read_vault(address, path, field).await?,
+        SecretSource::AwsSecretsManager { region, secret_id, field } => {
+            read_aws(region, secret_id, field.as_deref()).await?
+        },
+    };
+
+    // The files usually end with a line break
+    Ok(secret.trim().to_string())
+}
+
+fn read_file(path: &Path) -> SecretResult {
+    std::fs::read_to_string(path).map_err(|err| format!("{} could not have been read: {}", path.display(), err).into())
+}
+
+async fn read_vault(address: &str, path: &str, field: &str) -> SecretResult {
+    let token = env::var("VAULT_TOKEN").map_err(|_| "VAULT_TOKEN is not set")?;
+
+    let body = reqwest::Client::new()
+        .get(format!("{}/v1/{}", address.trim_end_matches('/'), path.trim_start_matches('/')))
+        .header("X-Vault-Token", token)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let body = serde_json::from_str::<Value>(&body)?;
+
+    // Version 2 of the KV engine nests the fields one level deeper than version 1
+    let value = body["data"]["data"].get(field).or_else(|| body["data"].get(field));
+
+    match value.and_then(Value::as_str) {
+        Some(value) => Ok(value.to_string()),
+        None => Err(format!("The Vault secret {} has no field {}", path, field).into()),
+    }
+}
+
+async fn read_aws(region: &str, secret_id: &str, field: Option<&str>) -> SecretResult {
+    let credentials = aws::Credentials::from_env()?;
+
+    let host = format!("secretsmanager.{}.amazonaws.com", region);
+    let payload = json!({ "SecretId": secret_id }).to_string();
+
+    let headers = aws::sign(&credentials, aws::Request {
+        method: "POST",
+        host: &host,
+        path: "/",
+        region,
+        service: "secretsmanager",
+        headers: vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+        ],
+        payload: payload.as_bytes(),
+    });
+
+    let mut request = reqwest::Client::new().post(format!("https://{}/", host)).body(payload);
+
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    let body = request.send().await?.error_for_status()?.text().await?;
+    let body = serde_json::from_str::<Value>(&body)?;
+
+    let secret = body["SecretString"].as_str().ok_or_else(|| format!("The secret {} is not a string", secret_id))?;
+
+    match field {
+        Some(field) => match serde_json::from_str::<Value>(secret)?[field].as_str() {
+            Some(value) => Ok(value.to_string()),
+            None => Err(format!("The secret {} has no field {}", secret_id, field).into()),
+        },
+        None => Ok(secret.to_string()),
+    }
+}
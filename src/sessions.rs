@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::user::User;
+use serenity::model::Timestamp;
+
+lazy_static! {
+    /// The games in progress, keyed by the ID of the command interaction that has started them,
+    /// which is also the session ID carried by the custom IDs of their buttons.
+    static ref SESSIONS: Mutex<HashMap<u64, Session>> = Mutex::new(HashMap::new());
+    /// The players whose game has ended because its message has been deleted in bulk, who have not been told yet.
+    static ref PURGED_PLAYERS: Mutex<HashSet<UserId>> = Mutex::new(HashSet::new());
+    /// The presses accepted lately in every session, by whom and on which component, to recognize a press sent again.
+    static ref PRESSES: Mutex<HashMap<u64, Vec<Press>>> = Mutex::new(HashMap::new());
+    /// The games waiting for the one in progress in their channel to end, in the guilds allowing a single game
+    /// per channel; they are not handed off, so a restart drops them.
+    static ref QUEUES: Mutex<HashMap<ChannelId, VecDeque<Session>>> = Mutex::new(HashMap::new());
+}
+
+/// How long a press is remembered, which only needs to cover a double click or a client sending the press again.
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(5);
+
+struct Press {
+    user_id: UserId,
+    /// The whole custom ID, which carries the turn, so the same button can still be pressed again on a later turn.
+    custom_id: String,
+    at: Instant,
+}
+
+/// Where a game stands between two button presses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Invited,
+    StarterTurn,
+    /// The move of the starter is kept here rather than in the buttons, so it cannot be seen by the opponent.
+    OpponentTurn { starter_move: &'static str },
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub id: u64,
+    pub starter: User,
+    pub opponent: User,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    /// The game message, known once the invitation has been sent.
+    pub message_id: Option<MessageId>,
+    /// The token of the interaction whose response is the game message, which allows editing it
+    /// for 15 minutes, after which it is edited as a regular message; `None` if the bot has posted the message itself.
+    pub token: Option<String>,
+    pub started_at: Timestamp,
+    /// The locale of the starter, used for every public message of the game.
+    pub locale: String,
+    pub round: usize,
+    /// The number of button presses that have advanced the game, carried by the buttons so stale ones are rejected.
+    pub turn: u32,
+    /// When the current turn has begun, to measure how long the players take to answer it.
+    pub turn_started_at: Instant,
+    pub state: State,
+    /// Whether the game is a ladder challenge, which swaps the positions of the players if the starter wins.
+    pub ladder: bool,
+    /// The moves of the starter and the opponent in every round played so far,
+    /// or since the session has been restored, as the messages do not keep them.
+    pub moves: Vec<(&'static str, &'static str)>,
+    /// The matches the starter and the opponent have won against each other before, once they have been read.
+    pub rivalry: Option<(usize, usize)>,
+}
+
+impl Session {
+    pub fn is_participant(&self, user_id: UserId) -> bool {
+        self.starter.id == user_id || self.opponent.id == user_id
+    }
+}
+
+/// How [`start_or_queue`] has registered a session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Start {
+    Started,
+    /// Waiting behind the game in progress in its channel, at the position, counted from 1.
+    Queued(usize),
+    /// Either player is already playing or waiting for a game.
+    Busy,
+}
+
+/// Registers the session unless either of its players is already playing, returning whether it has been started.
+pub fn start(session: Session) -> bool {
+    start_or_queue(session, false) == Start::Started
+}
+
+/// Registers the session unless either of its players is already playing or waiting; if `single_per_channel` is set
+/// and a game is already in progress in its channel, the session is queued behind it instead.
+pub fn start_or_queue(session: Session, single_per_channel: bool) -> Start {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let mut queues = QUEUES.lock().unwrap();
+
+    let involved = |other: &Session| other.is_participant(session.starter.id) || other.is_participant(session.opponent.id);
+
+    if sessions.values().any(involved) || queues.values().flatten().any(involved) {
+        return Start::Busy;
+    }
+
+    if single_per_channel && sessions.values().any(|other| other.channel_id == session.channel_id) {
+        let queue = queues.entry(session.channel_id).or_default();
+
+        // The game is posted by the bot once its turn comes, rather than as the response to the command
+        queue.push_back(Session { token: None, ..session });
+
+        return Start::Queued(queue.len());
+    }
+
+    sessions.insert(session.id, session);
+
+    Start::Started
+}
+
+/// Whether the user is waiting for a game queued in a channel.
+pub fn is_queued(user_id: UserId) -> bool {
+    QUEUES.lock().unwrap().values().flatten().any(|session| session.is_participant(user_id))
+}
+
+/// Takes the next game waiting in the channel, unless a game is still in progress there.
+pub fn next_queued(channel_id: ChannelId) -> Option<Session> {
+    let sessions = SESSIONS.lock().unwrap();
+
+    if sessions.values().any(|session| session.channel_id == channel_id) {
+        return None;
+    }
+
+    let mut queues = QUEUES.lock().unwrap();
+    let queue = queues.get_mut(&channel_id)?;
+    let session = queue.pop_front();
+
+    if queue.is_empty() {
+        queues.remove(&channel_id);
+    }
+
+    session
+}
+
+/// Whether the user has sent an invitation that has been neither answered nor cancelled yet.
+pub fn has_pending_invitation(user_id: UserId) -> bool {
+    SESSIONS.lock().unwrap().values().any(|session| session.starter.id == user_id && session.state == State::Invited)
+}
+
+/// The invitations the user has received and not answered yet.
+pub fn invitations_for(user_id: UserId) -> Vec<Session> {
+    SESSIONS.lock().unwrap()
+        .values()
+        .filter(|session| session.opponent.id == user_id && session.state == State::Invited)
+        .cloned()
+        .collect()
+}
+
+/// Applies the change to the session while holding the lock, so concurrent button presses are applied one by one.
+pub fn update<T>(id: u64, change: impl FnOnce(&mut Session) -> T) -> Option<T> {
+    SESSIONS.lock().unwrap().get_mut(&id).map(change)
+}
+
+pub fn contains(id: u64) -> bool {
+    SESSIONS.lock().unwrap().contains_key(&id)
+}
+
+pub fn end(id: u64) -> Option<Session> {
+    SESSIONS.lock().unwrap().remove(&id)
+}
+
+/// Ends the session if it still meets the condition, e.g. if it has not moved on since it was last read.
+pub fn end_if(id: u64, condition: impl FnOnce(&Session) -> bool) -> Option<Session> {
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    if !condition(sessions.get(&id)?) {
+        return None;
+    }
+
+    sessions.remove(&id)
+}
+
+/// The session whose game message is the message, if it is still in progress.
+pub fn by_message(message_id: MessageId) -> Option<Session> {
+    SESSIONS.lock().unwrap().values().find(|session| session.message_id == Some(message_id)).cloned()
+}
+
+/// Ends the game the user is playing and returns it, unless they are not playing any, an invitation not being one yet.
+pub fn end_played_by(user_id: UserId) -> Option<Session> {
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    let id = sessions.values()
+        .find(|session| session.is_participant(user_id) && session.state != State::Invited)
+        .map(|session| session.id)?;
+
+    sessions.remove(&id)
+}
+
+/// Ends the sessions whose game message has been deleted, returning how many there were.
+pub fn end_by_message(message_id: MessageId) -> usize {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let count = sessions.len();
+
+    sessions.retain(|_, session| session.message_id != Some(message_id));
+
+    count - sessions.len()
+}
+
+/// Ends the sessions whose game message is among the ones deleted in bulk (e.g. by a moderator purging the channel),
+/// returning how many there were; their players are told on their next interaction.
+pub fn end_by_messages(message_ids: &[MessageId]) -> usize {
+    let mut sessions = SESSIONS.lock().unwrap();
+    let mut purged_players = PURGED_PLAYERS.lock().unwrap();
+    let count = sessions.len();
+
+    sessions.retain(|_, session| {
+        let purged = session.message_id.is_some_and(|message_id| message_ids.contains(&message_id));
+
+        if purged {
+            purged_players.extend([session.starter.id, session.opponent.id]);
+        }
+
+        !purged
+    });
+
+    count - sessions.len()
+}
+
+/// Whether the game of the player has been ended by a bulk deletion since they have last been told, which they now are.
+pub fn take_purged(user_id: UserId) -> bool {
+    PURGED_PLAYERS.lock().unwrap().remove(&user_id)
+}
+
+/// Records the press of the component of the session by the user, once it has been accepted, so the same press
+/// sent again moments later is recognized as a duplicate by [`is_duplicate_press`].
+pub fn record_press(session_id: u64, user_id: UserId, custom_id: &str) {
+    let mut presses = PRESSES.lock().unwrap();
+
+    forget_expired_presses(&mut presses);
+
+    presses.entry(session_id).or_default().push(Press { user_id, custom_id: custom_id.to_string(), at: Instant::now() });
+}
+
+/// Whether the same press of the component of the session by the user has been accepted moments ago, in which case
+/// the press is a duplicate to acknowledge rather than reject; a rejected press is never recorded, so it is rejected
+/// again if sent again.
+pub fn is_duplicate_press(session_id: u64, user_id: UserId, custom_id: &str) -> bool {
+    let mut presses = PRESSES.lock().unwrap();
+
+    forget_expired_presses(&mut presses);
+
+    presses.get(&session_id)
+        .is_some_and(|ledger| ledger.iter().any(|press| press.user_id == user_id && press.custom_id == custom_id))
+}
+
+/// Forgets the presses older than [`DUPLICATE_WINDOW`], along with those of the sessions that have ended since.
+fn forget_expired_presses(presses: &mut HashMap<u64, Vec<Press>>) {
+    let now = Instant::now();
+
+    presses.retain(|_, ledger| {
+        ledger.retain(|press| now.duration_since(press.at) < DUPLICATE_WINDOW);
+
+        !ledger.is_empty()
+    });
+}
+
+/// A copy of every session in progress, oldest first.
+pub fn all() -> Vec<Session> {
+    let mut sessions = SESSIONS.lock().unwrap().values().cloned().collect::<Vec<_>>();
+
+    sessions.sort_by_key(|session| session.id);
+
+    sessions
+}
+
+/// Removes every session, returning them; used when the process shuts down.
+pub fn end_all() -> Vec<Session> {
+    SESSIONS.lock().unwrap().drain().map(|(_, session)| session).collect()
+}
+
+/// The number of sessions in progress and the number of users taking part in them.
+pub fn count() -> (usize, usize) {
+    let count = SESSIONS.lock().unwrap().len();
+
+    (count, count * 2)
+}
@@ -0,0 +1,90 @@
+//! The mirrors of live games posted with `/spectate` in other channels or in direct messages. They show the same embed
+//! as the game message, without its buttons or the state encoded in its footer, and are edited along with it until
+//! the game ends, after which they keep its result and are forgotten. They only live in memory, like the games.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use serenity::builder::CreateEmbed;
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, MessageId};
+
+use tracing::warn;
+
+use crate::i18n::tr;
+use crate::sessions::{self, Session};
+
+/// How many mirrors a game can have, so spectating cannot be used to flood channels with edits.
+const MAX_MIRRORS: usize = 10;
+
+lazy_static! {
+    /// The mirror messages of the games being spectated, keyed by their session.
+    static ref MIRRORS: Mutex<HashMap<u64, Vec<(ChannelId, MessageId)>>> = Mutex::new(HashMap::new());
+}
+
+/// Makes sure the game can be mirrored to the channel, or returns the resource key of the reason it cannot.
+pub fn check(session_id: u64, channel_id: ChannelId) -> Result<(), &'static str> {
+    let mirrors = MIRRORS.lock().unwrap();
+    let mirrors = mirrors.get(&session_id).map_or(&[][..], Vec::as_slice);
+
+    if mirrors.iter().any(|(mirror_channel_id, _)| *mirror_channel_id == channel_id) {
+        return Err("spectate.already_mirrored");
+    }
+
+    if mirrors.len() >= MAX_MIRRORS {
+        return Err("spectate.too_many");
+    }
+
+    Ok(())
+}
+
+/// Registers the message mirroring the game, once it has been posted.
+pub fn subscribe(session_id: u64, channel_id: ChannelId, message_id: MessageId) {
+    MIRRORS.lock().unwrap().entry(session_id).or_default().push((channel_id, message_id));
+}
+
+/// The embed of the game message as shown to the spectators: the snapshot in its footer is replaced,
+/// as it carries the move of the starter while the opponent is yet to move, masked but not secret.
+pub fn embed(session: &Session, mut embed: CreateEmbed) -> CreateEmbed {
+    embed.footer(|footer| footer.text(tr!(&session.locale, "spectate.footer")));
+
+    embed
+}
+
+/// Edits the mirrors of the game to show the embed of its message; once the game has ended, this is the last edit.
+pub fn mirror(ctx: &Context, session: &Session, game_embed: CreateEmbed) {
+    let mirrors = {
+        let mut mirrors = MIRRORS.lock().unwrap();
+
+        if sessions::contains(session.id) {
+            mirrors.get(&session.id).cloned()
+        } else {
+            mirrors.remove(&session.id)
+        }
+    };
+
+    let mirrors = match mirrors {
+        Some(mirrors) => mirrors,
+        None => return,
+    };
+
+    let embed = embed(session, game_embed);
+    let ctx = ctx.clone();
+    let session_id = session.id;
+
+    tokio::spawn(async move {
+        for (channel_id, message_id) in mirrors {
+            if let Err(err) = channel_id.edit_message(&ctx.http, message_id, |msg| msg.set_embed(embed.clone())).await {
+                warn!(session = session_id, "A mirror of the game could not have been edited: {:?}", err);
+            }
+        }
+    });
+}
+
+/// Forgets the mirrors of the games that have ended without being mirrored one last time,
+/// e.g. because their message has been deleted.
+pub fn forget_ended() {
+    MIRRORS.lock().unwrap().retain(|session_id, _| sessions::contains(*session_id));
+}
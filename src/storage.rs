@@ -0,0 +1,1628 @@
+use std::collections::HashMap;
+use std::error::Error;
+#[cfg(feature = "sqlcipher")]
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+
+use rusqlite::{params, OpenFlags, OptionalExtension, Row};
+#[cfg(feature = "sqlcipher")]
+use rusqlite::DatabaseName;
+
+use crate::config;
+
+pub type StorageResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Every schema change is appended to this list and applied once, tracked via `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE guild_settings (
+        guild_id INTEGER PRIMARY KEY,
+        success_color INTEGER,
+        failure_color INTEGER,
+        confirmation_color INTEGER,
+        warning_color INTEGER
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN rock_emoji TEXT;
+    ALTER TABLE guild_settings ADD COLUMN paper_emoji TEXT;
+    ALTER TABLE guild_settings ADD COLUMN scissors_emoji TEXT;",
+    "CREATE TABLE user_preferences (
+        user_id INTEGER PRIMARY KEY,
+        locale TEXT
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN button_labels TEXT;",
+    "CREATE TABLE guild_flags (
+        guild_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        enabled INTEGER NOT NULL,
+        PRIMARY KEY (guild_id, name)
+    );",
+    "ALTER TABLE user_preferences ADD COLUMN dm_invitations INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE scheduled_games (
+        id INTEGER PRIMARY KEY,
+        guild_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        starter_id INTEGER NOT NULL,
+        opponent_id INTEGER NOT NULL,
+        locale TEXT NOT NULL,
+        starts_at INTEGER NOT NULL
+    );
+    CREATE INDEX scheduled_games_starts_at ON scheduled_games (starts_at);",
+    "CREATE TABLE user_blocks (
+        user_id INTEGER NOT NULL,
+        blocked_id INTEGER NOT NULL,
+        PRIMARY KEY (user_id, blocked_id)
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN games_channel_id INTEGER;
+    ALTER TABLE guild_settings ADD COLUMN game_timeout INTEGER;
+    ALTER TABLE guild_settings ADD COLUMN log_channel_id INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN global_stats INTEGER;
+    CREATE TABLE matches (
+        id INTEGER PRIMARY KEY,
+        guild_id INTEGER,
+        starter_id INTEGER NOT NULL,
+        opponent_id INTEGER NOT NULL,
+        winner_id INTEGER NOT NULL,
+        rounds INTEGER NOT NULL,
+        global INTEGER NOT NULL,
+        finished_at INTEGER NOT NULL
+    );
+    CREATE INDEX matches_guild_id ON matches (guild_id);",
+    "ALTER TABLE guild_settings ADD COLUMN retention_days INTEGER;
+    CREATE INDEX matches_finished_at ON matches (finished_at);
+    CREATE TABLE match_totals (
+        guild_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        global INTEGER NOT NULL,
+        wins INTEGER NOT NULL,
+        losses INTEGER NOT NULL,
+        PRIMARY KEY (guild_id, user_id, global)
+    );",
+    "CREATE INDEX matches_starter_id ON matches (starter_id, finished_at);
+    CREATE INDEX matches_opponent_id ON matches (opponent_id, finished_at);",
+    "ALTER TABLE user_preferences ADD COLUMN title TEXT;",
+    "ALTER TABLE guild_settings ADD COLUMN rematch_emoji TEXT;",
+    "ALTER TABLE guild_settings ADD COLUMN highlights_channel_id INTEGER;
+    ALTER TABLE matches ADD COLUMN shared INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE guild_settings ADD COLUMN ladder_range INTEGER;
+    CREATE TABLE ladder (
+        guild_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (guild_id, user_id)
+    );
+    CREATE UNIQUE INDEX ladder_position ON ladder (guild_id, position);",
+    "CREATE TABLE rounds (
+        match_id INTEGER NOT NULL,
+        round INTEGER NOT NULL,
+        starter_move TEXT NOT NULL,
+        opponent_move TEXT NOT NULL,
+        PRIMARY KEY (match_id, round)
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN stream_mode INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN layout TEXT;
+    ALTER TABLE user_preferences ADD COLUMN layout TEXT;",
+    "CREATE INDEX matches_pair ON matches (starter_id, opponent_id, finished_at);",
+    "ALTER TABLE user_preferences ADD COLUMN hide_tendencies INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE votes (
+        user_id INTEGER NOT NULL,
+        voted_at INTEGER NOT NULL
+    );
+    CREATE INDEX votes_user_id ON votes (user_id, voted_at);",
+    "CREATE TABLE handed_off_sessions (
+        id INTEGER PRIMARY KEY,
+        snapshot TEXT NOT NULL,
+        guild_id INTEGER,
+        channel_id INTEGER NOT NULL,
+        message_id INTEGER,
+        token TEXT,
+        started_at INTEGER NOT NULL,
+        moves TEXT NOT NULL
+    );",
+    "CREATE TABLE stats_resets (
+        guild_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        reset_at INTEGER NOT NULL,
+        reset_by INTEGER NOT NULL,
+        previous_reset_at INTEGER,
+        PRIMARY KEY (guild_id, user_id)
+    );
+    CREATE TABLE reset_match_totals (
+        guild_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        global INTEGER NOT NULL,
+        wins INTEGER NOT NULL,
+        losses INTEGER NOT NULL,
+        PRIMARY KEY (guild_id, user_id, global)
+    );",
+    "ALTER TABLE guild_settings ADD COLUMN modal_moves INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN queue_games INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN turn_timeout INTEGER;
+    ALTER TABLE guild_settings ADD COLUMN afk_warning INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN public_replays INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN digest_channel_id INTEGER;
+    ALTER TABLE guild_settings ADD COLUMN digest_week INTEGER;",
+    "ALTER TABLE guild_settings ADD COLUMN champion_channel_id INTEGER;
+    ALTER TABLE user_preferences ADD COLUMN dm_dethronements INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE handed_off_sessions ADD COLUMN turn_started_at INTEGER;",
+];
+
+lazy_static! {
+    static ref POOL: Pool<SqliteConnectionManager> = {
+        let manager = SqliteConnectionManager::file(config::get().database_path);
+
+        // Every connection must be given the key before anything is read from an encrypted database
+        #[cfg(feature = "sqlcipher")]
+        let manager = match KEY.read().unwrap().clone() {
+            Some(key) => manager.with_init(move |conn| conn.pragma_update(None, "key", &key)),
+            None => manager,
+        };
+
+        Pool::new(manager).expect("The database could not have been opened!")
+    };
+}
+
+/// The key the database is encrypted with, set before the first connection is opened.
+#[cfg(feature = "sqlcipher")]
+static KEY: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets the key the database is encrypted with; must be called before the database is first accessed.
+pub fn set_key(key: String) -> StorageResult<()> {
+    #[cfg(feature = "sqlcipher")]
+    {
+        *KEY.write().unwrap() = Some(key);
+
+        Ok(())
+    }
+
+    // Running on the plaintext database the key was meant to protect would defeat its purpose
+    #[cfg(not(feature = "sqlcipher"))]
+    {
+        let _ = key;
+
+        Err("A database key is configured, but the bot has been built without the sqlcipher feature".into())
+    }
+}
+
+/// Writes an encrypted copy of the plaintext database at `database_path` to the path, which can then replace it.
+#[cfg(feature = "sqlcipher")]
+pub fn encrypt_into(path: &str) -> StorageResult<()> {
+    let key = KEY.read().unwrap().clone().ok_or("No database key is configured")?;
+
+    let conn = rusqlite::Connection::open(config::get().database_path)?;
+
+    conn.execute("ATTACH DATABASE ?1 AS encrypted KEY ?2", params![path, key])?;
+    conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+
+    // The schema version is not part of the export
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    conn.pragma_update(Some(DatabaseName::Attached("encrypted")), "user_version", version)?;
+    conn.execute("DETACH DATABASE encrypted", [])?;
+
+    Ok(())
+}
+
+pub fn pool_state() -> r2d2::State {
+    POOL.state()
+}
+
+pub fn pool_max_size() -> u32 {
+    POOL.max_size()
+}
+
+/// Checks that the database can be reached and queried.
+pub fn ping() -> StorageResult<()> {
+    connection()?.query_row("SELECT 1", [], |_| Ok(()))?;
+
+    Ok(())
+}
+
+fn connection() -> StorageResult<PooledConnection<SqliteConnectionManager>> {
+    Ok(POOL.get()?)
+}
+
+pub fn migrate() -> StorageResult<()> {
+    let mut conn = connection()?;
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    let tx = conn.transaction()?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// The number of migrations applied to the database.
+pub fn schema_version() -> StorageResult<usize> {
+    Ok(connection()?.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Writes a consistent snapshot of the database, including its schema version, to a file that must not exist yet.
+pub fn backup_into(path: &str) -> StorageResult<()> {
+    connection()?.execute("VACUUM INTO ?1", params![path])?;
+
+    Ok(())
+}
+
+/// Checks that the file is an intact backup this version of the bot can migrate, returning its schema version.
+pub fn check_backup(path: &str) -> StorageResult<usize> {
+    let conn = rusqlite::Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    #[cfg(feature = "sqlcipher")]
+    if let Some(key) = KEY.read().unwrap().as_ref() {
+        conn.pragma_update(None, "key", key)?;
+    }
+
+    let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+
+    if integrity != "ok" {
+        return Err(format!("The backup is corrupted: {}", integrity).into());
+    }
+
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    if version > MIGRATIONS.len() {
+        return Err(format!("The backup comes from a newer version of the bot (schema version {})", version).into());
+    }
+
+    Ok(version)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GuildSettings {
+    pub success_color: Option<u32>,
+    pub failure_color: Option<u32>,
+    pub confirmation_color: Option<u32>,
+    pub warning_color: Option<u32>,
+    pub rock_emoji: Option<String>,
+    pub paper_emoji: Option<String>,
+    pub scissors_emoji: Option<String>,
+    pub button_labels: Option<String>,
+    /// The only channel games can be started in.
+    pub games_channel_id: Option<u64>,
+    /// How long a game may last, in seconds.
+    pub game_timeout: Option<u64>,
+    /// The channel the finished games are logged in.
+    pub log_channel_id: Option<u64>,
+    /// Whether the games played in the guild count toward the global statistics, which they do unless disabled.
+    pub global_stats: Option<bool>,
+    /// How many days the matches played in the guild are kept, overriding the config.
+    pub retention_days: Option<u64>,
+    /// The reaction starting a rematch on the result of a game; rematches cannot be started by reaction if unset.
+    pub rematch_emoji: Option<String>,
+    /// The channel the players can share the results of their games to.
+    pub highlights_channel_id: Option<u64>,
+    /// How many positions above themselves the players of the ladder can challenge.
+    pub ladder_range: Option<u32>,
+    /// Whether the games are paced and narrated for an audience, with a pinned live score in their channels.
+    pub stream_mode: Option<bool>,
+    /// How much the game messages show, unless the starter of a game has chosen otherwise.
+    pub layout: Option<String>,
+    /// Whether the moves are typed into a form rather than picked with a button for each.
+    pub modal_moves: Option<bool>,
+    /// Whether a single game may be in progress in every channel, the others waiting for it to end.
+    pub queue_games: Option<bool>,
+    /// How long a player may take to move, in seconds, before forfeiting the game; the turns are not timed if unset.
+    pub turn_timeout: Option<u64>,
+    /// How long before the end of the turn timer the player who has not moved yet is warned, in seconds.
+    pub afk_warning: Option<u64>,
+    /// Whether the replays of the matches finished in the guild can be watched on the web by anyone with their link.
+    pub public_replays: Option<bool>,
+    /// The channel the weekly digest of the guild is posted in.
+    pub digest_channel_id: Option<u64>,
+    /// The channel a new first place of the leaderboard of the guild is announced in.
+    pub champion_channel_id: Option<u64>,
+}
+
+pub fn guild_settings(guild_id: u64) -> StorageResult<GuildSettings> {
+    let settings = connection()?
+        .query_row(
+            "SELECT success_color, failure_color, confirmation_color, warning_color,
+                rock_emoji, paper_emoji, scissors_emoji,
+                button_labels,
+                games_channel_id, game_timeout, log_channel_id,
+                global_stats, retention_days,
+                rematch_emoji, highlights_channel_id, ladder_range,
+                stream_mode, layout, modal_moves, queue_games,
+                turn_timeout, afk_warning, public_replays,
+                digest_channel_id, champion_channel_id
+            FROM guild_settings WHERE guild_id = ?1",
+            params![guild_id],
+            |row| Ok(GuildSettings {
+                success_color: row.get(0)?,
+                failure_color: row.get(1)?,
+                confirmation_color: row.get(2)?,
+                warning_color: row.get(3)?,
+                rock_emoji: row.get(4)?,
+                paper_emoji: row.get(5)?,
+                scissors_emoji: row.get(6)?,
+                button_labels: row.get(7)?,
+                games_channel_id: row.get(8)?,
+                game_timeout: row.get(9)?,
+                log_channel_id: row.get(10)?,
+                global_stats: row.get(11)?,
+                retention_days: row.get(12)?,
+                rematch_emoji: row.get(13)?,
+                highlights_channel_id: row.get(14)?,
+                ladder_range: row.get(15)?,
+                stream_mode: row.get(16)?,
+                layout: row.get(17)?,
+                modal_moves: row.get(18)?,
+                queue_games: row.get(19)?,
+                turn_timeout: row.get(20)?,
+                afk_warning: row.get(21)?,
+                public_replays: row.get(22)?,
+                digest_channel_id: row.get(23)?,
+                champion_channel_id: row.get(24)?,
+            }),
+        )
+        .optional()?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+pub fn save_guild_settings(guild_id: u64, settings: &GuildSettings) -> StorageResult<()> {
+    connection()?.execute(
+        "INSERT INTO guild_settings (
+            guild_id,
+            success_color, failure_color, confirmation_color, warning_color,
+            rock_emoji, paper_emoji, scissors_emoji,
+            button_labels,
+            games_channel_id, game_timeout, log_channel_id,
+            global_stats, retention_days,
+            rematch_emoji, highlights_channel_id, ladder_range,
+            stream_mode, layout, modal_moves, queue_games,
+            turn_timeout, afk_warning, public_replays,
+            digest_channel_id, champion_channel_id
+        )
+        VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21,
+            ?22, ?23, ?24, ?25, ?26
+        )
+        ON CONFLICT (guild_id) DO UPDATE SET
+            success_color = excluded.success_color,
+            failure_color = excluded.failure_color,
+            confirmation_color = excluded.confirmation_color,
+            warning_color = excluded.warning_color,
+            rock_emoji = excluded.rock_emoji,
+            paper_emoji = excluded.paper_emoji,
+            scissors_emoji = excluded.scissors_emoji,
+            button_labels = excluded.button_labels,
+            games_channel_id = excluded.games_channel_id,
+            game_timeout = excluded.game_timeout,
+            log_channel_id = excluded.log_channel_id,
+            global_stats = excluded.global_stats,
+            retention_days = excluded.retention_days,
+            rematch_emoji = excluded.rematch_emoji,
+            highlights_channel_id = excluded.highlights_channel_id,
+            ladder_range = excluded.ladder_range,
+            stream_mode = excluded.stream_mode,
+            layout = excluded.layout,
+            modal_moves = excluded.modal_moves,
+            queue_games = excluded.queue_games,
+            turn_timeout = excluded.turn_timeout,
+            afk_warning = excluded.afk_warning,
+            public_replays = excluded.public_replays,
+            digest_channel_id = excluded.digest_channel_id,
+            champion_channel_id = excluded.champion_channel_id",
+        params![
+            guild_id,
+            settings.success_color,
+            settings.failure_color,
+            settings.confirmation_color,
+            settings.warning_color,
+            settings.rock_emoji,
+            settings.paper_emoji,
+            settings.scissors_emoji,
+            settings.button_labels,
+            settings.games_channel_id,
+            settings.game_timeout,
+            settings.log_channel_id,
+            settings.global_stats,
+            settings.retention_days,
+            settings.rematch_emoji,
+            settings.highlights_channel_id,
+            settings.ladder_range,
+            settings.stream_mode,
+            settings.layout,
+            settings.modal_moves,
+            settings.queue_games,
+            settings.turn_timeout,
+            settings.afk_warning,
+            settings.public_replays,
+            settings.digest_channel_id,
+            settings.champion_channel_id,
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// The guilds with a digest channel that have not been sent the digest of the week yet, with their channels.
+pub fn due_digests(week: i64) -> StorageResult<Vec<(u64, u64)>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(
+        "SELECT guild_id, digest_channel_id FROM guild_settings
+        WHERE digest_channel_id IS NOT NULL AND (digest_week IS NULL OR digest_week < ?1)",
+    )?;
+
+    let guilds = statement
+        .query_map(params![week], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(guilds)
+}
+
+/// Remembers that the guild has been sent the digest of the week, so it is not sent again.
+pub fn set_digest_week(guild_id: u64, week: i64) -> StorageResult<()> {
+    connection()?.execute("UPDATE guild_settings SET digest_week = ?2 WHERE guild_id = ?1", params![guild_id, week])?;
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UserPreferences {
+    pub locale: Option<String>,
+    /// Whether the user is sent a direct message whenever they are challenged.
+    pub dm_invitations: bool,
+    /// The title shown next to the name of the user in game messages.
+    pub title: Option<String>,
+    /// How much the game messages started by the user show, overriding the layout of the guild.
+    pub layout: Option<String>,
+    /// Whether the user keeps their opponents from seeing how they tend to play after a game.
+    pub hide_tendencies: bool,
+    /// Whether the user is sent a direct message when someone takes their first place on the leaderboard of a guild.
+    pub dm_dethronements: bool,
+}
+
+pub fn user_preferences(user_id: u64) -> StorageResult<UserPreferences> {
+    let preferences = connection()?
+        .query_row(
+            "SELECT locale, dm_invitations, title, layout, hide_tendencies, dm_dethronements
+            FROM user_preferences WHERE user_id = ?1",
+            params![user_id],
+            |row| Ok(UserPreferences {
+                locale: row.get(0)?,
+                dm_invitations: row.get(1)?,
+                title: row.get(2)?,
+                layout: row.get(3)?,
+                hide_tendencies: row.get(4)?,
+                dm_dethronements: row.get(5)?,
+            }),
+        )
+        .optional()?;
+
+    Ok(preferences.unwrap_or_default())
+}
+
+pub fn save_user_preferences(user_id: u64, preferences: &UserPreferences) -> StorageResult<()> {
+    connection()?.execute(
+        "INSERT INTO user_preferences (user_id, locale, dm_invitations, title, layout, hide_tendencies, dm_dethronements)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ON CONFLICT (user_id) DO UPDATE SET
+            locale = excluded.locale,
+            dm_invitations = excluded.dm_invitations,
+            title = excluded.title,
+            layout = excluded.layout,
+            hide_tendencies = excluded.hide_tendencies,
+            dm_dethronements = excluded.dm_dethronements",
+        params![
+            user_id,
+            preferences.locale,
+            preferences.dm_invitations,
+            preferences.title,
+            preferences.layout,
+            preferences.hide_tendencies,
+            preferences.dm_dethronements,
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// The feature flags the guild overrides, mapped to whether they are enabled.
+pub fn guild_flags(guild_id: u64) -> StorageResult<HashMap<String, bool>> {
+    let conn = connection()?;
+    let mut statement = conn.prepare("SELECT name, enabled FROM guild_flags WHERE guild_id = ?1")?;
+
+    let flags = statement
+        .query_map(params![guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(flags)
+}
+
+/// Overrides a feature flag for the guild, or makes it follow the default again if `enabled` is `None`.
+pub fn save_guild_flag(guild_id: u64, name: &str, enabled: Option<bool>) -> StorageResult<()> {
+    let conn = connection()?;
+
+    match enabled {
+        Some(enabled) => conn.execute(
+            "INSERT INTO guild_flags (guild_id, name, enabled)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (guild_id, name) DO UPDATE SET
+                enabled = excluded.enabled",
+            params![guild_id, name, enabled],
+        )?,
+        None => conn.execute(
+            "DELETE FROM guild_flags WHERE guild_id = ?1 AND name = ?2",
+            params![guild_id, name],
+        )?,
+    };
+
+    Ok(())
+}
+
+/// Whether the user has blocked the other one, refusing their invitations.
+pub fn is_blocked(user_id: u64, blocked_id: u64) -> StorageResult<bool> {
+    let blocked = connection()?
+        .query_row(
+            "SELECT 1 FROM user_blocks WHERE user_id = ?1 AND blocked_id = ?2",
+            params![user_id, blocked_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    Ok(blocked)
+}
+
+/// Blocks or unblocks the other user for the user.
+pub fn save_block(user_id: u64, blocked_id: u64, blocked: bool) -> StorageResult<()> {
+    let conn = connection()?;
+
+    if blocked {
+        conn.execute(
+            "INSERT OR IGNORE INTO user_blocks (user_id, blocked_id) VALUES (?1, ?2)",
+            params![user_id, blocked_id],
+        )?;
+    } else {
+        conn.execute(
+            "DELETE FROM user_blocks WHERE user_id = ?1 AND blocked_id = ?2",
+            params![user_id, blocked_id],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A game booked with `/rps schedule`, whose invitation is posted once it is due.
+#[derive(Clone, Debug)]
+pub struct ScheduledGame {
+    /// The ID of the command interaction that has booked the game, which becomes the session ID.
+    pub id: u64,
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub starter_id: u64,
+    pub opponent_id: u64,
+    pub locale: String,
+    /// The UNIX timestamp the game starts at.
+    pub starts_at: i64,
+}
+
+pub fn save_scheduled_game(game: &ScheduledGame) -> StorageResult<()> {
+    connection()?.execute(
+        "INSERT INTO scheduled_games (id, guild_id, channel_id, starter_id, opponent_id, locale, starts_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            game.id,
+            game.guild_id,
+            game.channel_id,
+            game.starter_id,
+            game.opponent_id,
+            game.locale,
+            game.starts_at,
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// The scheduled games starting at or before the UNIX timestamp, earliest first.
+pub fn due_scheduled_games(now: i64) -> StorageResult<Vec<ScheduledGame>> {
+    let conn = connection()?;
+    let mut statement = conn.prepare(
+        "SELECT id, guild_id, channel_id, starter_id, opponent_id, locale, starts_at
+        FROM scheduled_games WHERE starts_at <= ?1 ORDER BY starts_at",
+    )?;
+
+    let games = statement
+        .query_map(params![now], |row| Ok(ScheduledGame {
+            id: row.get(0)?,
+            guild_id: row.get(1)?,
+            channel_id: row.get(2)?,
+            starter_id: row.get(3)?,
+            opponent_id: row.get(4)?,
+            locale: row.get(5)?,
+            starts_at: row.get(6)?,
+        }))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(games)
+}
+
+pub fn delete_scheduled_game(id: u64) -> StorageResult<()> {
+    connection()?.execute("DELETE FROM scheduled_games WHERE id = ?1", params![id])?;
+
+    Ok(())
+}
+
+/// A finished game, recorded once either player has won it.
+#[derive(Clone, Debug)]
+pub struct Match {
+    /// The ID of the session the game has been played in.
+    pub id: u64,
+    /// The guild the game has been played in, `None` in direct messages.
+    pub guild_id: Option<u64>,
+    pub starter_id: u64,
+    pub opponent_id: u64,
+    pub winner_id: u64,
+    pub rounds: u32,
+    /// The UNIX timestamp the game has finished at.
+    pub finished_at: i64,
+    /// Whether the result has been shared to the highlights channel of the guild.
+    pub shared: bool,
+}
+
+/// Records the match, tagged as global unless its guild keeps its statistics local.
+pub fn save_match(game: &Match) -> StorageResult<()> {
+    let global = match game.guild_id {
+        Some(guild_id) => guild_settings(guild_id)?.global_stats.unwrap_or(true),
+        None => true,
+    };
+
+    connection()?.execute(
+        "INSERT OR IGNORE INTO matches (id, guild_id, starter_id, opponent_id, winner_id, rounds, global, finished_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            game.id,
+            game.guild_id,
+            game.starter_id,
+            game.opponent_id,
+            game.winner_id,
+            game.rounds,
+            global,
+            game.finished_at,
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn find_match(id: u64) -> StorageResult<Option<Match>> {
+    let game = connection()?
+        .query_row(
+            "SELECT id, guild_id, starter_id, opponent_id, winner_id, rounds, finished_at, shared
+            FROM matches WHERE id = ?1",
+            params![id],
+            match_from_row,
+        )
+        .optional()?;
+
+    Ok(game)
+}
+
+/// Reads a match from the `id, guild_id, starter_id, opponent_id, winner_id, rounds, finished_at, shared` columns.
+fn match_from_row(row: &Row) -> rusqlite::Result<Match> {
+    Ok(Match {
+        id: row.get(0)?,
+        guild_id: row.get(1)?,
+        starter_id: row.get(2)?,
+        opponent_id: row.get(3)?,
+        winner_id: row.get(4)?,
+        rounds: row.get(5)?,
+        finished_at: row.get(6)?,
+        shared: row.get(7)?,
+    })
+}
+
+/// The latest matches of the guild, or of every guild and direct message if `None`, and of the user if any;
+/// the pruned ones are gone.
+pub fn recent_matches(guild_id: Option<u64>, user_id: Option<u64>, limit: usize) -> StorageResult<Vec<Match>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(
+        "SELECT id, guild_id, starter_id, opponent_id, winner_id, rounds, finished_at, shared
+        FROM matches
+        WHERE (?1 IS NULL OR guild_id = ?1) AND (?2 IS NULL OR starter_id = ?2 OR opponent_id = ?2)
+        ORDER BY finished_at DESC
+        LIMIT ?3",
+    )?;
+
+    let matches = statement
+        .query_map(params![guild_id, user_id, limit], match_from_row)?
+        .collect::<Result<_, _>>()?;
+
+    Ok(matches)
+}
+
+/// Counts the matches finished since the timestamp by guild (`None` for direct messages) and by UTC day,
+/// the days being numbered from the UNIX epoch.
+pub fn daily_matches(since: i64) -> StorageResult<Vec<(Option<u64>, i64, u64)>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(
+        "SELECT guild_id, finished_at / 86400 AS day, COUNT(*) FROM matches
+        WHERE finished_at >= ?1
+        GROUP BY guild_id, day",
+    )?;
+
+    let counts = statement
+        .query_map(params![since], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(counts)
+}
+
+/// Counts the distinct players of the matches finished since the timestamp by UTC day, numbered from the UNIX epoch.
+pub fn daily_players(since: i64) -> StorageResult<Vec<(i64, u64)>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(
+        "SELECT day, COUNT(DISTINCT player_id) FROM (
+            SELECT finished_at / 86400 AS day, starter_id AS player_id FROM matches WHERE finished_at >= ?1
+            UNION ALL
+            SELECT finished_at / 86400 AS day, opponent_id AS player_id FROM matches WHERE finished_at >= ?1
+        )
+        GROUP BY day",
+    )?;
+
+    let counts = statement
+        .query_map(params![since], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(counts)
+}
+
+/// Counts the distinct players of the matches finished from `since` until `until`, excluded.
+pub fn active_players(since: i64, until: i64) -> StorageResult<u64> {
+    let count = connection()?.query_row(
+        "SELECT COUNT(*) FROM (
+            SELECT starter_id FROM matches WHERE finished_at >= ?1 AND finished_at < ?2
+            UNION
+            SELECT opponent_id FROM matches WHERE finished_at >= ?1 AND finished_at < ?2
+        )",
+        params![since, until],
+        |row| row.get(0),
+    )?;
+
+    Ok(count)
+}
+
+/// Marks the result of the match as shared or not, returning whether it has changed.
+pub fn set_match_shared(id: u64, shared: bool) -> StorageResult<bool> {
+    let changed = connection()?.execute(
+        "UPDATE matches SET shared = ?2 WHERE id = ?1 AND shared != ?2",
+        params![id, shared],
+    )?;
+
+    Ok(changed > 0)
+}
+
+/// A game in progress stored by a process shutting down, for the next one to take over.
+#[derive(Clone, Debug)]
+pub struct HandedOffSession {
+    pub id: u64,
+    /// The players and the state of the game, as encoded in the footer of the game message.
+    pub snapshot: String,
+    pub guild_id: Option<u64>,
+    pub channel_id: u64,
+    pub message_id: Option<u64>,
+    pub token: Option<String>,
+    /// The UNIX timestamp the game has started at.
+    pub started_at: i64,
+    /// The moves of every round played so far, as `starter:opponent` pairs separated by commas.
+    pub moves: String,
+    /// The UNIX timestamp the current turn has started at; unknown for the sessions handed off by older versions.
+    pub turn_started_at: Option<i64>,
+}
+
+pub fn save_handed_off_sessions(sessions: &[HandedOffSession]) -> StorageResult<()> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    for session in sessions {
+        tx.execute(
+            "INSERT OR REPLACE INTO handed_off_sessions
+            (id, snapshot, guild_id, channel_id, message_id, token, started_at, moves, turn_started_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session.id,
+                session.snapshot,
+                session.guild_id,
+                session.channel_id,
+                session.message_id,
+                session.token,
+                session.started_at,
+                session.moves,
+                session.turn_started_at,
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Reads and deletes the sessions handed off by a previous process, so only one process takes them over.
+pub fn take_handed_off_sessions() -> StorageResult<Vec<HandedOffSession>> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    let sessions = tx
+        .prepare(
+            "SELECT id, snapshot, guild_id, channel_id, message_id, token, started_at, moves, turn_started_at
+            FROM handed_off_sessions",
+        )?
+        .query_map([], |row| Ok(HandedOffSession {
+            id: row.get(0)?,
+            snapshot: row.get(1)?,
+            guild_id: row.get(2)?,
+            channel_id: row.get(3)?,
+            message_id: row.get(4)?,
+            token: row.get(5)?,
+            started_at: row.get(6)?,
+            moves: row.get(7)?,
+            turn_started_at: row.get(8)?,
+        }))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    tx.execute("DELETE FROM handed_off_sessions", [])?;
+    tx.commit()?;
+
+    Ok(sessions)
+}
+
+/// The results of a user, either across the guilds sharing their statistics or within a single guild.
+#[derive(Clone, Debug, Default)]
+pub struct Record {
+    pub wins: u64,
+    pub losses: u64,
+    /// The number of consecutive wins (positive) or losses (negative) the latest matches end with.
+    pub streak: i64,
+}
+
+/// Matches the rows of either table that belong to the guild `?2`, or that count globally if it is `NULL`.
+const SCOPE: &str = "(?2 IS NULL AND global = 1 OR guild_id = ?2)";
+
+/// Leaves out the matches finished before the statistics of the player were last reset in the guild `?2`,
+/// which never applies to the global statistics.
+fn since_reset(user_id: &str) -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM stats_resets
+            WHERE stats_resets.guild_id = ?2 AND stats_resets.user_id = {} AND matches.finished_at <= stats_resets.reset_at)",
+        user_id,
+    )
+}
+
+/// The longest streak that is looked for, which spares reading the whole history of the most active players.
+const MAX_STREAK: usize = 100;
+
+/// Reads the record of the user, globally or within the guild, including the totals of the pruned matches.
+pub fn record(user_id: u64, guild_id: Option<u64>) -> StorageResult<Record> {
+    let conn = connection()?;
+
+    let (wins, losses): (u64, u64) = conn.query_row(
+        &format!(
+            "SELECT
+                (SELECT COUNT(*) FROM matches
+                    WHERE winner_id = ?1 AND {scope} AND {since_reset})
+                + (SELECT IFNULL(SUM(wins), 0) FROM match_totals WHERE user_id = ?1 AND {scope}),
+                (SELECT COUNT(*) FROM matches
+                    WHERE (starter_id = ?1 OR opponent_id = ?1) AND winner_id != ?1 AND {scope} AND {since_reset})
+                + (SELECT IFNULL(SUM(losses), 0) FROM match_totals WHERE user_id = ?1 AND {scope})",
+            scope = SCOPE,
+            since_reset = since_reset("?1"),
+        ),
+        params![user_id, guild_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT winner_id = ?1 FROM matches
+        WHERE (starter_id = ?1 OR opponent_id = ?1) AND {} AND {}
+        ORDER BY finished_at DESC LIMIT ?3",
+        SCOPE,
+        since_reset("?1"),
+    ))?;
+
+    let results = statement
+        .query_map(params![user_id, guild_id, MAX_STREAK], |row| row.get::<_, bool>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let streak = match results.first() {
+        Some(&latest) => {
+            let length = results.iter().take_while(|won| **won == latest).count() as i64;
+
+            if latest { length } else { -length }
+        },
+        None => 0,
+    };
+
+    Ok(Record { wins, losses, streak })
+}
+
+/// The IDs of the matches that have outlived the retention of their guild (`?2` days by default) at `?1`.
+const EXPIRED_MATCHES: &str = "SELECT matches.id FROM matches
+    LEFT JOIN guild_settings ON guild_settings.guild_id = matches.guild_id
+    WHERE matches.finished_at < ?1 - COALESCE(guild_settings.retention_days, ?2) * 86400";
+
+/// Deletes the matches that have outlived their retention, adding them to the totals of their players first
+/// (in guild 0 for direct messages), and returns how many there were; nothing is changed on a dry run.
+pub fn prune_matches(now: i64, default_days: Option<u64>, dry_run: bool) -> StorageResult<usize> {
+    let mut conn = connection()?;
+
+    if dry_run {
+        let count: usize = conn.query_row(
+            &format!("SELECT COUNT(*) FROM ({})", EXPIRED_MATCHES),
+            params![now, default_days],
+            |row| row.get(0),
+        )?;
+
+        return Ok(count);
+    }
+
+    let tx = conn.transaction()?;
+
+    // The results from before a reset of the statistics of their player are set aside with the reset instead,
+    // unless they predate an earlier reset which can no longer be undone
+    for (table, condition) in [
+        ("match_totals", "stats_resets.reset_at IS NULL OR results.finished_at > stats_resets.reset_at"),
+        (
+            "reset_match_totals",
+            "results.finished_at <= stats_resets.reset_at
+                AND (stats_resets.previous_reset_at IS NULL OR results.finished_at > stats_resets.previous_reset_at)",
+        ),
+    ] {
+        tx.execute(
+            &format!(
+                "INSERT INTO {table} (guild_id, user_id, global, wins, losses)
+                SELECT IFNULL(results.guild_id, 0), results.user_id, results.global, SUM(won), SUM(1 - won) FROM (
+                    SELECT guild_id, global, winner_id AS user_id, 1 AS won, finished_at FROM matches
+                    WHERE id IN ({expired})
+                    UNION ALL
+                    SELECT guild_id, global, CASE WHEN winner_id = starter_id THEN opponent_id ELSE starter_id END, 0,
+                        finished_at FROM matches
+                    WHERE id IN ({expired})
+                ) AS results
+                LEFT JOIN stats_resets
+                    ON stats_resets.guild_id = results.guild_id AND stats_resets.user_id = results.user_id
+                WHERE {condition}
+                GROUP BY 1, 2, 3
+                ON CONFLICT (guild_id, user_id, global) DO UPDATE SET
+                    wins = wins + excluded.wins,
+                    losses = losses + excluded.losses",
+                table = table,
+                expired = EXPIRED_MATCHES,
+                condition = condition,
+            ),
+            params![now, default_days],
+        )?;
+    }
+
+    tx.execute(
+        &format!("DELETE FROM rounds WHERE match_id IN ({})", EXPIRED_MATCHES),
+        params![now, default_days],
+    )?;
+
+    let count = tx.execute(
+        &format!("DELETE FROM matches WHERE id IN ({})", EXPIRED_MATCHES),
+        params![now, default_days],
+    )?;
+
+    tx.commit()?;
+
+    Ok(count)
+}
+
+/// Resets the statistics of the user in the guild: their matches until now stop counting for them, and the totals
+/// of their pruned matches are set aside, so the reset can be undone with [`restore_stats`] until it is purged.
+pub fn reset_stats(guild_id: u64, user_id: u64, reset_by: u64, now: i64, grace_seconds: i64) -> StorageResult<()> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    // Undoing a reset goes back to the latest one that can no longer be undone, if any
+    tx.execute(
+        "INSERT INTO stats_resets (guild_id, user_id, reset_at, reset_by) VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT (guild_id, user_id) DO UPDATE SET
+            previous_reset_at = IIF(reset_at < ?5, reset_at, previous_reset_at),
+            reset_at = excluded.reset_at,
+            reset_by = excluded.reset_by",
+        params![guild_id, user_id, now, reset_by, now - grace_seconds],
+    )?;
+
+    tx.execute(
+        "INSERT INTO reset_match_totals (guild_id, user_id, global, wins, losses)
+        SELECT guild_id, user_id, global, wins, losses FROM match_totals WHERE guild_id = ?1 AND user_id = ?2
+        ON CONFLICT (guild_id, user_id, global) DO UPDATE SET
+            wins = wins + excluded.wins,
+            losses = losses + excluded.losses",
+        params![guild_id, user_id],
+    )?;
+
+    tx.execute("DELETE FROM match_totals WHERE guild_id = ?1 AND user_id = ?2", params![guild_id, user_id])?;
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Undoes the resets of the statistics of the user in the guild that are still within their grace period,
+/// returning whether there was any.
+pub fn restore_stats(guild_id: u64, user_id: u64, now: i64, grace_seconds: i64) -> StorageResult<bool> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    let previous_reset_at = tx.query_row(
+        "SELECT previous_reset_at FROM stats_resets WHERE guild_id = ?1 AND user_id = ?2 AND reset_at >= ?3",
+        params![guild_id, user_id, now - grace_seconds],
+        |row| row.get::<_, Option<i64>>(0),
+    ).optional()?;
+
+    let previous_reset_at = match previous_reset_at {
+        Some(previous_reset_at) => previous_reset_at,
+        None => return Ok(false),
+    };
+
+    match previous_reset_at {
+        Some(previous_reset_at) => tx.execute(
+            "UPDATE stats_resets SET reset_at = ?3, previous_reset_at = NULL WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id, user_id, previous_reset_at],
+        )?,
+        None => tx.execute("DELETE FROM stats_resets WHERE guild_id = ?1 AND user_id = ?2", params![guild_id, user_id])?,
+    };
+
+    tx.execute(
+        "INSERT INTO match_totals (guild_id, user_id, global, wins, losses)
+        SELECT guild_id, user_id, global, wins, losses FROM reset_match_totals WHERE guild_id = ?1 AND user_id = ?2
+        ON CONFLICT (guild_id, user_id, global) DO UPDATE SET
+            wins = wins + excluded.wins,
+            losses = losses + excluded.losses",
+        params![guild_id, user_id],
+    )?;
+
+    tx.execute("DELETE FROM reset_match_totals WHERE guild_id = ?1 AND user_id = ?2", params![guild_id, user_id])?;
+
+    tx.commit()?;
+
+    Ok(true)
+}
+
+/// Drops the totals set aside by the resets made before the timestamp, which makes them permanent,
+/// and returns how many were dropped.
+pub fn purge_stats_resets(before: i64) -> StorageResult<usize> {
+    let count = connection()?.execute(
+        "DELETE FROM reset_match_totals WHERE EXISTS (
+            SELECT 1 FROM stats_resets
+            WHERE stats_resets.guild_id = reset_match_totals.guild_id
+                AND stats_resets.user_id = reset_match_totals.user_id
+                AND stats_resets.reset_at < ?1
+        )",
+        params![before],
+    )?;
+
+    Ok(count)
+}
+
+/// Stores the moves of the rounds of a recorded match, as pairs of the starter's and the opponent's move.
+pub fn save_rounds(match_id: u64, first_round: u32, moves: &[(&str, &str)]) -> StorageResult<()> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    for (round, (starter_move, opponent_move)) in (first_round..).zip(moves) {
+        tx.execute(
+            "INSERT OR REPLACE INTO rounds (match_id, round, starter_move, opponent_move) VALUES (?1, ?2, ?3, ?4)",
+            params![match_id, round, starter_move, opponent_move],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// A round of a recorded match.
+#[derive(Clone, Debug)]
+pub struct Round {
+    pub round: u32,
+    pub starter_move: String,
+    pub opponent_move: String,
+}
+
+/// Reads the recorded rounds of a match in order; the rounds played before its session was restored are missing,
+/// as are those of the matches that have been pruned.
+pub fn match_rounds(match_id: u64) -> StorageResult<Vec<Round>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(
+        "SELECT round, starter_move, opponent_move FROM rounds WHERE match_id = ?1 ORDER BY round",
+    )?;
+
+    let rounds = statement
+        .query_map(params![match_id], |row| {
+            Ok(Round { round: row.get(0)?, starter_move: row.get(1)?, opponent_move: row.get(2)? })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(rounds)
+}
+
+/// How often a move has been thrown, and how many of those rounds it has won.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MoveUsage {
+    pub thrown: u64,
+    pub won: u64,
+}
+
+/// Sums up the rounds of the user (or of everyone if `None`) by move, globally or within the guild.
+/// The rounds of the pruned matches are gone, as only their totals are kept.
+pub fn move_usage(user_id: Option<u64>, guild_id: Option<u64>) -> StorageResult<HashMap<String, MoveUsage>> {
+    let conn = connection()?;
+
+    let won = |own: &str, other: &str| {
+        format!(
+            "CASE WHEN {} || '>' || {} IN ('rock>scissors', 'paper>rock', 'scissors>paper') THEN 1 ELSE 0 END",
+            own,
+            other,
+        )
+    };
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT move, COUNT(*), SUM(won) FROM (
+            SELECT starter_move AS move, {starter_won} AS won FROM rounds JOIN matches ON matches.id = rounds.match_id
+            WHERE (?1 IS NULL OR starter_id = ?1) AND {scope}
+            UNION ALL
+            SELECT opponent_move, {opponent_won} FROM rounds JOIN matches ON matches.id = rounds.match_id
+            WHERE (?1 IS NULL OR opponent_id = ?1) AND {scope}
+        )
+        GROUP BY move",
+        starter_won = won("starter_move", "opponent_move"),
+        opponent_won = won("opponent_move", "starter_move"),
+        scope = SCOPE,
+    ))?;
+
+    let usage = statement
+        .query_map(params![user_id, guild_id], |row| {
+            Ok((row.get(0)?, MoveUsage { thrown: row.get(1)?, won: row.get(2)? }))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(usage)
+}
+
+/// How often the user has thrown each move in the first round of their matches that count toward the global
+/// statistics or have been played in the guild, as far as the rounds have been recorded.
+pub fn opening_moves(user_id: u64, guild_id: Option<u64>) -> StorageResult<HashMap<String, u64>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(
+        "SELECT move, COUNT(*) FROM (
+            SELECT IIF(starter_id = ?1, starter_move, opponent_move) AS move
+            FROM rounds JOIN matches ON matches.id = rounds.match_id
+            WHERE round = 1 AND (starter_id = ?1 OR opponent_id = ?1) AND (global = 1 OR guild_id = ?2)
+        )
+        GROUP BY move",
+    )?;
+
+    let openings = statement
+        .query_map(params![user_id, guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(openings)
+}
+
+/// The matches two users have played against each other, from the point of view of the first one.
+#[derive(Clone, Debug, Default)]
+pub struct HeadToHead {
+    /// Whether the first user has won each match, from the latest one.
+    pub results: Vec<bool>,
+    /// The rounds of those matches that have ended in a draw, as far as they have been recorded.
+    pub drawn_rounds: u64,
+}
+
+/// Reads the matches between the users that count toward the global statistics or have been played in the guild.
+/// Only the matches that have not been pruned yet are known, as the totals are not kept per pair.
+pub fn head_to_head(user_id: u64, opponent_id: u64, guild_id: Option<u64>) -> StorageResult<HeadToHead> {
+    const PAIR: &str = "(starter_id = ?1 AND opponent_id = ?2 OR starter_id = ?2 AND opponent_id = ?1)
+        AND (global = 1 OR guild_id = ?3)";
+
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT winner_id = ?1 FROM matches WHERE {} ORDER BY finished_at DESC",
+        PAIR,
+    ))?;
+
+    let results = statement
+        .query_map(params![user_id, opponent_id, guild_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    let drawn_rounds = conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM rounds JOIN matches ON matches.id = rounds.match_id
+            WHERE starter_move = opponent_move AND {}",
+            PAIR,
+        ),
+        params![user_id, opponent_id, guild_id],
+        |row| row.get(0),
+    )?;
+
+    Ok(HeadToHead { results, drawn_rounds })
+}
+
+/// The results of a player over the period of a leaderboard.
+#[derive(Clone, Debug)]
+pub struct Standing {
+    pub user_id: u64,
+    pub wins: u64,
+    pub losses: u64,
+}
+
+/// Ranks the players by their wins, then by their fewest losses, in the matches finished since the timestamp,
+/// globally or within the guild; the totals of the pruned matches only count when there is no timestamp.
+pub fn leaderboard(guild_id: Option<u64>, since: Option<i64>, limit: usize) -> StorageResult<Vec<Standing>> {
+    let conn = connection()?;
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT user_id, SUM(wins), SUM(losses) FROM (
+            SELECT winner_id AS user_id, 1 AS wins, 0 AS losses FROM matches
+                WHERE finished_at >= ?1 AND {scope} AND {winner_since_reset}
+            UNION ALL
+            SELECT IIF(winner_id = starter_id, opponent_id, starter_id), 0, 1 FROM matches
+                WHERE finished_at >= ?1 AND {scope} AND {loser_since_reset}
+            UNION ALL
+            SELECT user_id, wins, losses FROM match_totals
+                WHERE ?4 AND {scope}
+        )
+        GROUP BY user_id
+        ORDER BY SUM(wins) DESC, SUM(losses), user_id
+        LIMIT ?3",
+        scope = SCOPE,
+        winner_since_reset = since_reset("winner_id"),
+        loser_since_reset = since_reset("IIF(winner_id = starter_id, opponent_id, starter_id)"),
+    ))?;
+
+    let standings = statement
+        .query_map(params![since.unwrap_or(i64::MIN), guild_id, limit, since.is_none()], |row| Ok(Standing {
+            user_id: row.get(0)?,
+            wins: row.get(1)?,
+            losses: row.get(2)?,
+        }))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(standings)
+}
+
+/// The bests of the players, globally or within a single guild.
+#[derive(Clone, Debug, Default)]
+pub struct Records {
+    /// The player with the longest run of consecutive wins, and its length.
+    pub longest_streak: Option<(u64, u64)>,
+    /// The player who has played the most matches, and how many.
+    pub most_matches: Option<(u64, u64)>,
+    /// The match that has taken the most rounds to be decided, the earliest one on a tie.
+    pub longest_match: Option<Match>,
+}
+
+/// Reads the records, globally or within the guild. The streaks are only looked for in the matches
+/// that have not been pruned yet, while the totals of the pruned ones count toward the number of matches.
+pub fn records(guild_id: Option<u64>) -> StorageResult<Records> {
+    // The guild is the only parameter of these queries
+    let scope = SCOPE.replace("?2", "?1");
+
+    let conn = connection()?;
+
+    // Every result of a player is numbered among all of theirs and among those with the same outcome:
+    // the difference of both stays the same along a streak, which identifies it
+    let longest_streak = conn
+        .query_row(
+            &format!(
+                "WITH results AS (
+                    SELECT id, finished_at, starter_id AS user_id, winner_id = starter_id AS won FROM matches
+                        WHERE {scope}
+                    UNION ALL
+                    SELECT id, finished_at, opponent_id, winner_id = opponent_id FROM matches
+                        WHERE {scope}
+                ),
+                streaks AS (
+                    SELECT user_id, won,
+                        ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY finished_at, id)
+                        - ROW_NUMBER() OVER (PARTITION BY user_id, won ORDER BY finished_at, id) AS streak
+                    FROM results
+                )
+                SELECT user_id, COUNT(*) FROM streaks WHERE won
+                GROUP BY user_id, streak
+                ORDER BY COUNT(*) DESC, user_id
+                LIMIT 1",
+                scope = scope,
+            ),
+            params![guild_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let most_matches = conn
+        .query_row(
+            &format!(
+                "SELECT user_id, SUM(matches) FROM (
+                    SELECT starter_id AS user_id, 1 AS matches FROM matches WHERE {scope}
+                    UNION ALL
+                    SELECT opponent_id, 1 FROM matches WHERE {scope}
+                    UNION ALL
+                    SELECT user_id, wins + losses FROM match_totals WHERE {scope}
+                )
+                GROUP BY user_id
+                ORDER BY SUM(matches) DESC, user_id
+                LIMIT 1",
+                scope = scope,
+            ),
+            params![guild_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let longest_match = conn
+        .query_row(
+            &format!(
+                "SELECT id, guild_id, starter_id, opponent_id, winner_id, rounds, finished_at, shared
+                FROM matches WHERE {}
+                ORDER BY rounds DESC, finished_at
+                LIMIT 1",
+                scope,
+            ),
+            params![guild_id],
+            match_from_row,
+        )
+        .optional()?;
+
+    Ok(Records { longest_streak, most_matches, longest_match })
+}
+
+/// What has happened in a guild over a period, as its weekly digest tells.
+#[derive(Clone, Debug, Default)]
+pub struct Digest {
+    pub matches: u64,
+    /// The players who have played the most matches, with how many, the most active first.
+    pub most_active: Vec<(u64, u64)>,
+    /// The player with the longest run of consecutive wins within the period, and its length.
+    pub longest_streak: Option<(u64, u64)>,
+    /// The unlikeliest win of the period: the one over the opponent who had the most wins more than the winner
+    /// before the period, with the wins of the winner and of the loser then. There are no ratings to tell
+    /// an upset by, so the wins are all this goes by.
+    pub unlikeliest_win: Option<(Match, u64, u64)>,
+}
+
+/// Reads the digest of the matches of the guild finished from `since` until `until`, excluded.
+pub fn digest(guild_id: u64, since: i64, until: i64, most_active: usize) -> StorageResult<Digest> {
+    let conn = connection()?;
+
+    let period = "guild_id = ?1 AND finished_at >= ?2 AND finished_at < ?3";
+
+    let matches = conn.query_row(
+        &format!("SELECT COUNT(*) FROM matches WHERE {}", period),
+        params![guild_id, since, until],
+        |row| row.get(0),
+    )?;
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT user_id, COUNT(*) FROM (
+            SELECT starter_id AS user_id FROM matches WHERE {period}
+            UNION ALL
+            SELECT opponent_id FROM matches WHERE {period}
+        )
+        GROUP BY user_id
+        ORDER BY COUNT(*) DESC, user_id
+        LIMIT ?4",
+        period = period,
+    ))?;
+
+    let most_active = statement
+        .query_map(params![guild_id, since, until, most_active], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    // Numbered like the streaks of the records, within the period only
+    let longest_streak = conn
+        .query_row(
+            &format!(
+                "WITH results AS (
+                    SELECT id, finished_at, starter_id AS user_id, winner_id = starter_id AS won FROM matches
+                        WHERE {period}
+                    UNION ALL
+                    SELECT id, finished_at, opponent_id, winner_id = opponent_id FROM matches
+                        WHERE {period}
+                ),
+                streaks AS (
+                    SELECT user_id, won,
+                        ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY finished_at, id)
+                        - ROW_NUMBER() OVER (PARTITION BY user_id, won ORDER BY finished_at, id) AS streak
+                    FROM results
+                )
+                SELECT user_id, COUNT(*) FROM streaks WHERE won
+                GROUP BY user_id, streak
+                ORDER BY COUNT(*) DESC, user_id
+                LIMIT 1",
+                period = period,
+            ),
+            params![guild_id, since, until],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    // The wins before the period include the totals of the pruned matches
+    let unlikeliest_win = conn
+        .query_row(
+            "WITH wins AS (
+                SELECT user_id, SUM(wins) AS wins FROM (
+                    SELECT winner_id AS user_id, 1 AS wins FROM matches WHERE guild_id = ?1 AND finished_at < ?2
+                    UNION ALL
+                    SELECT user_id, wins FROM match_totals WHERE guild_id = ?1
+                )
+                GROUP BY user_id
+            )
+            SELECT matches.id, matches.guild_id, starter_id, opponent_id, winner_id, rounds, finished_at, shared,
+                COALESCE(winner.wins, 0), COALESCE(loser.wins, 0)
+            FROM matches
+            LEFT JOIN wins AS winner ON winner.user_id = winner_id
+            LEFT JOIN wins AS loser ON loser.user_id = CASE WHEN winner_id = starter_id THEN opponent_id ELSE starter_id END
+            WHERE matches.guild_id = ?1 AND finished_at >= ?2 AND finished_at < ?3
+                AND COALESCE(loser.wins, 0) > COALESCE(winner.wins, 0)
+            ORDER BY COALESCE(loser.wins, 0) - COALESCE(winner.wins, 0) DESC, finished_at
+            LIMIT 1",
+            params![guild_id, since, until],
+            |row| Ok((match_from_row(row)?, row.get(8)?, row.get(9)?)),
+        )
+        .optional()?;
+
+    Ok(Digest { matches, most_active, longest_streak, unlikeliest_win })
+}
+
+/// The players of the ladder of the guild with their positions, from the top (1) down.
+pub fn ladder(guild_id: u64) -> StorageResult<Vec<(u64, u32)>> {
+    let conn = connection()?;
+    let mut statement = conn.prepare("SELECT user_id, position FROM ladder WHERE guild_id = ?1 ORDER BY position")?;
+
+    let players = statement
+        .query_map(params![guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(players)
+}
+
+pub fn ladder_position(guild_id: u64, user_id: u64) -> StorageResult<Option<u32>> {
+    let position = connection()?
+        .query_row(
+            "SELECT position FROM ladder WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id, user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(position)
+}
+
+/// Puts the user at the bottom of the ladder, returning their position, or `None` if they are already on it.
+pub fn join_ladder(guild_id: u64, user_id: u64) -> StorageResult<Option<u32>> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    let joined = tx.execute(
+        "INSERT OR IGNORE INTO ladder (guild_id, user_id, position)
+        SELECT ?1, ?2, IFNULL(MAX(position), 0) + 1 FROM ladder WHERE guild_id = ?1",
+        params![guild_id, user_id],
+    )?;
+
+    let position = if joined > 0 {
+        tx.query_row(
+            "SELECT position FROM ladder WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id, user_id],
+            |row| row.get(0),
+        ).optional()?
+    } else {
+        None
+    };
+
+    tx.commit()?;
+
+    Ok(position)
+}
+
+/// Takes the user off the ladder, moving everyone below them up by one, and returns whether they were on it.
+pub fn leave_ladder(guild_id: u64, user_id: u64) -> StorageResult<bool> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    let position: Option<u32> = tx
+        .query_row(
+            "SELECT position FROM ladder WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id, user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if let Some(position) = position {
+        tx.execute("DELETE FROM ladder WHERE guild_id = ?1 AND user_id = ?2", params![guild_id, user_id])?;
+
+        // Moved through negative positions first, as the unique index is checked row by row
+        tx.execute(
+            "UPDATE ladder SET position = -(position - 1) WHERE guild_id = ?1 AND position > ?2",
+            params![guild_id, position],
+        )?;
+        tx.execute("UPDATE ladder SET position = -position WHERE guild_id = ?1 AND position < 0", params![guild_id])?;
+    }
+
+    tx.commit()?;
+
+    Ok(position.is_some())
+}
+
+/// Swaps the positions of the winner and the loser if the winner was below, returning whether they have been swapped.
+pub fn climb_ladder(guild_id: u64, winner_id: u64, loser_id: u64) -> StorageResult<bool> {
+    let mut conn = connection()?;
+    let tx = conn.transaction()?;
+
+    let position = |user_id: u64| -> rusqlite::Result<Option<u32>> {
+        tx.query_row(
+            "SELECT position FROM ladder WHERE guild_id = ?1 AND user_id = ?2",
+            params![guild_id, user_id],
+            |row| row.get(0),
+        ).optional()
+    };
+
+    let swapped = match (position(winner_id)?, position(loser_id)?) {
+        (Some(winner), Some(loser)) if winner > loser => {
+            tx.execute("UPDATE ladder SET position = 0 WHERE guild_id = ?1 AND user_id = ?2", params![guild_id, loser_id])?;
+            tx.execute(
+                "UPDATE ladder SET position = ?3 WHERE guild_id = ?1 AND user_id = ?2",
+                params![guild_id, winner_id, loser],
+            )?;
+            tx.execute(
+                "UPDATE ladder SET position = ?3 WHERE guild_id = ?1 AND user_id = ?2",
+                params![guild_id, loser_id, winner],
+            )?;
+
+            true
+        },
+        _ => false,
+    };
+
+    tx.commit()?;
+
+    Ok(swapped)
+}
+
+/// The votes of a user on top.gg.
+#[cfg(feature = "topgg")]
+#[derive(Clone, Debug, Default)]
+pub struct Votes {
+    pub count: u64,
+    /// The UNIX timestamp of the latest vote.
+    pub latest: Option<i64>,
+}
+
+#[cfg(feature = "topgg")]
+pub fn save_vote(user_id: u64, voted_at: i64) -> StorageResult<()> {
+    connection()?.execute("INSERT INTO votes (user_id, voted_at) VALUES (?1, ?2)", params![user_id, voted_at])?;
+
+    Ok(())
+}
+
+#[cfg(feature = "topgg")]
+pub fn votes(user_id: u64) -> StorageResult<Votes> {
+    let votes = connection()?.query_row(
+        "SELECT COUNT(*), MAX(voted_at) FROM votes WHERE user_id = ?1",
+        params![user_id],
+        |row| Ok(Votes { count: row.get(0)?, latest: row.get(1)? }),
+    )?;
+
+    Ok(votes)
+}
@@ -0,0 +1,266 @@
+//! The stream mode of the guilds running community events: the moves are revealed after a pause,
+//! the draws are narrated, and every channel hosting games keeps a pinned message with the live score.
+//! The scores only live in memory, so they start over whenever the bot restarts or the mode is toggled.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::model::user::User;
+use serenity::prelude::Mentionable;
+
+use tracing::{error, warn};
+
+use crate::i18n::tr;
+use crate::sessions::Session;
+use crate::storage::GuildSettings;
+use crate::theme::Palette;
+
+/// How long the moves stay hidden once both players have chosen, so the audience can follow along.
+pub const REVEAL_DELAY: Duration = Duration::from_secs(3);
+
+/// The number of `stream.flavor.<n>` lines the draws are narrated with.
+const FLAVOR_LINES: usize = 5;
+
+/// How many players the live score lists, from the one with the most wins.
+const LISTED_PLAYERS: usize = 10;
+
+lazy_static! {
+    static ref SCOREBOARDS: Mutex<HashMap<ChannelId, Scoreboard>> = Mutex::new(HashMap::new());
+}
+
+/// The live score of a channel, with the message showing it once it has been posted.
+#[derive(Default)]
+struct Scoreboard {
+    guild_id: Option<GuildId>,
+    message_id: Option<MessageId>,
+    /// Whether the message has been pinned, which the bot may only do with the Manage Messages permission.
+    pinned: bool,
+    /// Whether a win is posting the message, which the other ones leave to it rather than posting their own.
+    posting: bool,
+    wins: HashMap<UserId, u32>,
+}
+
+pub fn enabled(settings: &GuildSettings) -> bool {
+    settings.stream_mode.unwrap_or(false)
+}
+
+/// Posts a line of flavor text after a draw, picked from the session and the round so that it varies between games.
+pub async fn narrate(ctx: &Context, session: &Session) {
+    let palette = Palette::for_guild(session.guild_id);
+    let locale = session.locale.as_str();
+
+    let line = (session.id as usize + session.round) % FLAVOR_LINES;
+
+    let description = tr!(
+        locale,
+        &format!("stream.flavor.{}", line),
+        starter = session.starter.mention(),
+        opponent = session.opponent.mention(),
+        round = session.round,
+    );
+
+    let _ = session.channel_id.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| {
+            embed
+                .color(palette.confirmation)
+                .description(description)
+        })
+    }).await;
+}
+
+/// Counts the win in the live score of the channel, then updates its message or posts a new one, which is pinned
+/// if the permissions of the bot that Discord has sent with the press let it manage the messages of the channel.
+pub async fn record_win(ctx: &Context, session: &Session, winner: &User, permissions: Option<Permissions>) {
+    let channel_id = session.channel_id;
+
+    let (message_id, pinned, text) = {
+        let mut scoreboards = SCOREBOARDS.lock().unwrap();
+        let scoreboard = scoreboards.entry(channel_id).or_default();
+
+        scoreboard.guild_id = session.guild_id;
+        *scoreboard.wins.entry(winner.id).or_default() += 1;
+
+        // The message being posted shows this win too once it is
+        if scoreboard.posting {
+            return;
+        }
+
+        scoreboard.posting = scoreboard.message_id.is_none();
+
+        (scoreboard.message_id, scoreboard.pinned, render(&scoreboard.wins, session.locale.as_str()))
+    };
+
+    let can_pin = permissions.is_some_and(|permissions| permissions.manage_messages());
+    let palette = Palette::for_guild(session.guild_id);
+    let locale = session.locale.as_str();
+
+    if let Some(message_id) = message_id {
+        if show(ctx, channel_id, message_id, &palette, locale, &text).await.is_ok() {
+            if can_pin && !pinned && pin(ctx, channel_id, message_id).await {
+                if let Some(scoreboard) = SCOREBOARDS.lock().unwrap().get_mut(&channel_id) {
+                    scoreboard.pinned = true;
+                }
+            }
+
+            return;
+        }
+
+        // Posted again as it has been deleted since, unless another win is posting it already
+        let claimed = match SCOREBOARDS.lock().unwrap().get_mut(&channel_id) {
+            Some(scoreboard) if !scoreboard.posting => {
+                scoreboard.message_id = None;
+                scoreboard.pinned = false;
+                scoreboard.posting = true;
+
+                true
+            },
+            _ => false,
+        };
+
+        if !claimed {
+            return;
+        }
+    }
+
+    post(ctx, session, &palette, can_pin).await;
+}
+
+/// Posts the live score of the channel, for the win that has claimed it, then shows the wins counted meanwhile.
+async fn post(ctx: &Context, session: &Session, palette: &Palette, can_pin: bool) {
+    let channel_id = session.channel_id;
+    let locale = session.locale.as_str();
+
+    let text = match SCOREBOARDS.lock().unwrap().get(&channel_id) {
+        Some(scoreboard) => render(&scoreboard.wins, locale),
+        None => return,
+    };
+
+    let posted = channel_id.send_message(&ctx.http, |msg| {
+        msg.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.live_score")))
+                .color(palette.success)
+                .description(&text)
+        })
+    }).await;
+
+    let message_id = match posted {
+        Ok(message) => Some(message.id),
+        Err(err) => {
+            error!("The live score could not have been posted: {:?}", err);
+
+            None
+        },
+    };
+
+    let pinned = match message_id {
+        Some(message_id) if can_pin => pin(ctx, channel_id, message_id).await,
+        _ => false,
+    };
+
+    let latest = SCOREBOARDS.lock().unwrap().get_mut(&channel_id).map(|scoreboard| {
+        scoreboard.message_id = message_id;
+        scoreboard.pinned = pinned;
+        scoreboard.posting = false;
+
+        render(&scoreboard.wins, locale)
+    });
+
+    let message_id = match message_id {
+        Some(message_id) => message_id,
+        None => return,
+    };
+
+    match latest {
+        Some(latest) if latest != text => {
+            let _ = show(ctx, channel_id, message_id, palette, locale, &latest).await;
+        },
+        Some(_) => {},
+        // The stream mode has been turned off while it was being posted
+        None if pinned => {
+            let _ = channel_id.unpin(&ctx.http, message_id).await;
+        },
+        None => {},
+    }
+}
+
+/// Edits the message of the live score to show the text.
+async fn show(
+    ctx: &Context,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    palette: &Palette,
+    locale: &str,
+    text: &str,
+) -> serenity::Result<Message> {
+    channel_id.edit_message(&ctx.http, message_id, |msg| {
+        msg.embed(|embed| {
+            embed
+                .author(|a| a.name(tr!(locale, "embeds.live_score")))
+                .color(palette.success)
+                .description(text)
+        })
+    }).await
+}
+
+/// Pins the message of the live score, returning whether it has been.
+async fn pin(ctx: &Context, channel_id: ChannelId, message_id: MessageId) -> bool {
+    match channel_id.pin(&ctx.http, message_id).await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!("The live score could not have been pinned: {:?}", err);
+
+            false
+        },
+    }
+}
+
+/// Unpins the live scores of the guild and forgets them, once its stream mode has been turned off.
+pub async fn stop(ctx: &Context, guild_id: GuildId) {
+    let messages = {
+        let mut scoreboards = SCOREBOARDS.lock().unwrap();
+        let mut messages = Vec::new();
+
+        scoreboards.retain(|channel_id, scoreboard| {
+            if scoreboard.guild_id != Some(guild_id) {
+                return true;
+            }
+
+            // Only the pinned ones, as the bot may not be allowed to unpin anything otherwise
+            if scoreboard.pinned {
+                messages.extend(scoreboard.message_id.map(|message_id| (*channel_id, message_id)));
+            }
+
+            false
+        });
+
+        messages
+    };
+
+    for (channel_id, message_id) in messages {
+        let _ = channel_id.unpin(&ctx.http, message_id).await;
+    }
+}
+
+fn render(wins: &HashMap<UserId, u32>, locale: &str) -> String {
+    let mut standings = wins.iter().collect::<Vec<_>>();
+
+    // Ties are listed by ID, so the order does not change between updates
+    standings.sort_by(|(a_id, a_wins), (b_id, b_wins)| b_wins.cmp(a_wins).then(a_id.cmp(b_id)));
+
+    standings.into_iter()
+        .take(LISTED_PLAYERS)
+        .enumerate()
+        .map(|(index, (user_id, wins))| {
+            tr!(locale, "stream.score_entry", position = index + 1, user = user_id.mention(), wins = wins)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,72 @@
+//! Ends the sessions that should not be in progress anymore: the ones older than `max_session_minutes`, which
+//! their timeout has failed to end, and the ones whose game message has been deleted without the bot noticing
+//! (e.g. while it was disconnected). A safety net, so a leaked session cannot keep its players from playing.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use serenity::client::Context;
+use serenity::model::Timestamp;
+use serenity::Error as SerenityError;
+
+use tracing::{info, warn};
+
+use crate::commands::rps;
+use crate::config;
+use crate::metrics;
+use crate::sessions::{self, Session};
+use crate::spectators;
+
+/// How often the sessions are swept; the games themselves expire on time, so there is no need to be precise.
+const INTERVAL: Duration = Duration::from_secs(60 * 5);
+
+/// `ready` is dispatched again on every reconnect, but only one sweeper must run.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+pub fn start(ctx: &Context) {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run(ctx.clone()));
+    }
+}
+
+async fn run(ctx: Context) {
+    let mut interval = tokio::time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let max_age = config::get().max_session_minutes as i64 * 60;
+        let now = Timestamp::now().unix_timestamp();
+
+        for session in sessions::all() {
+            if now - session.started_at.unix_timestamp() > max_age {
+                if rps::end_stale(&ctx, session.id).await {
+                    warn!(session = session.id, "A game has outlasted the maximum duration of a session");
+                }
+            } else if !message_exists(&ctx, &session).await && sessions::end(session.id).is_some() {
+                metrics::SESSIONS_ENDED.with_label_values(&[metrics::RPS, "deleted"]).inc();
+                info!(session = session.id, "The game has ended: its message no longer exists");
+
+                rps::start_queued(&ctx, session.channel_id);
+            }
+        }
+
+        spectators::forget_ended();
+    }
+}
+
+/// Whether the game message is still there; only a definite answer from Discord counts as deleted,
+/// so a game is not ended over a network error.
+async fn message_exists(ctx: &Context, session: &Session) -> bool {
+    let message_id = match session.message_id {
+        Some(message_id) => message_id,
+        None => return true,
+    };
+
+    match session.channel_id.message(&ctx.http, message_id).await {
+        Err(SerenityError::Http(err)) => err.status_code() != Some(StatusCode::NOT_FOUND),
+        _ => true,
+    }
+}
@@ -0,0 +1,87 @@
+//! Tells systemd how the bot is doing when it runs as a `Type=notify` service, and keeps its watchdog fed
+//! if `WatchdogSec=` is set, so a hung process gets restarted. Does nothing outside of systemd.
+
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+/// `ready` is dispatched again on every reconnect, but systemd only needs to hear it once.
+static READY_SENT: AtomicBool = AtomicBool::new(false);
+
+/// Reports that the bot has connected and registered its commands, which ends the startup of the unit.
+pub fn ready() {
+    if !READY_SENT.swap(true, Ordering::SeqCst) {
+        notify("READY=1\nSTATUS=Connected to Discord");
+    }
+}
+
+/// Reports that the bot is shutting down, so systemd does not mistake the handoff for a hang.
+pub fn stopping() {
+    notify("STOPPING=1");
+}
+
+/// Pings the watchdog at half of its interval from a task of the runtime, so the pings stop once the runtime stalls.
+pub fn start_watchdog() {
+    let interval = match watchdog_interval() {
+        Some(interval) => interval,
+        None => return,
+    };
+
+    info!("Feeding the systemd watchdog every {:?}", interval / 2);
+
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval / 2);
+
+        loop {
+            ticks.tick().await;
+
+            notify("WATCHDOG=1");
+        }
+    });
+}
+
+/// The interval systemd expects the pings within, if the watchdog is enabled for this very process.
+fn watchdog_interval() -> Option<Duration> {
+    let usec = env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+
+    // The variables are inherited by child processes, which must not feed the watchdog in the name of the bot
+    if let Ok(pid) = env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+#[cfg(unix)]
+fn notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+
+    let path = match env::var("NOTIFY_SOCKET") {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        match path.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                use std::os::unix::net::SocketAddr;
+
+                socket.send_to_addr(state.as_bytes(), &SocketAddr::from_abstract_name(name)?)
+            },
+            _ => socket.send_to(state.as_bytes(), &path),
+        }
+    });
+
+    if let Err(err) = result {
+        warn!("systemd could not have been notified: {:?}", err);
+    }
+}
+
+#[cfg(not(unix))]
+fn notify(_state: &str) {}
@@ -0,0 +1,46 @@
+use opentelemetry::sdk::trace::{self, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::KeyValue;
+
+use opentelemetry_otlp::WithExportConfig;
+
+use tracing::Subscriber;
+
+use tracing_opentelemetry::OpenTelemetryLayer;
+
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::OtlpConfig;
+
+/// Builds the layer exporting the spans to the configured collector in batches,
+/// or `None` if there is no endpoint (or the exporter cannot be set up).
+pub fn layer<S>(config: &OtlpConfig) -> Option<OpenTelemetryLayer<S, Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let endpoint = config.endpoint.as_ref()?;
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+        .with_trace_config(
+            trace::config()
+                .with_resource(Resource::new([KeyValue::new("service.name", config.service_name.clone())]))
+        )
+        .install_batch(opentelemetry::runtime::Tokio);
+
+    match tracer {
+        Ok(tracer) => Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+        Err(err) => {
+            // The subscriber is not set up yet, so this cannot be logged
+            eprintln!("The OTLP exporter could not have been set up: {:?}", err);
+
+            None
+        },
+    }
+}
+
+/// Exports the spans that are still queued.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use serenity::model::channel::ReactionType;
+use serenity::model::id::{GuildId, UserId};
+use serenity::utils::Color;
+
+use tracing::error;
+
+use crate::storage::{self, GuildSettings};
+
+const SUCCESS_COLOR: Color = Color::from_rgb(140, 190, 218);
+const FAILURE_COLOR: Color = Color::from_rgb(239, 67, 63);
+const CONFIRMATION_COLOR: Color = Color::from_rgb(118, 255, 3);
+const WARNING_COLOR: Color = Color::from_rgb(255, 242, 54);
+
+const ROCK: char = '\u{270A}';
+const PAPER: char = '\u{270B}';
+const SCISSORS: char = '\u{270C}';
+
+/// How long the settings of a guild are reused once read. They are forgotten as soon as they are saved,
+/// so this only bounds how long a change made otherwise (e.g. a restored backup) takes to show.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<u64, (Instant, GuildSettings)>> = Mutex::new(HashMap::new());
+}
+
+/// Everything a game message needs to be rendered in the style of its guild.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub palette: Palette,
+    pub emojis: MoveEmojis,
+    pub button_labels: ButtonLabels,
+    pub layout: Layout,
+}
+
+impl Theme {
+    pub fn for_guild(guild_id: Option<GuildId>) -> Self {
+        Self::from(&settings(guild_id))
+    }
+
+    /// The theme of a game in the guild with the settings, whose layout is the one its starter has chosen if any.
+    pub fn for_game(settings: &GuildSettings, starter_id: UserId) -> Self {
+        let mut theme = Self::from(settings);
+
+        let layout = storage::user_preferences(starter_id.0).ok()
+            .and_then(|preferences| preferences.layout)
+            .and_then(|layout| Layout::from_setting(&layout));
+
+        if let Some(layout) = layout {
+            theme.layout = layout;
+        }
+
+        theme
+    }
+}
+
+impl From<&GuildSettings> for Theme {
+    fn from(settings: &GuildSettings) -> Self {
+        Self {
+            palette: Palette::from(settings),
+            emojis: MoveEmojis::from(settings),
+            button_labels: ButtonLabels::from(settings),
+            layout: Layout::from(settings),
+        }
+    }
+}
+
+/// The set of embed colors used for a single response, with per-guild overrides applied.
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    pub success: Color,
+    pub failure: Color,
+    pub confirmation: Color,
+    pub warning: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            success: SUCCESS_COLOR,
+            failure: FAILURE_COLOR,
+            confirmation: CONFIRMATION_COLOR,
+            warning: WARNING_COLOR,
+        }
+    }
+}
+
+impl From<&GuildSettings> for Palette {
+    fn from(settings: &GuildSettings) -> Self {
+        let default = Self::default();
+
+        Self {
+            success: settings.success_color.map_or(default.success, Color::new),
+            failure: settings.failure_color.map_or(default.failure, Color::new),
+            confirmation: settings.confirmation_color.map_or(default.confirmation, Color::new),
+            warning: settings.warning_color.map_or(default.warning, Color::new),
+        }
+    }
+}
+
+impl Palette {
+    pub fn for_guild(guild_id: Option<GuildId>) -> Self {
+        Self::from(&settings(guild_id))
+    }
+}
+
+/// The emojis shown on the move buttons and in the result fields.
+#[derive(Clone, Debug)]
+pub struct MoveEmojis {
+    pub rock: ReactionType,
+    pub paper: ReactionType,
+    pub scissors: ReactionType,
+}
+
+impl Default for MoveEmojis {
+    fn default() -> Self {
+        Self {
+            rock: ROCK.into(),
+            paper: PAPER.into(),
+            scissors: SCISSORS.into(),
+        }
+    }
+}
+
+impl From<&GuildSettings> for MoveEmojis {
+    fn from(settings: &GuildSettings) -> Self {
+        let default = Self::default();
+
+        let parse = |emoji: &Option<String>, default: ReactionType| {
+            emoji.as_deref()
+                .and_then(|emoji| ReactionType::try_from(emoji).ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            rock: parse(&settings.rock_emoji, default.rock),
+            paper: parse(&settings.paper_emoji, default.paper),
+            scissors: parse(&settings.scissors_emoji, default.scissors),
+        }
+    }
+}
+
+/// How the move buttons are labeled; text labels make the game usable with screen readers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonLabels {
+    #[default]
+    Emoji,
+    Both,
+    Text,
+}
+
+impl ButtonLabels {
+    pub const ALL: [Self; 3] = [Self::Emoji, Self::Both, Self::Text];
+
+    pub fn from_setting(setting: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|labels| labels.as_setting() == setting)
+    }
+
+    pub fn as_setting(self) -> &'static str {
+        match self {
+            Self::Emoji => "emoji",
+            Self::Both => "both",
+            Self::Text => "text",
+        }
+    }
+
+    pub fn shows_emoji(self) -> bool {
+        self != Self::Text
+    }
+
+    pub fn shows_text(self) -> bool {
+        self != Self::Emoji
+    }
+}
+
+impl From<&GuildSettings> for ButtonLabels {
+    fn from(settings: &GuildSettings) -> Self {
+        settings.button_labels.as_deref()
+            .and_then(Self::from_setting)
+            .unwrap_or_default()
+    }
+}
+
+/// How much the game messages show: everything, or only a short line that fits on a phone screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    Full,
+    Compact,
+}
+
+impl Layout {
+    pub const ALL: [Self; 2] = [Self::Full, Self::Compact];
+
+    pub fn from_setting(setting: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.as_setting() == setting)
+    }
+
+    pub fn as_setting(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Compact => "compact",
+        }
+    }
+
+    pub fn is_compact(self) -> bool {
+        self == Self::Compact
+    }
+}
+
+impl From<&GuildSettings> for Layout {
+    fn from(settings: &GuildSettings) -> Self {
+        settings.layout.as_deref()
+            .and_then(Self::from_setting)
+            .unwrap_or_default()
+    }
+}
+
+/// The settings of the guild, read again only once the cached ones are outdated; the defaults in DMs, for the guilds
+/// that have not changed anything, or if the settings cannot be read. Only the commands saving the settings read
+/// them from the database directly, as they must not save outdated ones back.
+pub fn settings(guild_id: Option<GuildId>) -> GuildSettings {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id.0,
+        None => return GuildSettings::default(),
+    };
+
+    if let Some((read_at, settings)) = CACHE.lock().unwrap().get(&guild_id) {
+        if read_at.elapsed() < CACHE_TTL {
+            return settings.clone();
+        }
+    }
+
+    match storage::guild_settings(guild_id) {
+        Ok(settings) => {
+            let mut cache = CACHE.lock().unwrap();
+
+            cache.retain(|_, (read_at, _)| read_at.elapsed() < CACHE_TTL);
+            cache.insert(guild_id, (Instant::now(), settings.clone()));
+
+            settings
+        },
+        Err(err) => {
+            error!("The guild settings could not have been read: {:?}", err);
+
+            GuildSettings::default()
+        },
+    }
+}
+
+/// Forgets the cached settings of the guild once they have been saved, so its next responses show the change.
+pub fn forget(guild_id: GuildId) {
+    CACHE.lock().unwrap().remove(&guild_id.0);
+}
+
+/// Parses a hex color code such as `#8CBEDA`, `8cbeda` or `0x8CBEDA`.
+pub fn parse_color(input: &str) -> Option<u32> {
+    let input = input.trim();
+    let hex = input.strip_prefix('#')
+        .or_else(|| input.strip_prefix("0x"))
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    u32::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_are_parsed() {
+        for input in ["#8CBEDA", "8cbeda", "0x8CBEDA", "0X8cbeda", " #8cBeDa "] {
+            assert_eq!(parse_color(input), Some(0x8CBEDA), "{:?}", input);
+        }
+
+        assert_eq!(parse_color("#000000"), Some(0));
+        assert_eq!(parse_color("FFFFFF"), Some(0xFFFFFF));
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        for input in [
+            "", "#", "0x", "#FFF", "8cbed", "#1000000", "0x1000000", "FFFFFFFF",
+            "##8CBEDA", "#0x8CBEDA", "#8CBEDG", "+8CBEDA", "-8CBEDA", "8C BEDA", "#8CBEDä", "blue",
+        ] {
+            assert_eq!(parse_color(input), None, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! The titles players unlock by reaching milestones and can wear next to their name in game messages.
+
+use serenity::model::user::User;
+use serenity::prelude::Mentionable;
+
+use tracing::error;
+
+use crate::i18n::tr;
+use crate::storage::{self, Record};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Title {
+    Rookie,
+    Contender,
+    Veteran,
+    Unstoppable,
+    Champion,
+}
+
+impl Title {
+    pub const ALL: [Self; 5] = [Self::Rookie, Self::Contender, Self::Veteran, Self::Unstoppable, Self::Champion];
+
+    pub fn from_setting(setting: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|title| title.as_setting() == setting)
+    }
+
+    pub fn as_setting(self) -> &'static str {
+        match self {
+            Self::Rookie => "rookie",
+            Self::Contender => "contender",
+            Self::Veteran => "veteran",
+            Self::Unstoppable => "unstoppable",
+            Self::Champion => "champion",
+        }
+    }
+
+    /// Whether the global record reaches the milestone; a streak only has to be running when the title is equipped.
+    pub fn is_unlocked(self, record: &Record) -> bool {
+        match self {
+            Self::Rookie => record.wins >= 1,
+            Self::Contender => record.wins >= 10,
+            Self::Veteran => record.wins + record.losses >= 50,
+            Self::Unstoppable => record.streak >= 5,
+            Self::Champion => record.wins >= 100,
+        }
+    }
+
+    pub fn name(self, locale: &str) -> String {
+        tr!(locale, &format!("titles.{}", self.as_setting()))
+    }
+
+    pub fn requirement(self, locale: &str) -> String {
+        tr!(locale, &format!("titles.{}.requirement", self.as_setting()))
+    }
+}
+
+/// Mentions the user along with the title they wear, if any.
+pub fn decorated(user: &User, locale: &str) -> String {
+    let title = match storage::user_preferences(user.id.0) {
+        Ok(preferences) => preferences.title.as_deref().and_then(Title::from_setting),
+        Err(err) => {
+            error!("The user preferences could not have been read: {:?}", err);
+
+            None
+        },
+    };
+
+    match title {
+        Some(title) => tr!(locale, "titles.decorated", user = user.mention(), title = title.name(locale)),
+        None => user.mention().to_string(),
+    }
+}
@@ -0,0 +1,139 @@
+//! The calls to the Discord REST API the game messages go through: answering a press, editing the message through
+//! the token of its interaction or as a regular message, and posting it again. The bot makes them with its [`Http`]
+//! client, whereas the tests play whole games against a [`Mock`] recording them, with failures on demand.
+
+use std::future::Future;
+
+use serenity::http::Http;
+use serenity::json::Value;
+use serenity::model::id::{ChannelId, InteractionId, MessageId};
+
+pub trait Transport: Sync {
+    fn respond(&self, interaction_id: InteractionId, token: &str, response: &Value) -> impl Future<Output = serenity::Result<()>> + Send;
+
+    fn edit_response(&self, token: &str, message: &Value) -> impl Future<Output = serenity::Result<()>> + Send;
+
+    fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, message: &Value) -> impl Future<Output = serenity::Result<()>> + Send;
+
+    fn send_message(&self, channel_id: ChannelId, message: &Value) -> impl Future<Output = serenity::Result<MessageId>> + Send;
+}
+
+impl Transport for Http {
+    async fn respond(&self, interaction_id: InteractionId, token: &str, response: &Value) -> serenity::Result<()> {
+        self.create_interaction_response(interaction_id.0, token, response).await
+    }
+
+    async fn edit_response(&self, token: &str, message: &Value) -> serenity::Result<()> {
+        self.edit_original_interaction_response(token, message).await.map(|_| ())
+    }
+
+    async fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, message: &Value) -> serenity::Result<()> {
+        Http::edit_message(self, channel_id.0, message_id.0, message).await.map(|_| ())
+    }
+
+    async fn send_message(&self, channel_id: ChannelId, message: &Value) -> serenity::Result<MessageId> {
+        Http::send_message(self, channel_id.0, message).await.map(|message| message.id)
+    }
+}
+
+#[cfg(test)]
+pub use mock::{Call, Mock};
+
+#[cfg(test)]
+mod mock {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// A call made to the [`Mock`], with the JSON body Discord would have been sent.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Call {
+        Respond { token: String, response: Value },
+        EditResponse { token: String, message: Value },
+        EditMessage { message_id: MessageId, message: Value },
+        SendMessage { channel_id: ChannelId, message: Value },
+    }
+
+    impl Call {
+        pub fn kind(&self) -> &'static str {
+            match self {
+                Call::Respond { .. } => "respond",
+                Call::EditResponse { .. } => "edit_response",
+                Call::EditMessage { .. } => "edit_message",
+                Call::SendMessage { .. } => "send_message",
+            }
+        }
+
+        /// The message the call has sent, whichever way it has been sent.
+        pub fn message(&self) -> &Value {
+            match self {
+                Call::Respond { response, .. } => &response["data"],
+                Call::EditResponse { message, .. } | Call::EditMessage { message, .. } | Call::SendMessage { message, .. } => message,
+            }
+        }
+    }
+
+    /// Records every call, failing the next ones of a kind as many times as asked with [`Mock::fail`].
+    #[derive(Default)]
+    pub struct Mock {
+        calls: Mutex<Vec<Call>>,
+        failures: Mutex<HashMap<&'static str, usize>>,
+        next_message_id: Mutex<u64>,
+    }
+
+    impl Mock {
+        pub fn fail(&self, kind: &'static str, times: usize) {
+            *self.failures.lock().unwrap().entry(kind).or_default() += times;
+        }
+
+        pub fn calls(&self) -> Vec<Call> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// The kinds of the calls in the order they have been made, including the failed ones.
+        pub fn kinds(&self) -> Vec<&'static str> {
+            self.calls().iter().map(Call::kind).collect()
+        }
+
+        /// Records the call, which fails while failures of its kind are due.
+        fn record(&self, call: Call) -> Result<(), &'static str> {
+            let kind = call.kind();
+
+            self.calls.lock().unwrap().push(call);
+
+            match self.failures.lock().unwrap().get_mut(kind) {
+                Some(remaining) if *remaining > 0 => {
+                    *remaining -= 1;
+
+                    Err("mocked failure")
+                },
+                _ => Ok(()),
+            }
+        }
+    }
+
+    impl Transport for Mock {
+        async fn respond(&self, _: InteractionId, token: &str, response: &Value) -> serenity::Result<()> {
+            self.record(Call::Respond { token: token.to_string(), response: response.clone() }).map_err(serenity::Error::Other)
+        }
+
+        async fn edit_response(&self, token: &str, message: &Value) -> serenity::Result<()> {
+            self.record(Call::EditResponse { token: token.to_string(), message: message.clone() }).map_err(serenity::Error::Other)
+        }
+
+        async fn edit_message(&self, _: ChannelId, message_id: MessageId, message: &Value) -> serenity::Result<()> {
+            self.record(Call::EditMessage { message_id, message: message.clone() }).map_err(serenity::Error::Other)
+        }
+
+        async fn send_message(&self, channel_id: ChannelId, message: &Value) -> serenity::Result<MessageId> {
+            self.record(Call::SendMessage { channel_id, message: message.clone() }).map_err(serenity::Error::Other)?;
+
+            let mut next_message_id = self.next_message_id.lock().unwrap();
+
+            *next_message_id += 1;
+
+            Ok(MessageId(*next_message_id))
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Receives the votes for the bot on top.gg, which are sent as webhooks to the HTTP server.
+//! Only compiled with the `topgg` feature, and only active once `topgg.webhook_secret` is set.
+
+use std::time::Duration;
+
+use hyper::{Body, Request, Response, StatusCode};
+
+use serde::Deserialize;
+
+use serenity::model::id::ApplicationId;
+use serenity::model::Timestamp;
+
+use tracing::{error, info, warn};
+
+use crate::admin;
+use crate::config;
+use crate::storage;
+
+/// The path of the HTTP server the webhooks are received at.
+pub const PATH: &str = "/topgg/votes";
+
+/// How long top.gg makes a user wait between two votes.
+pub const COOLDOWN: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// The payload of a vote webhook; the other fields are not needed.
+#[derive(Deserialize)]
+struct Vote {
+    /// The ID of the voter, as a string.
+    user: String,
+    /// `upvote` for actual votes, `test` for the ones sent from the webhook settings.
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Whether the votes are received and shown.
+pub fn enabled() -> bool {
+    config::get().topgg.webhook_secret.is_some()
+}
+
+/// The page of the bot on top.gg the users vote on.
+pub fn url(application_id: ApplicationId) -> String {
+    format!("https://top.gg/bot/{}/vote", application_id)
+}
+
+/// Records the vote of the webhook, once it has been authorized with the configured secret.
+pub async fn handle_webhook(request: Request<Body>) -> hyper::http::Result<Response<Body>> {
+    let secret = config::get().topgg.webhook_secret;
+
+    let authorized = request.headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .zip(secret.as_deref())
+        .is_some_and(|(authorization, secret)| admin::matches_secret(authorization, secret));
+
+    if !authorized {
+        return Response::builder().status(StatusCode::UNAUTHORIZED).body(Body::empty());
+    }
+
+    let vote = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => serde_json::from_slice::<Vote>(&body).ok(),
+        Err(err) => {
+            warn!("The body of a vote webhook could not have been read: {:?}", err);
+
+            None
+        },
+    };
+
+    let (user_id, kind) = match vote.and_then(|vote| Some((vote.user.parse::<u64>().ok()?, vote.kind))) {
+        Some(vote) => vote,
+        None => return Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()),
+    };
+
+    if kind == "test" {
+        info!(user = user_id, "A test vote has been received");
+    } else if let Err(err) = storage::save_vote(user_id, Timestamp::now().unix_timestamp()) {
+        error!("The vote could not have been saved: {:?}", err);
+
+        // top.gg retries the webhooks that fail
+        return Response::builder().status(StatusCode::INTERNAL_SERVER_ERROR).body(Body::empty());
+    } else {
+        info!(user = user_id, "A vote has been received");
+    }
+
+    Response::builder().status(StatusCode::NO_CONTENT).body(Body::empty())
+}